@@ -3,6 +3,10 @@
 -- Allows intercepting and overriding clipboard read, write, and clear actions,
 -- enabling custom behavior or filtering of clipboard text.
 -- Useful for implementing custom clipboard logic.
+--
+-- Clipboard contents can come from the system clipboard, an in-process
+-- clipboard shared by all views, or `love.system`, see `setMode`.
+-- Views can be restricted further with `View:setClipboardPolicy`.
 -- @module ultralight.clipboard
 
 local clipboard = {}
@@ -12,17 +16,23 @@ local clipboard = {}
 -- If the callback returns a string, it will be used as clipboard content.
 -- If it returns `nil`, the system clipboard will be read.
 -- If it returns `false`, the operation will be suppressed entirely.
+-- Runs after the view's own `View:onPaste` handler. Errors cancel it like `false`,
+-- and are reported to the logger.
 -- @function onGetText
 -- @tparam function callback A function returning text (`string`), `nil`, or `false`.
+-- @tparam View|nil callback.view The view pasting: the one handling input, or else the
+-- last one given focus with `View:focus`. `nil` if there is none.
 -- @treturn string|nil|false The text to use, `nil` to use system clipboard, or `false` to cancel.
 function ultralight.clipboard.onGetText(callback) end
 
 --- Registers a callback for writing clipboard text.
 -- If the callback returns `true` or `nil`, the system clipboard will be updated with the provided text.
 -- If it returns `false`, the operation will be suppressed.
+-- Runs after the view's own `View:onCopy` or `View:onCut` handler.
 -- @function onSetText
 -- @tparam function callback A function receiving the text to be written.
 -- @tparam string callback.text The text to write to the clipboard.
+-- @tparam View|nil callback.view The view copying, see `onGetText`.
 -- @treturn boolean|nil|false `true`/`nil` to proceed, `false` to cancel.
 function clipboard.onSetText(callback) end
 
@@ -31,7 +41,63 @@ function clipboard.onSetText(callback) end
 -- If it returns `false`, the operation will be suppressed.
 -- @function onClearClipboard
 -- @tparam function callback A function called when the clipboard is about to be cleared.
+-- @tparam View|nil callback.view The view clearing, see `onGetText`.
 -- @treturn boolean|nil|false `true`/`nil` to proceed, `false` to cancel.
 function clipboard.onClear(callback) end
 
+--- Chooses where clipboard contents are kept.
+--
+-- * `"system"` (default): the OS clipboard. Falls back to `love.system` on
+--   platforms without one.
+-- * `"memory"`: a clipboard inside the game, shared by all views and never seen
+--   by other programs. The only mode that keeps formats other than text.
+-- * `"love"`: `love.system.getClipboardText` and `setClipboardText`.
+--
+-- Switching modes doesn't carry the contents over.
+-- @function setMode
+-- @tparam string mode `"system"`, `"memory"` or `"love"`.
+function clipboard.setMode(mode) end
+
+--- Returns the current clipboard mode.
+-- @function getMode
+-- @treturn string `"system"`, `"memory"` or `"love"`.
+function clipboard.getMode() end
+
+--- Reads the clipboard text.
+-- Unlike pages, Lua is not bound by view clipboard policies or `onGetText`.
+-- @function getText
+-- @treturn string|nil The text, or `nil` if the clipboard can't be read.
+function clipboard.getText() end
+
+--- Replaces the clipboard contents with text.
+-- @function setText
+-- @tparam string text The text to copy.
+function clipboard.setText(text) end
+
+--- Reads one format of the clipboard.
+-- Formats other than `"text/plain"` need the `"memory"` mode.
+-- @function getData
+-- @tparam string format A MIME type, such as `"text/html"` or `"image/png"`.
+-- @treturn string|nil The data, or `nil` if the clipboard doesn't hold this format.
+-- @raise if the format isn't supported by the current mode.
+function clipboard.getData(format) end
+
+--- Stores data in one format, keeping the other formats on the clipboard.
+-- Formats other than `"text/plain"` need the `"memory"` mode. When a page
+-- copies, the clipboard is replaced with its `"text/plain"` text.
+-- @function setData
+-- @tparam string format A MIME type, such as `"text/html"` or `"image/png"`.
+-- @tparam string|Data|nil data The data, as a string or a love `Data` object. `nil` removes the format.
+-- @raise if the format isn't supported by the current mode.
+-- @usage
+-- ultralight.clipboard.setMode("memory")
+-- ultralight.clipboard.setData("text/plain", "Sword of Dawn")
+-- ultralight.clipboard.setData("text/html", "<b>Sword of Dawn</b>")
+function clipboard.setData(format, data) end
+
+--- Lists the formats on the clipboard.
+-- @function getFormats
+-- @treturn {string,...} MIME types, such as `{ "text/html", "text/plain" }`.
+function clipboard.getFormats() end
+
 return clipboard
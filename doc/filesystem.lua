@@ -1,7 +1,23 @@
 --- Filesystem submodule for Ultralight.
 -- Provides callbacks to handle file operations for local resources.
 -- This submodule is used to implement support for the `file://` protocol,
--- allowing Ultralight to access local files via registered Lua callbacks.
+-- allowing Ultralight to access local files via registered Lua callbacks,
+-- or directly through the built-in `love.filesystem` provider.
+--
+-- Ultralight loads files on its own thread. Requests meant for Lua callbacks wait until
+-- the next call to `ultralight.update`, `ultralight.draw` or `ultralight.quit`, and fail
+-- after a timeout (2 seconds by default, see `setTimeout`). Added files, mounts and the
+-- built-in provider never wait for Lua.
+--
+-- Without a callback, MIME types are guessed from the file extension (html, css, js, json,
+-- svg, png, jpg, webp, gif, fonts, audio, wasm and more), or from the file contents when
+-- the extension is unknown. Charsets are read from byte order marks, `<meta charset>` and
+-- `http-equiv` tags in HTML, and an `@charset` rule opening a CSS file, and default to
+-- `"utf-8"`. Contents are only inspected for files served without Lua.
+--
+-- Requested paths are cleaned up before they reach a callback or a built-in backend: query
+-- strings and fragments are removed, percent-escapes decoded, `\` turned into `/` and
+-- `.` and `..` resolved. Paths climbing above the root are refused, see `setSandbox`.
 -- @module ultralight.filesystem
 
 local filesystem = {}
@@ -20,11 +36,12 @@ function filesystem.onFileExists(callback) end
 
 --- Registers a callback to determine the MIME type of a file.
 -- The callback receives the file path and should return a string representing the MIME type.
--- If the callback returns `nil`, the `"application/unknown"` MIME type will be used.
+-- If the callback returns `nil`, the MIME type is detected automatically.
+-- Files added with `addFile` or `mount` don't go through the callback.
 -- @function onGetFileMimeType
 -- @tparam function callback A function receiving the file path and returning a MIME type string or `nil`.
 -- @tparam string callback.path The file path to check.
--- @treturn string The MIME type of the file (defaults to `"application/unknown"` if callback returns `nil`).
+-- @treturn string|nil The MIME type of the file, or `nil` to detect it automatically.
 -- @usage
 -- ultralight.filesystem.onGetFileMimeType(function(path)
 --   if path:match("%.html$") then
@@ -35,11 +52,12 @@ function filesystem.onGetFileMimeType(callback) end
 
 --- Registers a callback to determine the character set of a file.
 -- The callback receives the file path and should return a charset string (e.g., `"utf-8"`, `"iso-8859-1"`).
--- If the callback returns `nil`, the default charset `"utf-8"` will be used.
+-- If the callback returns `nil`, the charset is detected automatically.
+-- Files added with `addFile` or `mount` don't go through the callback.
 -- @function onGetFileCharset
 -- @tparam function callback A function receiving the file path and returning a charset string or `nil`.
 -- @tparam string callback.path The file path to check.
--- @treturn string|nil The charset name (defaults to `"utf-8"` if callback returns `nil`).
+-- @treturn string|nil The charset name, or `nil` to detect it automatically.
 -- @usage
 -- ultralight.filesystem.onGetFileCharset(function(path)
 --   if path:match("%.txt$") then
@@ -61,4 +79,134 @@ function filesystem.onGetFileCharset(callback) end
 -- end)
 function filesystem.onOpenFile(callback) end
 
+--- Serves local files straight from the game's files.
+-- Files are looked up the way `love.filesystem` sees them: the save directory first,
+-- then the game source, which may be a directory, a `.love` file or a fused executable.
+-- Requests are answered without calling into Lua, so pages load even while Lua is busy.
+-- Callbacks registered with the functions above still take precedence over the built-in provider.
+-- MIME types and charsets are detected automatically.
+-- @function useLoveFilesystem
+-- @tparam[opt] table|boolean options Options table, or `false` to turn the provider off again.
+-- @tparam[opt=""] string options.root Directory that `file:///` URLs are resolved against.
+-- @usage
+-- ultralight.filesystem.useLoveFilesystem({ root = "ui/" })
+-- view:loadURL("file:///index.html") -- loads ui/index.html
+function filesystem.useLoveFilesystem(options) end
+
+--- Sets how long a file request may wait for the Lua callbacks.
+-- When the timeout expires, the file is reported as missing.
+-- @function setTimeout
+-- @tparam number seconds Time to wait, in seconds. Defaults to `2`.
+-- @usage
+-- ultralight.filesystem.setTimeout(0.5)
+function filesystem.setTimeout(seconds) end
+
+--- Restricts which files pages may request.
+-- The sandbox is checked before any callback, added file, mount or the built-in provider
+-- sees the request. Requested paths are not changed by it: `root` only limits which paths
+-- may be requested, before the root of `useLoveFilesystem` is applied.
+-- Requests leaving the `file:///` root, outside `root`, or for extensions not in
+-- `extensions`, are refused and logged as warnings (see `ultralight.setLogger`), once per path.
+-- @function setSandbox
+-- @tparam[opt] table options Sandbox options. Restores the defaults if omitted.
+-- @tparam[opt=""] string options.root Directory that requested paths must be inside of.
+-- @tparam[opt] {string,...} options.extensions File extensions that may be requested,
+-- like `{ "html", "css", "js" }`. All extensions are allowed if omitted.
+-- @tparam[opt=true] boolean options.logDenied Whether to report refused requests.
+-- @usage
+-- ultralight.filesystem.useLoveFilesystem()
+-- ultralight.filesystem.setSandbox({
+--   root = "ui",
+--   extensions = { "html", "css", "js", "png", "woff2" },
+-- })
+-- view:loadURL("file:///ui/index.html") -- file:///main.lua can't be read
+function filesystem.setSandbox(options) end
+
+--- Stores a file in memory, so it is served without calling into Lua.
+-- Added files take precedence over mounts, the callbacks and the built-in provider.
+-- Their MIME type and charset are detected automatically.
+-- @function addFile
+-- @tparam string path The path the page will request, like `"report.html"`.
+-- @tparam string|Data data The file content, as a string or a love `Data` object.
+-- @usage
+-- ultralight.filesystem.addFile("report.html", buildReport())
+-- view:loadURL("file:///report.html")
+function filesystem.addFile(path, data) end
+
+--- Removes a file stored with `addFile`.
+-- @function removeFile
+-- @tparam string path The path given to `addFile`.
+-- @treturn boolean `true` if the file was removed.
+function filesystem.removeFile(path) end
+
+--- Alias of `addFile`, kept for older code.
+-- @function preload
+-- @tparam string path The path the page will request.
+-- @tparam string|Data data The file content, as a string or a love `Data` object.
+-- @see addFile
+function filesystem.preload(path, data) end
+
+--- Removes a file stored with `addFile` or `preload`, like `removeFile`.
+-- Kept for older code.
+-- @function unload
+-- @tparam[opt] string path The path given to `preload`. Removes all stored files if omitted.
+-- @see removeFile
+function filesystem.unload(path) end
+
+--- Makes a set of files visible under a path prefix.
+-- The source can be a directory or archive on disk, a table mapping paths to contents,
+-- or a love `Data` object holding an archive. Archives are zip files or packs made with
+-- `createPack`, and may be encrypted. Mounting at a prefix that is already mounted
+-- replaces it, and the latest mounts are searched first.
+-- Mounted files are served without calling into Lua, and files read from archives are
+-- kept in a small cache so they are not decompressed again.
+-- Packs whose index points past the end of the archive fail to mount, and files larger
+-- than 256 MiB are not read from archives.
+-- @function mount
+-- @tparam string prefix The path prefix, like `"themes"`. Use `""` to mount at the root.
+-- @tparam string|table|Data source The files to mount.
+-- @tparam[opt] table options Options for archives.
+-- @tparam[opt] string options.format `"zip"` or `"pack"`. Detected from the archive if omitted.
+-- @tparam[opt] string options.cipher `"xor"` or `"aes-ctr"`, to decrypt the archive once when mounting.
+-- @tparam[opt] string options.key The cipher key. AES keys are 16, 24 or 32 bytes long.
+-- @tparam[opt] string options.iv The 16 byte initial counter for `"aes-ctr"`.
+-- @tparam[opt] function options.decrypt A function receiving the archive as a string and
+-- returning it decrypted, used instead of `cipher`. It is called once, when mounting.
+-- @tparam[opt=32] number options.cacheSize How many files to keep cached. `0` disables the cache.
+-- @usage
+-- ultralight.filesystem.mount("themes", {
+--   ["dark.css"] = "body { background: #111; color: #eee; }",
+--   ["light.css"] = "body { background: #fff; color: #111; }",
+-- })
+-- ultralight.filesystem.mount("docs", love.filesystem.newFileData("docs.zip"))
+-- ultralight.filesystem.mount("", love.filesystem.newFileData("ui.pack"), {
+--   cipher = "aes-ctr", key = UI_KEY, iv = UI_IV,
+-- })
+function filesystem.mount(prefix, source, options) end
+
+--- Removes a mount created with `mount`.
+-- @function unmount
+-- @tparam string prefix The prefix given to `mount`.
+-- @treturn boolean `true` if a mount was removed.
+function filesystem.unmount(prefix) end
+
+--- Builds a pack archive, to be loaded later with `mount`.
+-- Packs are a simple indexed format that is quick to open. The same cipher options given
+-- to `mount` encrypt the pack here.
+-- @function createPack
+-- @tparam table files A table mapping paths to contents, as strings or love `Data` objects.
+-- @tparam[opt] table options
+-- @tparam[opt=true] boolean options.compress Whether to compress the files.
+-- @tparam[opt] string options.cipher `"xor"` or `"aes-ctr"`.
+-- @tparam[opt] string options.key The cipher key.
+-- @tparam[opt] string options.iv The 16 byte initial counter for `"aes-ctr"`.
+-- @treturn string The pack contents.
+-- @usage
+-- local pack = ultralight.filesystem.createPack({
+--   ["index.html"] = love.filesystem.read("ui/index.html"),
+--   ["app.js"] = love.filesystem.read("ui/app.js"),
+-- }, { cipher = "xor", key = "not very secret" })
+-- love.filesystem.write("ui.pack", pack)
+function filesystem.createPack(files, options) end
+
 return filesystem
@@ -12,8 +12,26 @@ ultralight.version = "1.0.0"
 
 --- Creates a new Ultralight view.
 -- @function createView
+-- @tparam[opt] table options
+-- @tparam[opt=0] number options.display The display the view is shown on, see `setDisplayRate`.
+-- @tparam[opt=false] boolean options.accelerated Render the view on the GPU into a
+-- `Canvas`, see `View:getTexture`. Its frame buffer and `ImageData` are not available.
 -- @treturn UltralightView A new view instance.
-function ultralight.createView() end
+function ultralight.createView(options) end
+
+--- Creates a JavaScript context that is not attached to a view.
+-- @function newScriptContext
+-- @tparam[opt] table options Context options:
+-- @tparam[opt] string options.name Name of the context, shown in the inspector.
+-- @tparam[opt=false] boolean options.inspectable Whether the context can be inspected.
+-- @tparam[opt] ScriptContext options.group Another script context to share objects with.
+-- Both contexts are then part of the same group, so JavaScript objects can be passed between them.
+-- @treturn ScriptContext A new script context.
+-- @usage
+-- local i18n = ultralight.newScriptContext({ name = "i18n" })
+-- i18n:evaluate(love.filesystem.read("lib/i18n.js"))
+-- local text = i18n:call("i18n.format", "coins", { count = 3 })
+function ultralight.newScriptContext(options) end
 
 --- Updates logic for all views.
 -- Should be called from `love.update`.
@@ -22,9 +40,181 @@ function ultralight.update() end
 
 --- Renders all views.
 -- Should be called from `love.draw`.
+-- Also refreshes the displays that are due, which advances animations of
+-- their views. Display 0 is refreshed on every call.
 -- @function draw
+-- @see setDisplayRate
 function ultralight.draw() end
 
+--- Sets how often `draw` refreshes a display.
+-- Views on a display animate, and run `requestAnimationFrame` callbacks, each
+-- time it is refreshed. Rates are measured with `love.timer`.
+-- Display ids are yours to choose; put views on them with `View:setDisplay`.
+-- The highest id, `4294967295`, is kept for suspended views and raises an error.
+-- @function setDisplayRate
+-- @tparam number display The display id.
+-- @tparam number|nil rate Refreshes per second, `0` for every frame, or `nil` to
+-- only refresh it with `refreshDisplay`.
+-- @usage
+-- local billboard = ultralight.createView({ display = 1 })
+-- ultralight.setDisplayRate(1, 30) -- the in-world screen animates at 30 Hz
+function ultralight.setDisplayRate(display, rate) end
+
+--- Refreshes a display right away.
+-- Useful for displays left out of `draw` with `setDisplayRate(id, nil)`.
+-- Refreshing the display of suspended views raises an error.
+-- @function refreshDisplay
+-- @tparam number display The display id.
+function ultralight.refreshDisplay(display) end
+
+--- Wraps binary data so it is passed to JavaScript as a `Uint8Array`.
+-- Lua strings are always converted to JavaScript strings, use this to send raw bytes instead
+-- (e.g. save files or generated images). love `Data` objects (`ByteData`, `FileData`, ...)
+-- are converted to `Uint8Array` automatically and can also be wrapped.
+-- @function bytes
+-- @tparam string|Data data The bytes to wrap.
+-- @treturn Bytes A wrapper that can be returned to JavaScript or passed to a JavaScript function.
+-- It has `getString()` and `getSize()` methods to read the bytes back.
+-- @usage
+-- webview:addFunction("game", "loadSave", function()
+--   return ultralight.bytes(love.filesystem.read("save.dat"))
+-- end)
+function ultralight.bytes(data) end
+
+--- Configures how values are converted between JavaScript and Lua.
+-- Only the given fields are changed.
+--
+-- Integers outside of JavaScript's safe integer range are passed to JavaScript as `BigInt`,
+-- and `BigInt`s are passed to Lua as integers, or as strings of digits if they do not fit.
+-- Converting a value that contains a cycle raises an error.
+-- `Error` objects are converted to a table with their `name`, `message` and `stack`.
+-- @function setConversionOptions
+-- @tparam table options Conversion options:
+-- @tparam[opt] string options.binary How typed arrays and `ArrayBuffer`s are represented in Lua:
+-- `"string"` (default) for a Lua string with the raw bytes, or `"bytedata"` for a love `ByteData` object.
+-- @tparam[opt] string options.null How `null` is represented in Lua:
+-- `"nil"` (default) converts both `null` and `undefined` to `nil`, and `nil` to `null`.
+-- `"sentinel"` converts `null` to `ultralight.null`, and `nil` to `undefined`.
+-- @tparam[opt] string options.date How `Date` objects are represented in Lua:
+-- `"timestamp"` (default) for seconds since the Unix epoch, as returned by `os.time`,
+-- or `"table"` for a UTC table in the format of `os.date("!*t")` with an extra `ms` field.
+-- Date tables are converted back to a `Date` when passed to JavaScript, see `ultralight.date`.
+-- @tparam[opt] number options.maxDepth Maximum nesting depth of converted tables and objects (default 64).
+-- @usage
+-- ultralight.setConversionOptions({ binary = "bytedata", null = "sentinel", date = "table" })
+function ultralight.setConversionOptions(options) end
+
+--- Receives the engine's log messages in Lua.
+-- Messages are collected while Ultralight works and handed to the callback during `update`.
+-- While no logger or log file is set, warnings and errors are printed to stderr.
+-- @function setLogger
+-- @tparam function|nil callback A function receiving `level`, `message` and `timestamp`,
+-- or `nil` to stop logging to Lua.
+-- @tparam string callback.level `"info"`, `"warning"` or `"error"`.
+-- @tparam string callback.message The message.
+-- @tparam number callback.timestamp When the message was logged, in seconds since the Unix epoch.
+-- @tparam[opt="info"] string level The least severe level passed to the callback.
+-- @usage
+-- ultralight.setLogger(function(level, message, timestamp)
+--   print(os.date("%H:%M:%S", timestamp), level, message)
+-- end, "warning")
+function ultralight.setLogger(callback, level) end
+
+--- Appends the engine's log messages to a file.
+-- Messages are written as soon as they are logged, one per line.
+-- @function setLogFile
+-- @tparam string|nil path Path of the log file, or `nil` to stop logging to a file.
+-- @tparam[opt="info"] string level The least severe level written to the file.
+-- @usage
+-- ultralight.setLogFile(love.filesystem.getSaveDirectory() .. "/ultralight.log")
+function ultralight.setLogFile(path, level) end
+
+--- Prints the console output of every page with Lua's `print`.
+-- Each line shows the level, the source file and line, and the message,
+-- like `[console error] file:///app.js:12: boom`.
+-- @function setConsoleMirror
+-- @tparam boolean enabled Whether to mirror console output.
+function ultralight.setConsoleMirror(enabled) end
+
+--- Frees as much memory as the engine can spare.
+-- Runs a JavaScript garbage collection in every view, then drops caches and
+-- the memory of views that don't need it. Call it after closing menus or on
+-- loading screens. Calling it from a view's callbacks or from JavaScript raises an error.
+-- @function purgeMemory
+-- @see setAutoPurge
+function ultralight.purgeMemory() end
+
+--- Reports the memory used by views and the engine.
+-- @function getMemoryStats
+-- @treturn table stats
+-- @treturn {table,...} stats.views One entry per view, oldest first, with `view`,
+-- `width`, `height` and `surfaceBytes`, the size of its pixel buffer.
+-- Views rendered on the GPU report 0.
+-- @treturn number stats.viewCount The number of views.
+-- @treturn number stats.surfaceBytes The size of all pixel buffers together.
+-- @treturn number stats.purgeCount How many times memory was purged.
+-- @treturn number stats.autoPurgeCount How many of those purges were automatic.
+-- @treturn number stats.lowMemoryCount How many `lowmemory` events were seen while automatic purging was on.
+-- @treturn number|nil stats.lastPurgeTime When memory was last purged, in seconds since the Unix epoch.
+-- @treturn boolean stats.autoPurge Whether automatic purging is on.
+-- @treturn {string,...} stats.report The engine's detailed breakdown, as lines of text.
+-- @usage
+-- local stats = ultralight.getMemoryStats()
+-- print(("%d views, %.1f MB of surfaces"):format(stats.viewCount, stats.surfaceBytes / 2^20))
+function ultralight.getMemoryStats() end
+
+--- Purges memory whenever the system runs low.
+-- Wraps `love.handlers.lowmemory`: love's handler and `love.lowmemory` still
+-- run first, then `purgeMemory` is called.
+-- @function setAutoPurge
+-- @tparam boolean enabled Whether to purge on `lowmemory`.
+function ultralight.setAutoPurge(enabled) end
+
+--- Creates a shader that draws pixels painted by views in the right colors.
+-- Views always paint in BGRA order. The shader swaps the channels on the GPU,
+-- so images made from `View:getImageData` need no conversion on the CPU.
+-- @function newSurfaceShader
+-- @treturn Shader The shader.
+-- @see View:getImageData
+function ultralight.newSurfaceShader() end
+
+--- Value representing JavaScript `null`.
+-- Passing it to JavaScript always gives `null`, and JavaScript `null` is converted to it
+-- when the `null` conversion option is set to `"sentinel"`.
+ultralight.null = nil
+
+--- Marks a table to be converted to a JavaScript array.
+-- By default, tables with only positive integer keys become arrays, as long as they are not too sparse.
+-- Missing items become `null` (or `undefined` with the `"sentinel"` null option).
+-- @function array
+-- @tparam table t The table to mark.
+-- @treturn table The same table.
+-- @usage
+-- view:addFunction("lua", "getTags", function() return ultralight.array({}) end)
+function ultralight.array(t) end
+
+--- Creates a value that is converted to a JavaScript `Date`.
+-- @function date
+-- @tparam number|table value Seconds since the Unix epoch, as returned by `os.time`,
+-- or a UTC table in the format of `os.date("!*t")`, with an optional `ms` field.
+-- Missing time fields default to 0, and out of range fields are normalized like `os.time` does.
+-- @treturn table A date table, the given table is marked and returned as is.
+-- @usage
+-- view:addFunction("game", "getSaveTime", function() return ultralight.date(saveTime) end)
+--
+-- -- JavaScript:
+-- -- game.getSaveTime().toLocaleString()
+function ultralight.date(value) end
+
+--- Marks a table to be converted to a JavaScript object, even if it looks like an array.
+-- Keys must be strings or numbers.
+-- @function object
+-- @tparam table t The table to mark.
+-- @treturn table The same table.
+-- @usage
+-- local scores = ultralight.object({ [1] = 10, [2] = 20 }) -- { "1": 10, "2": 20 }
+function ultralight.object(t) end
+
 --- Cleans up Ultralight resources before quitting.
 ---
 --- IMPORTANT: This function **must** be called from `love.quit()` when the application is closing.
@@ -0,0 +1,41 @@
+--- JavaScript context that is not attached to a view.
+--
+-- Script contexts run JavaScript without loading a page, which is useful to run
+-- pure JavaScript libraries (markdown renderers, i18n formatters, ...) from Lua.
+-- Values are converted the same way as for `UltralightView:addFunction`.
+--
+-- Created with `ultralight.newScriptContext`.
+-- @classmod ScriptContext
+
+local ScriptContext = {}
+
+--- Evaluates a JavaScript string in the context and returns the result.
+-- @function evaluate
+-- @tparam string script JavaScript code to execute.
+-- @tparam[opt] table options Evaluation options, see `UltralightView:evaluate`.
+-- @tparam[opt] string options.sourceUrl The URL or file name the script comes from.
+-- @tparam[opt=1] number options.startLine The line number of the first line of the script in that file.
+-- @return The result of the script, converted to a Lua value, or `nil` if an error occurred.
+-- @treturn string|nil An error message including the source location if execution failed, or `nil` if successful.
+-- @usage
+-- local js = ultralight.newScriptContext()
+-- js:evaluate(love.filesystem.read("lib/marked.min.js"))
+-- print(js:evaluate("1 + 2")) -- 3
+function ScriptContext:evaluate(script, options) end
+
+--- Calls a global JavaScript function and returns the result.
+-- @function call
+-- @tparam string name Name of the function, nested functions can be called with a dotted path
+-- like `"marked.parse"`, in which case `this` is the object the function belongs to.
+-- @param ... Arguments, converted to JavaScript values.
+-- @return The return value of the function, converted to a Lua value, or `nil` if an error occurred.
+-- @treturn string|nil An error message if the call failed, or `nil` if successful.
+-- @usage
+-- local html, err = js:call("marked.parse", "# Hello")
+function ScriptContext:call(name, ...) end
+
+--- Performs a JavaScript garbage collection.
+-- @function garbageCollect
+function ScriptContext:garbageCollect() end
+
+return ScriptContext
@@ -51,14 +51,124 @@ function View:setScale(scale) end
 -- This function retrieves the pixel data as a byte string, which can be used for image processing or rendering.
 -- See examples for how to use this data.
 --
+-- The pixels are only copied when something was painted since the last call.
+-- Otherwise, and while the View is hidden or suspended, the same string is returned again.
+--
+-- Raises an error for accelerated views, use `getTexture` instead.
+--
 -- @function getFrameBuffer
 -- @treturn string Raw pixel data as a byte string.
 -- @treturn number width The width of the framebuffer in pixels.
 -- @treturn number height The height of the framebuffer in pixels.
 function View:getFrameBuffer() end
 
+--- Returns the `Canvas` an accelerated View is rendered into.
+-- `ultralight.draw` renders the View on the GPU with love's graphics, into a
+-- Canvas that may be larger than the View; draw it with the returned quad.
+-- The pixels have premultiplied alpha.
+-- @function getTexture
+-- @treturn Canvas|nil The canvas, or `nil` when the View isn't accelerated or hasn't been rendered yet.
+-- @treturn Quad|nil The part of the canvas showing the View.
+-- @see isAccelerated
+-- @usage
+-- local view = ultralight.createView({ accelerated = true })
+--
+-- function love.draw()
+--   ultralight.draw()
+--   local canvas, quad = view:getTexture()
+--   if canvas then
+--     love.graphics.setBlendMode("alpha", "premultiplied")
+--     love.graphics.draw(canvas, quad)
+--     love.graphics.setBlendMode("alpha")
+--   end
+-- end
+function View:getTexture() end
+
+--- Checks whether the View is rendered on the GPU.
+-- @function isAccelerated
+-- @treturn boolean `true` when created with `accelerated = true`.
+function View:isAccelerated() end
+
+--- Returns an `ImageData` holding the View's pixels.
+-- The View paints into memory of its own, and the pixels are copied into the same
+-- `ImageData` each time something was painted. The `ImageData` reports the `"rgba8"`
+-- format but holds BGRA bytes, with premultiplied alpha, so draw it with the shader
+-- from `ultralight.newSurfaceShader` and the `"premultiplied"` alpha mode.
+--
+-- Resizing the View gives a new `ImageData`, call this again afterwards. Calling
+-- `release` on the `ImageData` makes the next call raise an error.
+-- @function getImageData
+-- @treturn ImageData|nil The pixels, or `nil` for accelerated Views, or when `love.image`
+-- isn't loaded.
+-- @treturn boolean changed Whether the pixels were updated since the last call.
+-- @usage
+-- local shader = ultralight.newSurfaceShader()
+-- local image
+--
+-- function love.draw()
+--   local data, changed = view:getImageData()
+--   if not image or image:getWidth() ~= data:getWidth() or image:getHeight() ~= data:getHeight() then
+--     image = love.graphics.newImage(data)
+--   elseif changed then
+--     image:replacePixels(data)
+--   end
+--   love.graphics.setShader(shader)
+--   love.graphics.setBlendMode("alpha", "premultiplied")
+--   love.graphics.draw(image)
+--   love.graphics.setBlendMode("alpha")
+--   love.graphics.setShader()
+-- end
+function View:getImageData() end
+
+--- Shows or hides the View.
+-- A hidden View isn't painted, but its page keeps running, with animations
+-- and timers, so it is up to date when shown again.
+-- @function setVisible
+-- @tparam boolean visible Whether the View is on screen.
+-- @see suspend
+function View:setVisible(visible) end
+
+--- Checks whether the View is shown.
+-- @function isVisible
+-- @treturn boolean `true` unless hidden with `setVisible(false)`.
+function View:isVisible() end
+
+--- Pauses the View while it is off screen.
+-- A suspended View isn't painted and its animations and
+-- `requestAnimationFrame` callbacks stop. Timers and network requests still run.
+-- Useful for menu tabs that aren't showing.
+-- @function suspend
+-- @see resume
+-- @usage
+-- for i, tab in ipairs(tabs) do
+--   if i == current then tab:resume() else tab:suspend() end
+-- end
+function View:suspend() end
+
+--- Undoes `suspend`, repainting the View.
+-- @function resume
+function View:resume() end
+
+--- Checks whether the View is suspended.
+-- @function isSuspended
+-- @treturn boolean `true` between `suspend` and `resume`.
+function View:isSuspended() end
+
+--- Moves the View to another display.
+-- The View animates whenever its display is refreshed, see `ultralight.setDisplayRate`.
+-- A suspended View moves when resumed.
+-- @function setDisplay
+-- @tparam number display The display id.
+function View:setDisplay(display) end
+
+--- Returns the display the View is on.
+-- @function getDisplay
+-- @treturn number The display id, `0` unless changed.
+function View:getDisplay() end
+
 --- Gives keyboard focus to the View.
 -- This sets visual focus (e.g., highlights selection) and allows the View to receive keyboard input.
+-- Clipboard access outside of input events is blamed on the View focused last.
 -- @function focus
 -- @see unfocus
 -- @see hasFocus
@@ -88,6 +198,56 @@ function View:hasFocus() end
 -- @see hasFocus
 function View:hasInputFocus() end
 
+--- Limits what pages in this View may do with the clipboard.
+-- The policy applies while the View handles mouse and keyboard input, which is
+-- when pages copy and paste. Outside of input, clipboard access must be
+-- allowed by every View's policy.
+-- Lua itself is not limited, see `ultralight.clipboard.getText`.
+-- @function setClipboardPolicy
+-- @tparam string policy `"allow"` (default), `"readOnly"`, `"writeOnly"` or `"deny"`.
+-- @usage store:setClipboardPolicy("writeOnly") -- the store can copy but never paste
+function View:setClipboardPolicy(policy) end
+
+--- Returns the View's clipboard policy.
+-- @function getClipboardPolicy
+-- @treturn string `"allow"`, `"readOnly"`, `"writeOnly"` or `"deny"`.
+-- @see setClipboardPolicy
+function View:getClipboardPolicy() end
+
+--- Registers a handler for text the View's pages copy.
+-- Return a string to copy that instead, `false` to keep the clipboard
+-- unchanged, or `nil` to copy the text as is. A handler raising an error denies
+-- the copy, and the error is reported to the logger (see `ultralight.setLogger`).
+-- The global `ultralight.clipboard.onSetText` callback runs afterwards.
+-- @function onCopy
+-- @tparam function callback A function receiving the copied text.
+-- @tparam string callback.text The text being copied.
+-- @treturn string|false|nil The text to copy, `false` to deny, or `nil` to allow.
+-- @usage view:onCopy(function(text) return text .. "\n-- copied from the wiki" end)
+function View:onCopy(callback) end
+
+--- Registers a handler for text the View's pages cut.
+-- Works like `onCopy`. Cuts are recognized by their shortcut, Ctrl/Cmd+X or
+-- Shift+Delete; cuts made otherwise reach `onCopy`.
+-- Denying a cut keeps the text off the clipboard, but the page still removes it.
+-- @function onCut
+-- @tparam function callback A function receiving the cut text.
+-- @tparam string callback.text The text being cut.
+-- @treturn string|false|nil The text to copy, `false` to deny, or `nil` to allow.
+-- @see onCopy
+function View:onCut(callback) end
+
+--- Registers a handler for the View's pages reading the clipboard.
+-- Return a string to paste that instead, `false` to paste nothing, or `nil` to
+-- read the clipboard. The global `ultralight.clipboard.onGetText` callback
+-- only runs when the handler returns `nil`. A handler raising an error denies
+-- the paste, and the error is reported to the logger.
+-- @function onPaste
+-- @tparam function callback A function called before the clipboard is read.
+-- @treturn string|false|nil The text to paste, `false` to deny, or `nil` to allow.
+-- @usage chat:onPaste(function() return false end)
+function View:onPaste(callback) end
+
 --- Loads a URL into the view.
 -- This will start loading and rendering the given web page.
 -- @function loadURL
@@ -267,13 +427,55 @@ function View:textInput(text, modifiers) end
 -- @treturn string|nil err An error message if execution failed, or `nil` if successful.
 function View:evaluateScript(script) end
 
+--- Evaluates JavaScript in the page and returns the result converted to a Lua value.
+--
+-- Unlike `evaluateScript`, the result is converted the same way as values passed to `addFunction` callbacks,
+-- and the script can be given a source URL and starting line. These are used in error messages,
+-- stack traces and console messages, so they point at the original file.
+--
+-- @function evaluate
+-- @tparam string script JavaScript code to execute.
+-- @tparam[opt] table options Evaluation options:
+-- @tparam[opt] string options.sourceUrl The URL or file name the script comes from.
+-- @tparam[opt=1] number options.startLine The line number of the first line of the script in that file.
+-- @return The result of the script, or `nil` if an error occurred.
+-- @treturn string|nil An error message including the source location, e.g. `"mods/hud.js:12: ReferenceError: Can't find variable: foo"`.
+-- @usage
+-- local result, err = view:evaluate(love.filesystem.read("mods/hud.js"), { sourceUrl = "mods/hud.js" })
+-- if err then print(err) end
+function View:evaluate(script, options) end
+
+--- Checks a JavaScript string for syntax errors without running it.
+--
+-- @function checkSyntax
+-- @tparam string script JavaScript code to check.
+-- @tparam[opt] string sourceUrl The URL or file name the script comes from.
+-- @treturn boolean `true` if the script is syntactically correct.
+-- @treturn string|nil The syntax error message.
+-- @treturn number|nil The line of the syntax error.
+-- @treturn number|nil The column of the syntax error.
+-- @usage
+-- local ok, message, line = view:checkSyntax(source, "mods/hud.js")
+-- if not ok then
+--   print(("mods/hud.js:%d: %s"):format(line, message))
+-- end
+function View:checkSyntax(script, sourceUrl) end
+
 --- Adds a Lua callback function accessible from JavaScript under a given namespace.
 -- If the namespace object does not exist in JavaScript, it will be created automatically.
 --
 -- When the JavaScript function is called, the provided Lua callback is invoked with
--- the JS arguments converted to Lua types (note: tables/objects are not supported).
+-- the JS arguments converted to Lua types.
 -- The Lua callback can return multiple values, which will be returned back to JavaScript as an array.
 --
+-- JavaScript functions passed as arguments are converted to Lua functions, so they can be
+-- stored and called later from Lua. Their arguments and return value are converted the same way.
+-- Calling such a function after the page that created it was unloaded raises an error.
+--
+-- Lua functions returned to JavaScript, including functions nested inside returned tables,
+-- are converted to JavaScript functions. They stay callable for as long as JavaScript keeps
+-- a reference to them.
+--
 -- It is recommended to call this method inside the `onDocumentReady` callback
 -- to ensure the JavaScript environment is fully initialized before adding functions.
 --
@@ -282,7 +484,6 @@ function View:evaluateScript(script) end
 -- @tparam string name The name of the function inside the namespace.
 -- @tparam function callback The Lua callback to be called from JS.
 --   Receives the JS call arguments as Lua values (`...`), and can return values back to JS.
---   Tables/objects are not supported in arguments or return values.
 -- @see onDocumentReady
 --
 -- @usage
@@ -301,8 +502,99 @@ function View:evaluateScript(script) end
 -- -- JavaScript:
 -- -- let result = myNamespace.greet('World')
 -- -- console.log(result) // Outputs: Hello, World
+--
+-- @usage
+-- local onProgress
+-- webview:addFunction("game", "loadLevel", function(id, callback)
+--   onProgress = callback
+--   startLoadingLevel(id)
+-- end)
+--
+-- -- later, e.g. in love.update:
+-- onProgress(0.5)
+--
+-- -- JavaScript:
+-- -- game.loadLevel(3, (progress) => console.log(progress))
+--
+-- @usage
+-- webview:addFunction("game", "getApi", function()
+--   return {
+--     getScore = function() return score end,
+--     addScore = function(points) score = score + points end,
+--   }
+-- end)
+--
+-- -- JavaScript:
+-- -- const api = game.getApi()
+-- -- api.addScore(10)
+-- -- console.log(api.getScore())
 function View:addFunction(namespace, name, callback) end
 
+--- Adds an asynchronous Lua callback accessible from JavaScript under a given namespace.
+--
+-- Works like `addFunction`, but the JavaScript function returns a `Promise`.
+-- The Lua callback receives a `resolve` and a `reject` function before the JS arguments,
+-- and can call one of them at any later time, for example once a coroutine
+-- or a `love.thread` job finished. Only the first call settles the promise.
+--
+-- If the callback raises an error before settling the promise, the promise is rejected with that error.
+-- Calls to `resolve` or `reject` made after the page was unloaded are ignored.
+--
+-- @function addAsyncFunction
+-- @tparam string namespace The JS object name under which the function will be added.
+-- @tparam string name The name of the function inside the namespace.
+-- @tparam function callback The Lua callback, called as `callback(resolve, reject, ...)`.
+-- @tparam function callback.resolve Resolves the promise with the given value.
+-- @tparam function callback.reject Rejects the promise with the given value.
+-- @see addFunction
+--
+-- @usage
+-- local pendingSaves = {}
+--
+-- webview:addAsyncFunction("game", "loadSave", function(resolve, reject, slot)
+--   table.insert(pendingSaves, { slot = slot, resolve = resolve, reject = reject })
+-- end)
+--
+-- -- later, e.g. in love.update once the save was read:
+-- local save = table.remove(pendingSaves, 1)
+-- save.resolve({ level = 3, coins = 120 })
+--
+-- -- JavaScript:
+-- -- const save = await game.loadSave(1)
+-- -- console.log(save.level) // 3
+function View:addAsyncFunction(namespace, name, callback) end
+
+--- Exposes a Lua table to JavaScript as a global object.
+--
+-- Unlike values passed through `addFunction`, the table is not copied: reading a property
+-- from JavaScript reads the current value from the Lua table, and assigning or deleting a property
+-- changes the Lua table. Nested tables are exposed the same way, so changes made on either side
+-- are always visible on the other. `Object.keys` and `for...in` list the table's string and integer keys.
+-- A page always sees the same object for the same table, so `game.player === game.player`.
+--
+-- Passing an exposed table (or a nested one) back to Lua through a function gives the original table.
+--
+-- Like `addFunction`, the object is added to the current page only, so it is recommended
+-- to call this method inside the `onDocumentReady` callback.
+--
+-- @function exposeObject
+-- @tparam string name The name of the global JavaScript object.
+-- @tparam table table The Lua table to expose.
+-- @see addFunction
+--
+-- @usage
+-- local game = { player = { name = "Hero", hp = 100 } }
+--
+-- webview:onDocumentReady(function(isMainFrame)
+--   if not isMainFrame then return end
+--   webview:exposeObject("game", game)
+-- end)
+--
+-- -- JavaScript:
+-- -- console.log(game.player.hp) // 100
+-- -- game.player.hp -= 10        // game.player.hp is now 90 in Lua too
+function View:exposeObject(name, table) end
+
 --- Registers a callback fired when a document is ready.
 --
 -- This callback is useful for initializing JavaScript functions or performing actions
@@ -409,37 +701,47 @@ function View:onChangeTooltip(callback) end
 --
 -- @function onConsoleMessage
 -- @tparam function callback Called with the following arguments:
--- @tparam number callback.messageSource The source of the message
--- <pre>
---   0 = XML
---   1 = JS
---   2 = Network
---   3 = ConsoleAPI
---   4 = Storage
---   5 = AppCache
---   6 = Rendering
---   7 = CSS
---   8 = Security
---   9 = ContentBlocker
---   10 = Media
---   11 = MediaSource
---   12 = WebRTC
---   13 = ITPDebug
---   14 = PrivateClickMeasurement
---   15 = PaymentRequest
---   16 = Other </pre>
--- @tparam number callback.messageLevel The level of the message.
--- <pre>
---   0 = Log
---   1 = Warning
---   2 = Error
---   3 = Debug
---   4 = Info </pre>
---
+-- @tparam string callback.source Where the message comes from: `"xml"`, `"javascript"`,
+-- `"network"`, `"console-api"`, `"storage"`, `"app-cache"`, `"rendering"`, `"css"`,
+-- `"security"`, `"content-blocker"`, `"media"`, `"media-source"`, `"webrtc"`,
+-- `"itp-debug"`, `"private-click-measurement"`, `"payment-request"` or `"other"`.
+-- @tparam string callback.level The level of the message: `"log"`, `"warning"`, `"error"`,
+-- `"debug"` or `"info"`.
 -- @tparam string callback.message The console message text.
 -- @tparam number callback.lineNumber The line number where the message originated.
 -- @tparam number callback.columnNumber The column number where the message originated.
 -- @tparam string callback.sourceId The source ID of the message.
+-- @usage
+-- view:onConsoleMessage(function(source, level, message, line, column, sourceId)
+--   if level == "error" then
+--     print(("%s:%d: %s"):format(sourceId, line, message))
+--   end
+-- end)
 function View:onConsoleMessage(callback) end
 
+--- Keeps the last console messages of the view, to be read with `getConsoleHistory`.
+-- The history is off until this is called.
+--
+-- @function setConsoleHistorySize
+-- @tparam number size How many messages to keep. `0` turns the history off.
+function View:setConsoleHistorySize(size) end
+
+--- Returns the console messages kept by `setConsoleHistorySize`, oldest first.
+--
+-- @function getConsoleHistory
+-- @treturn {table,...} Tables with the fields `source`, `level`, `message`, `line`,
+-- `column` and `sourceId`, as passed to `onConsoleMessage`.
+-- @usage
+-- view:setConsoleHistorySize(50)
+-- -- later, in a debug overlay
+-- for _, entry in ipairs(view:getConsoleHistory()) do
+--   print(entry.level, entry.message)
+-- end
+function View:getConsoleHistory() end
+
+--- Forgets the console messages kept so far.
+--
+-- @function clearConsoleHistory
+function View:clearConsoleHistory() end
+
 return View
@@ -1,35 +1,132 @@
-use crate::clipboard::{clipboard_on_clear, clipboard_on_get_text, clipboard_on_set_text};
+use crate::clipboard::{
+    clipboard_get_data, clipboard_get_formats, clipboard_get_mode, clipboard_get_text,
+    clipboard_init, clipboard_on_clear, clipboard_on_get_text, clipboard_on_set_text,
+    clipboard_set_data, clipboard_set_mode, clipboard_set_text,
+};
+use crate::console::console_set_mirror;
+use crate::conversion::{
+    LuaBytes, function_update, lua_bytes_from_value, lua_date, mark_table, set_conversion_options,
+};
 use crate::filesystem::{
-    filesystem_set_on_file_exists_callback, filesystem_set_on_get_file_charset_callback,
-    filesystem_set_on_get_file_mime_type_callback, filesystem_set_on_open_file_callback,
-    filesystem_update,
+    filesystem_add_file, filesystem_clear_files, filesystem_create_pack, filesystem_init,
+    filesystem_mount, filesystem_remove_file, filesystem_set_on_file_exists_callback,
+    filesystem_set_on_get_file_charset_callback, filesystem_set_on_get_file_mime_type_callback,
+    filesystem_set_on_open_file_callback, filesystem_set_sandbox, filesystem_set_timeout,
+    filesystem_unmount, filesystem_update, filesystem_use_love_filesystem,
+};
+use crate::gpu_replay::gpu_replay;
+use crate::logger::{logger_set_callback, logger_set_file, logger_update};
+use crate::memory::{memory_get_stats, memory_purge, memory_set_auto_purge};
+use crate::proxy::proxy_update;
+use crate::script_context::ScriptContext;
+use crate::surface::surface_new_shader;
+use crate::ultralight_renderer::{
+    renderer_init, renderer_quit, renderer_refresh_display, renderer_refresh_displays,
+    renderer_render, renderer_set_display_rate, renderer_update,
 };
-use crate::ultralight_renderer::{renderer_draw, renderer_init, renderer_quit, renderer_update};
-use crate::ultralight_view::UltralightView;
+use crate::ultralight_view::{UltralightView, check_display_id, views_init, views_skip_hidden};
 use mlua::prelude::*;
 
-pub fn lua_create_view(lua: &Lua, _: ()) -> LuaResult<UltralightView> {
-    UltralightView::new(lua)
+pub fn lua_create_view(lua: &Lua, options: Option<LuaTable>) -> LuaResult<LuaAnyUserData> {
+    UltralightView::create(lua, options)
+}
+
+pub fn lua_new_script_context(_: &Lua, options: Option<LuaTable>) -> LuaResult<ScriptContext> {
+    ScriptContext::new(options)
 }
 
 pub fn lua_update(lua: &Lua, _: ()) -> LuaResult<()> {
     filesystem_update(lua);
     renderer_update();
+    proxy_update();
+    function_update();
+    logger_update(lua);
     Ok(())
 }
 
-pub fn lua_draw(_: &Lua, _: ()) -> LuaResult<()> {
-    renderer_draw();
+pub fn lua_draw(lua: &Lua, _: ()) -> LuaResult<()> {
+    filesystem_update(lua);
+    renderer_refresh_displays(lua);
+    // after the refresh, which marks animated views as needing paint again
+    views_skip_hidden(lua)?;
+    renderer_render();
+    gpu_replay(lua)?;
 
     Ok(())
 }
 
-pub fn lua_quit(_: &Lua, _: ()) -> LuaResult<()> {
+pub fn lua_quit(lua: &Lua, _: ()) -> LuaResult<()> {
+    filesystem_update(lua);
     renderer_quit();
 
     Ok(())
 }
 
+pub fn lua_bytes(_: &Lua, data: LuaValue) -> LuaResult<LuaBytes> {
+    lua_bytes_from_value(data)
+}
+
+pub fn lua_set_conversion_options(_: &Lua, options: LuaTable) -> LuaResult<()> {
+    set_conversion_options(options)
+}
+
+pub fn lua_array(lua: &Lua, table: LuaTable) -> LuaResult<LuaTable> {
+    mark_table(lua, &table, "array")?;
+    Ok(table)
+}
+
+pub fn lua_date_from_value(lua: &Lua, value: LuaValue) -> LuaResult<LuaTable> {
+    lua_date(lua, value)
+}
+
+pub fn lua_object(lua: &Lua, table: LuaTable) -> LuaResult<LuaTable> {
+    mark_table(lua, &table, "object")?;
+    Ok(table)
+}
+
+pub fn lua_set_logger(
+    lua: &Lua,
+    (callback, level): (Option<LuaFunction>, Option<String>),
+) -> LuaResult<()> {
+    logger_set_callback(lua, callback, level)
+}
+
+pub fn lua_set_log_file(_: &Lua, (path, level): (Option<String>, Option<String>)) -> LuaResult<()> {
+    logger_set_file(path, level)
+}
+
+pub fn lua_set_console_mirror(_: &Lua, enabled: bool) -> LuaResult<()> {
+    console_set_mirror(enabled);
+    Ok(())
+}
+
+pub fn lua_refresh_display(_: &Lua, display_id: u32) -> LuaResult<()> {
+    check_display_id(display_id)?;
+    renderer_refresh_display(display_id);
+    Ok(())
+}
+
+pub fn lua_set_display_rate(_: &Lua, (display_id, rate): (u32, Option<f64>)) -> LuaResult<()> {
+    check_display_id(display_id)?;
+    renderer_set_display_rate(display_id, rate)
+}
+
+pub fn lua_purge_memory(lua: &Lua, _: ()) -> LuaResult<()> {
+    memory_purge(lua)
+}
+
+pub fn lua_get_memory_stats(lua: &Lua, _: ()) -> LuaResult<LuaTable> {
+    memory_get_stats(lua)
+}
+
+pub fn lua_new_surface_shader(lua: &Lua, _: ()) -> LuaResult<LuaValue> {
+    surface_new_shader(lua)
+}
+
+pub fn lua_set_auto_purge(lua: &Lua, enabled: bool) -> LuaResult<()> {
+    memory_set_auto_purge(lua, enabled)
+}
+
 // Clipboard handling functions
 fn lua_clipboard_on_get_text(lua: &Lua, callback: LuaFunction) -> LuaResult<()> {
     clipboard_on_get_text(lua, callback)
@@ -43,6 +140,41 @@ fn lua_clipboard_on_clear(lua: &Lua, callback: LuaFunction) -> LuaResult<()> {
     clipboard_on_clear(lua, callback)
 }
 
+fn lua_clipboard_set_mode(_: &Lua, mode: String) -> LuaResult<()> {
+    clipboard_set_mode(&mode)
+}
+
+fn lua_clipboard_get_mode(_: &Lua, _: ()) -> LuaResult<&'static str> {
+    Ok(clipboard_get_mode())
+}
+
+fn lua_clipboard_get_text(_: &Lua, _: ()) -> LuaResult<Option<String>> {
+    Ok(clipboard_get_text())
+}
+
+fn lua_clipboard_set_text(_: &Lua, text: String) -> LuaResult<()> {
+    clipboard_set_text(&text);
+    Ok(())
+}
+
+fn lua_clipboard_get_data(lua: &Lua, format: String) -> LuaResult<Option<LuaString>> {
+    clipboard_get_data(&format)?
+        .map(|data| lua.create_string(data))
+        .transpose()
+}
+
+fn lua_clipboard_set_data(_: &Lua, (format, data): (String, LuaValue)) -> LuaResult<()> {
+    let data = match data {
+        LuaValue::Nil => None,
+        data => Some(lua_bytes_from_value(data)?.0),
+    };
+    clipboard_set_data(&format, data)
+}
+
+fn lua_clipboard_get_formats(_: &Lua, _: ()) -> LuaResult<Vec<String>> {
+    Ok(clipboard_get_formats())
+}
+
 // Filesystem handling functions
 pub fn lua_filesystem_on_file_exists(lua: &Lua, callback: LuaFunction) -> LuaResult<()> {
     filesystem_set_on_file_exists_callback(lua, callback)
@@ -60,20 +192,107 @@ fn lua_filesystem_on_open_file(lua: &Lua, callback: LuaFunction) -> LuaResult<()
     filesystem_set_on_open_file_callback(lua, callback)
 }
 
+fn lua_filesystem_use_love_filesystem(lua: &Lua, options: LuaValue) -> LuaResult<()> {
+    filesystem_use_love_filesystem(lua, options)
+}
+
+fn lua_filesystem_set_timeout(_: &Lua, seconds: f64) -> LuaResult<()> {
+    filesystem_set_timeout(seconds)
+}
+
+fn lua_filesystem_set_sandbox(_: &Lua, options: Option<LuaTable>) -> LuaResult<()> {
+    filesystem_set_sandbox(options)
+}
+
+fn lua_filesystem_add_file(_: &Lua, (path, data): (String, LuaValue)) -> LuaResult<()> {
+    filesystem_add_file(&path, lua_bytes_from_value(data)?.0)
+}
+
+fn lua_filesystem_remove_file(_: &Lua, path: String) -> LuaResult<bool> {
+    filesystem_remove_file(&path)
+}
+
+// `unload` predates `removeFile`, and clears every file when no path is given
+fn lua_filesystem_unload(_: &Lua, path: Option<String>) -> LuaResult<()> {
+    match path {
+        Some(path) => filesystem_remove_file(&path).map(|_| ()),
+        None => filesystem_clear_files(),
+    }
+}
+
+fn lua_filesystem_mount(
+    lua: &Lua,
+    (prefix, source, options): (String, LuaValue, Option<LuaTable>),
+) -> LuaResult<()> {
+    filesystem_mount(lua, &prefix, source, options)
+}
+
+fn lua_filesystem_create_pack(
+    lua: &Lua,
+    (files, options): (LuaTable, Option<LuaTable>),
+) -> LuaResult<LuaString> {
+    filesystem_create_pack(lua, files, options)
+}
+
+fn lua_filesystem_unmount(_: &Lua, prefix: String) -> LuaResult<bool> {
+    filesystem_unmount(&prefix)
+}
+
 pub fn init_webview_module(lua: &Lua) -> LuaResult<LuaTable> {
+    filesystem_init(lua);
+    clipboard_init(lua);
+    views_init(lua)?;
     renderer_init(lua)?;
 
     let exports = lua.create_table()?;
     exports.set("createView", lua.create_function(lua_create_view)?)?;
+    exports.set(
+        "newScriptContext",
+        lua.create_function(lua_new_script_context)?,
+    )?;
     exports.set("update", lua.create_function(lua_update)?)?;
     exports.set("draw", lua.create_function(lua_draw)?)?;
     exports.set("quit", lua.create_function(lua_quit)?)?;
+    exports.set("bytes", lua.create_function(lua_bytes)?)?;
+    exports.set(
+        "setConversionOptions",
+        lua.create_function(lua_set_conversion_options)?,
+    )?;
+    exports.set("array", lua.create_function(lua_array)?)?;
+    exports.set("object", lua.create_function(lua_object)?)?;
+    exports.set("date", lua.create_function(lua_date_from_value)?)?;
+    exports.set("setLogger", lua.create_function(lua_set_logger)?)?;
+    exports.set("setLogFile", lua.create_function(lua_set_log_file)?)?;
+    exports.set(
+        "setConsoleMirror",
+        lua.create_function(lua_set_console_mirror)?,
+    )?;
+    exports.set("refreshDisplay", lua.create_function(lua_refresh_display)?)?;
+    exports.set("setDisplayRate", lua.create_function(lua_set_display_rate)?)?;
+    exports.set("purgeMemory", lua.create_function(lua_purge_memory)?)?;
+    exports.set("getMemoryStats", lua.create_function(lua_get_memory_stats)?)?;
+    exports.set("setAutoPurge", lua.create_function(lua_set_auto_purge)?)?;
+    exports.set(
+        "newSurfaceShader",
+        lua.create_function(lua_new_surface_shader)?,
+    )?;
+    exports.set("null", LuaValue::NULL)?;
     exports.set("version", env!("CARGO_PKG_VERSION"))?;
 
     let clipboard = lua.create_table()?;
     clipboard.set("onGetText", lua.create_function(lua_clipboard_on_get_text)?)?;
     clipboard.set("onSetText", lua.create_function(lua_clipboard_on_set_text)?)?;
     clipboard.set("onClear", lua.create_function(lua_clipboard_on_clear)?)?;
+    clipboard.set("setMode", lua.create_function(lua_clipboard_set_mode)?)?;
+    clipboard.set("getMode", lua.create_function(lua_clipboard_get_mode)?)?;
+    clipboard.set("getText", lua.create_function(lua_clipboard_get_text)?)?;
+    clipboard.set("setText", lua.create_function(lua_clipboard_set_text)?)?;
+    clipboard.set("getData", lua.create_function(lua_clipboard_get_data)?)?;
+    clipboard.set("setData", lua.create_function(lua_clipboard_set_data)?)?;
+    clipboard.set(
+        "getFormats",
+        lua.create_function(lua_clipboard_get_formats)?,
+    )?;
     exports.set("clipboard", clipboard)?;
 
     let filesystem = lua.create_table()?;
@@ -93,6 +312,31 @@ pub fn init_webview_module(lua: &Lua) -> LuaResult<LuaTable> {
         "onOpenFile",
         lua.create_function(lua_filesystem_on_open_file)?,
     )?;
+    filesystem.set(
+        "useLoveFilesystem",
+        lua.create_function(lua_filesystem_use_love_filesystem)?,
+    )?;
+    filesystem.set(
+        "setTimeout",
+        lua.create_function(lua_filesystem_set_timeout)?,
+    )?;
+    filesystem.set(
+        "setSandbox",
+        lua.create_function(lua_filesystem_set_sandbox)?,
+    )?;
+    filesystem.set("addFile", lua.create_function(lua_filesystem_add_file)?)?;
+    filesystem.set(
+        "removeFile",
+        lua.create_function(lua_filesystem_remove_file)?,
+    )?;
+    filesystem.set("preload", lua.create_function(lua_filesystem_add_file)?)?;
+    filesystem.set("unload", lua.create_function(lua_filesystem_unload)?)?;
+    filesystem.set("mount", lua.create_function(lua_filesystem_mount)?)?;
+    filesystem.set("unmount", lua.create_function(lua_filesystem_unmount)?)?;
+    filesystem.set(
+        "createPack",
+        lua.create_function(lua_filesystem_create_pack)?,
+    )?;
     exports.set("filesystem", filesystem)?;
 
     Ok(exports)
@@ -1,213 +1,145 @@
 use mlua::prelude::*;
+use std::{
+    cell::{Cell, RefCell},
+    rc::{Rc, Weak},
+};
 use ul_next::View;
-use ul_next::javascript::{AsJSValue, JSObject, JSPropertyAttributes, JSValue};
-
-fn js_value_to_lua_value(
-    lua: &Lua,
-    js: &ul_next::javascript::JSContext,
-    js_value: &JSValue,
-) -> Result<mlua::Value, LuaError> {
-    if JSValue::is_array(js_value) || JSValue::is_typed_array(js_value) {
-        if let Ok(obj) = js_value.as_object() {
-            if let Ok(length) = obj.get_property("length") {
-                if let Ok(len) = length.as_number() {
-                    let tbl = lua.create_table()?;
-                    for i in 0..len as usize {
-                        if let Ok(item) = obj.get_property(&i.to_string()) {
-                            tbl.set(i + 1, js_value_to_lua_value(lua, js, &item)?)?;
-                        }
-                    }
-
-                    return Ok(mlua::Value::Table(tbl));
-                }
-            }
-        }
+use ul_next::javascript::{JSObject, JSPropertyAttributes, JSValue};
+
+use crate::console::{
+    ConsoleCapture, ConsoleMessage, dispatch_console_message, level_name, source_name,
+};
+use crate::conversion::{
+    Converter, JSExceptionInfo, LuaCall, PageContext, ScriptSource, call_lua_async_function,
+    call_lua_function, new_lua_function,
+};
+use crate::proxy::expose_table;
+use crate::ultralight_renderer::engine_call;
 
-        Ok(mlua::Value::Nil)
-    } else if JSValue::is_boolean(js_value) {
-        Ok(mlua::Value::Boolean(js_value.as_boolean()))
-    } else if JSValue::is_null(js_value) {
-        Ok(mlua::Value::Nil)
-    } else if JSValue::is_number(js_value) {
-        Ok(mlua::Value::Number(js_value.as_number().unwrap_or(0.0)))
-    } else if JSValue::is_object(js_value) {
-        let tbl = lua.create_table()?;
-        if let Ok(obj) = js_value.as_object() {
-            if obj.is_function() {
-                return Ok(mlua::Value::Nil);
-            }
-
-            let keys = obj.get_property_names();
-            for i in 0..keys.len() {
-                if let Some(key) = keys.get(i) {
-                    if let Ok(value) =
-                        obj.get_property_for_key(&JSValue::from_jsstring(js, key.clone()))
-                    {
-                        let key_str = key.to_string();
-                        if let Ok(num) = key_str.parse::<i64>() {
-                            tbl.set(num, js_value_to_lua_value(lua, js, &value)?)?;
-                        } else {
-                            tbl.set(key_str, js_value_to_lua_value(lua, js, &value)?)?;
-                        }
-                    }
-                }
-            }
-        }
-        Ok(mlua::Value::Table(tbl))
-    } else if JSValue::is_string(js_value) {
-        if let Ok(str_val) = js_value.as_string() {
-            return Ok(mlua::Value::String(
-                lua.create_string(&str_val.to_string())?,
-            ));
-        }
+#[derive(Default)]
+pub struct UltralightViewCallbacks {
+    lua: Lua,
+    view: Weak<View>,
+    page_generation: Rc<Cell<u64>>,
+    page_tracking: bool,
+    console: Rc<RefCell<ConsoleCapture>>,
+    console_capture: bool,
+}
 
-        Ok(mlua::Value::Nil)
-    } else {
-        Ok(mlua::Value::Nil)
+impl Drop for UltralightViewCallbacks {
+    fn drop(&mut self) {
+        // invalidate every JS handle still held by Lua
+        self.page_generation.set(self.page_generation.get() + 1);
+        self.lua.expire_registry_values();
     }
 }
 
-fn is_sequential_table(tbl: &mlua::Table) -> bool {
-    let len = tbl.len().unwrap_or(0);
-    for i in 1..=len {
-        if tbl.get(i).unwrap_or(mlua::Value::Nil) == mlua::Value::Nil {
-            return false;
+impl UltralightViewCallbacks {
+    pub fn new(lua: Lua, view: Weak<View>) -> Self {
+        UltralightViewCallbacks {
+            lua,
+            view,
+            page_generation: Rc::new(Cell::new(0)),
+            page_tracking: false,
+            console: Rc::new(RefCell::new(ConsoleCapture::default())),
+            console_capture: false,
         }
     }
 
-    for pair in tbl.pairs::<mlua::Value, mlua::Value>() {
-        if let Ok((key, _)) = pair {
-            match key {
-                mlua::Value::Integer(i) => {
-                    if i < 1 || i > len as i64 {
-                        return false;
-                    }
-                }
-                _ => return false,
-            }
+    fn next_page(page_generation: &Rc<Cell<u64>>, is_main_frame: bool) {
+        if is_main_frame {
+            page_generation.set(page_generation.get() + 1);
         }
     }
-    true
-}
 
-fn lua_value_to_js_value<'a>(
-    js_ctx: &'a ul_next::javascript::JSContext,
-    ret_vals: mlua::Value,
-) -> Result<JSValue<'a>, LuaError> {
-    if let mlua::Value::Boolean(b) = ret_vals {
-        return Ok(JSValue::new_boolean(js_ctx, b));
-    } else if let mlua::Value::Integer(i) = ret_vals {
-        return Ok(JSValue::new_number(js_ctx, i as f64));
-    } else if let mlua::Value::Nil = ret_vals {
-        return Ok(JSValue::new_null(js_ctx));
-    } else if let mlua::Value::Number(n) = ret_vals {
-        return Ok(JSValue::new_number(js_ctx, n));
-    } else if let mlua::Value::String(s) = ret_vals {
-        if let Ok(str_val) = s.to_str() {
-            return Ok(JSValue::new_string(js_ctx, &str_val));
+    // The begin loading callback can only be installed once the view lives
+    // inside its userdata, so tracking starts lazily on first use.
+    fn ensure_page_tracking(&mut self, view: &View) {
+        if self.page_tracking {
+            return;
         }
-    } else if let mlua::Value::Table(tbl) = ret_vals {
-        if is_sequential_table(&tbl) {
-            let mut array_items = vec![];
-
-            for i in 1..=tbl.len().unwrap_or(0) {
-                if let Ok(value) = tbl.get(i) {
-                    if let Ok(js_value) = lua_value_to_js_value(js_ctx, value) {
-                        array_items.push(js_value);
-                    }
-                }
-            }
 
-            return JSObject::new_array(js_ctx, &array_items)
-                .map(|js_array| js_array.as_value().clone())
-                .map_err(|e| LuaError::external(e.as_string().unwrap().to_string()));
-        } else {
-            let js_obj = JSObject::new(&js_ctx);
-
-            for pair in tbl.pairs::<String, mlua::Value>() {
-                match pair {
-                    Ok((key_str, value)) => {
-                        if let Ok(js_value) = lua_value_to_js_value(js_ctx, value) {
-                            js_obj
-                                .set_property(&key_str, &js_value, JSPropertyAttributes::default())
-                                .map_err(|e| {
-                                    LuaError::external(e.as_string().unwrap().to_string())
-                                })?;
-                        }
-                    }
-                    Err(e) => return Err(LuaError::external(e.to_string())),
-                }
-            }
+        let page_generation = self.page_generation.clone();
+        view.set_begin_loading_callback(move |_, _, is_main_frame, _| {
+            Self::next_page(&page_generation, is_main_frame);
+        });
 
-            return Ok(js_obj.as_value().clone());
-        }
+        self.page_tracking = true;
     }
 
-    Ok(JSValue::new_undefined(js_ctx))
-}
+    /// Starts collecting console messages, for the history, the mirror and the Lua callback.
+    ///
+    /// Installed lazily for the same reason as page tracking.
+    pub fn ensure_console_capture(&mut self, view: &View) {
+        if self.console_capture {
+            return;
+        }
 
-#[derive(Default)]
-pub struct UltralightViewCallbacks {
-    lua: Lua,
-}
+        let lua = self.lua.clone();
+        let console = self.console.clone();
+        view.set_add_console_message_callback(
+            move |_, source, level, message, line, column, source_id| {
+                let message = ConsoleMessage {
+                    source: source_name(source),
+                    level: level_name(level),
+                    message,
+                    line,
+                    column,
+                    source_id,
+                };
+                dispatch_console_message(&lua, &console, message);
+            },
+        );
 
-impl Drop for UltralightViewCallbacks {
-    fn drop(&mut self) {
-        self.lua.expire_registry_values();
+        self.console_capture = true;
     }
-}
 
-impl UltralightViewCallbacks {
-    pub fn new(lua: Lua) -> Self {
-        UltralightViewCallbacks { lua }
+    pub fn console(&self) -> &RefCell<ConsoleCapture> {
+        &self.console
     }
 
-    fn call_lua_callback_with_result<'a>(
+    pub fn add_function(
+        &mut self,
         lua: &Lua,
-        registry_key: &LuaRegistryKey,
-        js_ctx: &'a ul_next::javascript::JSContext,
-        args: &[JSValue],
-    ) -> Result<JSValue<'a>, LuaError> {
-        let mut lua_args = LuaMultiValue::new();
-
-        for arg in args {
-            lua_args.push_back(js_value_to_lua_value(lua, js_ctx, arg)?);
-        }
-
-        if let Ok(func) = lua.registry_value::<LuaFunction>(registry_key) {
-            let result = func
-                .call::<mlua::MultiValue>(lua_args)
-                .map_err(|e| mlua::Error::external(e.to_string()))?;
-
-            let mut js_values = Vec::new();
-            for val in result {
-                if let Ok(js_value) = lua_value_to_js_value(js_ctx, val) {
-                    js_values.push(js_value);
-                }
-            }
+        view: &View,
+        namespace: String,
+        name: String,
+        callback: LuaFunction,
+    ) -> LuaResult<()> {
+        self.define_function(lua, view, namespace, name, callback, call_lua_function)
+    }
 
-            match js_values.len() {
-                0 => Ok(JSValue::new_undefined(js_ctx)),
-                1 => Ok(js_values[0].clone()),
-                _ => match JSObject::new_array(js_ctx, &js_values) {
-                    Ok(js_array) => Ok(js_array.as_value().clone()),
-                    Err(_) => Ok(JSValue::new_undefined(js_ctx)),
-                },
-            }
-        } else {
-            Ok(JSValue::new_undefined(js_ctx))
-        }
+    pub fn add_async_function(
+        &mut self,
+        lua: &Lua,
+        view: &View,
+        namespace: String,
+        name: String,
+        callback: LuaFunction,
+    ) -> LuaResult<()> {
+        self.define_function(
+            lua,
+            view,
+            namespace,
+            name,
+            callback,
+            call_lua_async_function,
+        )
     }
 
-    pub fn add_function(
+    fn define_function(
         &mut self,
         lua: &Lua,
         view: &View,
         namespace: String,
         name: String,
         callback: LuaFunction,
+        call: LuaCall,
     ) -> LuaResult<()> {
-        let callback_key = lua.create_registry_value(callback)?;
+        self.ensure_page_tracking(view);
+
+        let page_generation = self.page_generation.clone();
+        let page_view = self.view.clone();
 
         let ctx = view.lock_js_context();
         let global = ctx.global_object();
@@ -223,19 +155,8 @@ impl UltralightViewCallbacks {
             Err(_) => JSObject::new(&ctx),
         };
 
-        let lua_clone = lua.clone();
-        let js_func = JSObject::new_function_with_callback(&ctx, move |js_ctx, _this, args| {
-            match Self::call_lua_callback_with_result(&lua_clone, &callback_key, js_ctx, args) {
-                Ok(result) => Ok(result),
-                Err(e) => {
-                    match JSObject::new_error(&js_ctx, JSValue::new_string(js_ctx, &e.to_string()))
-                    {
-                        Ok(e_obj) => Err(e_obj.into_value()),
-                        Err(_) => Ok(JSValue::new_undefined(&js_ctx)),
-                    }
-                }
-            }
-        });
+        let page = move || PageContext::with_view(&page_generation, &page_view);
+        let js_func = new_lua_function(lua, &ctx, callback, page, call)?;
 
         namespace_obj
             .set_property(&name, &js_func, JSPropertyAttributes::default())
@@ -250,6 +171,56 @@ impl UltralightViewCallbacks {
         Ok(())
     }
 
+    /// Evaluates a script in the page, converting its result to Lua.
+    ///
+    /// JavaScript exceptions are returned as errors including the location they were thrown at.
+    pub fn evaluate(
+        &mut self,
+        lua: &Lua,
+        view: &View,
+        script: &str,
+        options: &ScriptSource,
+    ) -> LuaResult<LuaValue> {
+        self.ensure_page_tracking(view);
+
+        let page = PageContext::with_view(&self.page_generation, &self.view);
+        let ctx = view.lock_js_context();
+
+        match engine_call(|| {
+            ctx.evaluate_script_with_source(
+                script,
+                options.source_url.as_deref(),
+                options.start_line,
+            )
+        }) {
+            Ok(value) => Converter::new(lua, &page).js_to_lua(&ctx, &value),
+            Err(e) => Err(LuaError::external(JSExceptionInfo::new(&e).to_string())),
+        }
+    }
+
+    pub fn expose_object(
+        &mut self,
+        lua: &Lua,
+        view: &View,
+        name: String,
+        table: LuaTable,
+    ) -> LuaResult<()> {
+        self.ensure_page_tracking(view);
+
+        let page = PageContext::with_view(&self.page_generation, &self.view);
+
+        let ctx = view.lock_js_context();
+        let proxy = expose_table(lua, &ctx, table, &page)?;
+
+        ctx.global_object()
+            .set_property(&name, &proxy, JSPropertyAttributes::default())
+            .map_err(|e| mlua::Error::external(e.as_string().unwrap().to_string()))?;
+
+        lua.expire_registry_values();
+
+        Ok(())
+    }
+
     pub fn set_dom_ready_callback(
         &mut self,
         lua: &Lua,
@@ -277,13 +248,17 @@ impl UltralightViewCallbacks {
         callback: LuaFunction,
     ) -> LuaResult<()> {
         let callback_key = lua.create_registry_value(callback)?;
+        let page_generation = self.page_generation.clone();
 
         let lua_clone = lua.clone();
         view.set_begin_loading_callback(move |_, _, is_main_frame, url| {
+            Self::next_page(&page_generation, is_main_frame);
+
             if let Ok(func) = lua_clone.registry_value::<LuaFunction>(&callback_key) {
                 let _ = func.call::<(bool, String)>((is_main_frame, url));
             }
         });
+        self.page_tracking = true;
 
         lua.expire_registry_values();
 
@@ -465,29 +440,8 @@ impl UltralightViewCallbacks {
         view: &View,
         callback: LuaFunction,
     ) -> LuaResult<()> {
-        let callback_key = lua.create_registry_value(callback)?;
-
-        let lua_clone = lua.clone();
-        view.set_add_console_message_callback(
-            move |_,
-                  message_source,
-                  message_level,
-                  message,
-                  line_number,
-                  column_number,
-                  source_id| {
-                if let Ok(func) = lua_clone.registry_value::<LuaFunction>(&callback_key) {
-                    let _ = func.call::<(isize, isize, String, u32, u32, String)>((
-                        message_source as isize,
-                        message_level as isize,
-                        message,
-                        line_number,
-                        column_number,
-                        source_id,
-                    ));
-                }
-            },
-        );
+        self.ensure_console_capture(view);
+        self.console.borrow_mut().callback = Some(lua.create_registry_value(callback)?);
 
         lua.expire_registry_values();
 
@@ -0,0 +1,121 @@
+use aes::{Aes128, Aes192, Aes256};
+use ctr::{
+    Ctr128BE,
+    cipher::{KeyIvInit, StreamCipher},
+};
+
+/// A symmetric transform applied to a whole archive.
+///
+/// Applying it twice gives back the original data, so the same cipher both
+/// encrypts packs and decrypts them when they are mounted.
+pub trait Cipher {
+    fn apply(&self, data: &mut [u8]);
+}
+
+/// XORs the data with a repeating key.
+pub struct XorCipher {
+    key: Vec<u8>,
+}
+
+impl XorCipher {
+    pub fn new(key: &[u8]) -> Result<Self, String> {
+        if key.is_empty() {
+            return Err("xor key must not be empty".to_string());
+        }
+
+        Ok(XorCipher { key: key.to_vec() })
+    }
+}
+
+impl Cipher for XorCipher {
+    fn apply(&self, data: &mut [u8]) {
+        for (byte, key) in data.iter_mut().zip(self.key.iter().cycle()) {
+            *byte ^= key;
+        }
+    }
+}
+
+/// AES in counter mode, with a 128, 192 or 256 bit key.
+pub struct AesCtrCipher {
+    key: Vec<u8>,
+    iv: [u8; 16],
+}
+
+impl AesCtrCipher {
+    pub fn new(key: &[u8], iv: &[u8]) -> Result<Self, String> {
+        if !matches!(key.len(), 16 | 24 | 32) {
+            return Err(format!(
+                "aes-ctr key must be 16, 24 or 32 bytes, got {}",
+                key.len()
+            ));
+        }
+
+        let iv = iv
+            .try_into()
+            .map_err(|_| format!("aes-ctr iv must be 16 bytes, got {}", iv.len()))?;
+
+        Ok(AesCtrCipher {
+            key: key.to_vec(),
+            iv,
+        })
+    }
+}
+
+impl Cipher for AesCtrCipher {
+    fn apply(&self, data: &mut [u8]) {
+        // key lengths are checked in `new`
+        match self.key.len() {
+            16 => Ctr128BE::<Aes128>::new_from_slices(&self.key, &self.iv)
+                .expect("valid aes-128 key")
+                .apply_keystream(data),
+            24 => Ctr128BE::<Aes192>::new_from_slices(&self.key, &self.iv)
+                .expect("valid aes-192 key")
+                .apply_keystream(data),
+            _ => Ctr128BE::<Aes256>::new_from_slices(&self.key, &self.iv)
+                .expect("valid aes-256 key")
+                .apply_keystream(data),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn xor_cipher_round_trips() {
+        let cipher = XorCipher::new(b"key").unwrap();
+        let mut data = b"hello world".to_vec();
+
+        cipher.apply(&mut data);
+        assert_ne!(data, b"hello world");
+        cipher.apply(&mut data);
+        assert_eq!(data, b"hello world");
+
+        assert!(XorCipher::new(b"").is_err());
+    }
+
+    #[test]
+    fn aes_ctr_cipher_matches_the_nist_vector() {
+        // NIST SP 800-38A, F.5.1 CTR-AES128.Encrypt
+        let cipher = AesCtrCipher::new(
+            &hex("2b7e151628aed2a6abf7158809cf4f3c"),
+            &hex("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff"),
+        )
+        .unwrap();
+        let mut data = hex("6bc1bee22e409f96e93d7e117393172a");
+
+        cipher.apply(&mut data);
+        assert_eq!(data, hex("874d6191b620e3261bef6864990db6ce"));
+
+        assert!(AesCtrCipher::new(&[0; 15], &[0; 16]).is_err());
+        assert!(AesCtrCipher::new(&[0; 16], &[0; 8]).is_err());
+    }
+}
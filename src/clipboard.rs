@@ -1,76 +1,502 @@
+use crate::logger::log_message;
+use crate::ultralight_view::view_userdata;
 use cli_clipboard::{ClipboardContext, ClipboardProvider};
 use mlua::prelude::*;
-use std::cell::RefCell;
-use ul_next::platform;
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    rc::{Rc, Weak},
+};
+use ul_next::platform::{self, LogLevel};
 
-pub enum ClipboardOnGetAction {
+pub const TEXT_FORMAT: &str = "text/plain";
+
+/// What a clipboard callback decided.
+pub enum ClipboardAction {
     Override(String),
     Allow,
     Deny,
 }
 
+impl ClipboardAction {
+    /// A string overrides the text, `false` denies and anything else allows.
+    ///
+    /// A failing callback denies, and its error is sent to the logger.
+    fn from_lua(value: LuaResult<LuaValue>) -> Self {
+        match value {
+            Err(e) => {
+                log_message(
+                    LogLevel::Error,
+                    &format!("clipboard callback failed: {}", e),
+                );
+                ClipboardAction::Deny
+            }
+            Ok(LuaValue::String(lua_string)) => lua_string
+                .to_str()
+                .map(|text| ClipboardAction::Override(text.to_string()))
+                .unwrap_or(ClipboardAction::Allow),
+            Ok(LuaValue::Boolean(false)) => ClipboardAction::Deny,
+            _ => ClipboardAction::Allow,
+        }
+    }
+}
+
+/// The view-level clipboard events, see [`ViewClipboard::set_handler`].
+#[derive(Clone, Copy)]
+pub enum ClipboardEvent {
+    Copy,
+    Cut,
+    Paste,
+}
+
+// global callbacks, given the id of the acting view if there is one
+type ReadCallback = Box<dyn Fn(Option<u64>) -> ClipboardAction>;
+type WriteCallback = Box<dyn Fn(String, Option<u64>) -> bool>;
+type ClearCallback = Box<dyn Fn(Option<u64>) -> bool>;
+
 thread_local! {
-    static CLIPBOARD_READ_CALLBACK: RefCell<Option<Box<dyn Fn() -> ClipboardOnGetAction + 'static>>> = RefCell::new(None);
-    static CLIPBOARD_WRITE_CALLBACK: RefCell<Option<Box<dyn Fn(String) -> bool + 'static>>> = RefCell::new(None);
-    static CLIPBOARD_CLEAR_CALLBACK: RefCell<Option<Box<dyn Fn() -> bool + 'static>>> = RefCell::new(None);
+    static CLIPBOARD_READ_CALLBACK: RefCell<Option<ReadCallback>> = const { RefCell::new(None) };
+    static CLIPBOARD_WRITE_CALLBACK: RefCell<Option<WriteCallback>> = const { RefCell::new(None) };
+    static CLIPBOARD_CLEAR_CALLBACK: RefCell<Option<ClearCallback>> = const { RefCell::new(None) };
+
+    static CLIPBOARD_MODE: Cell<ClipboardMode> = const { Cell::new(ClipboardMode::System) };
+    static CLIPBOARD_LUA: RefCell<Option<WeakLua>> = const { RefCell::new(None) };
+    // opened once, `None` inside when the platform has no clipboard
+    static SYSTEM_CLIPBOARD: RefCell<Option<Option<ClipboardContext>>> = const { RefCell::new(None) };
+    // the in-process clipboard, format -> data
+    static MEMORY_CLIPBOARD: RefCell<BTreeMap<String, Vec<u8>>> = const { RefCell::new(BTreeMap::new()) };
+
+    static VIEWS: RefCell<Vec<Weak<ViewState>>> = const { RefCell::new(Vec::new()) };
+    // the view whose input is being handled
+    static ACTIVE_VIEW: RefCell<Option<Rc<ViewState>>> = const { RefCell::new(None) };
+    // the view last given focus through `view:focus()`
+    static FOCUSED_VIEW: RefCell<Weak<ViewState>> = const { RefCell::new(Weak::new()) };
+    // a clear asked for during input, held back until we know the copy goes through
+    static PENDING_CLEAR: Cell<bool> = const { Cell::new(false) };
+    // set while a cut shortcut is handled, so writes are reported as cuts
+    static CUTTING: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Where clipboard contents are kept.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ClipboardMode {
+    /// The OS clipboard, falling back to `love.system` when there is none.
+    System,
+    /// A clipboard private to the game, shared by all views.
+    Memory,
+    /// `love.system.getClipboardText` and `setClipboardText`.
+    Love,
+}
+
+impl ClipboardMode {
+    fn parse(mode: &str) -> LuaResult<Self> {
+        match mode {
+            "system" => Ok(ClipboardMode::System),
+            "memory" => Ok(ClipboardMode::Memory),
+            "love" => Ok(ClipboardMode::Love),
+            other => Err(LuaError::external(format!(
+                "unknown clipboard mode '{}', expected 'system', 'memory' or 'love'",
+                other
+            ))),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ClipboardMode::System => "system",
+            ClipboardMode::Memory => "memory",
+            ClipboardMode::Love => "love",
+        }
+    }
+}
+
+/// What a view's pages may do with the clipboard.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ClipboardPolicy {
+    #[default]
+    Allow,
+    ReadOnly,
+    WriteOnly,
+    Deny,
+}
+
+impl ClipboardPolicy {
+    pub fn parse(policy: &str) -> LuaResult<Self> {
+        match policy {
+            "allow" => Ok(ClipboardPolicy::Allow),
+            "readOnly" => Ok(ClipboardPolicy::ReadOnly),
+            "writeOnly" => Ok(ClipboardPolicy::WriteOnly),
+            "deny" => Ok(ClipboardPolicy::Deny),
+            other => Err(LuaError::external(format!(
+                "unknown clipboard policy '{}', expected 'allow', 'readOnly', 'writeOnly' or 'deny'",
+                other
+            ))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ClipboardPolicy::Allow => "allow",
+            ClipboardPolicy::ReadOnly => "readOnly",
+            ClipboardPolicy::WriteOnly => "writeOnly",
+            ClipboardPolicy::Deny => "deny",
+        }
+    }
+
+    pub fn can_read(self) -> bool {
+        matches!(self, ClipboardPolicy::Allow | ClipboardPolicy::ReadOnly)
+    }
+
+    pub fn can_write(self) -> bool {
+        matches!(self, ClipboardPolicy::Allow | ClipboardPolicy::WriteOnly)
+    }
+
+    /// The policy allowing only what both allow.
+    pub fn intersect(self, other: Self) -> Self {
+        match (
+            self.can_read() && other.can_read(),
+            self.can_write() && other.can_write(),
+        ) {
+            (true, true) => ClipboardPolicy::Allow,
+            (true, false) => ClipboardPolicy::ReadOnly,
+            (false, true) => ClipboardPolicy::WriteOnly,
+            (false, false) => ClipboardPolicy::Deny,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ViewHandlers {
+    copy: Option<LuaRegistryKey>,
+    cut: Option<LuaRegistryKey>,
+    paste: Option<LuaRegistryKey>,
+}
+
+struct ViewState {
+    id: u64,
+    policy: Cell<ClipboardPolicy>,
+    handlers: RefCell<ViewHandlers>,
+}
+
+impl ViewState {
+    /// Asks the view's handler for `event`, `text` is what is being copied.
+    fn call_handler(&self, event: ClipboardEvent, text: Option<&str>) -> ClipboardAction {
+        let Some(lua) = main_lua() else {
+            return ClipboardAction::Allow;
+        };
+        let callback = {
+            let handlers = self.handlers.borrow();
+            let key = match event {
+                ClipboardEvent::Copy => &handlers.copy,
+                ClipboardEvent::Cut => &handlers.cut,
+                ClipboardEvent::Paste => &handlers.paste,
+            };
+            key.as_ref()
+                .and_then(|key| lua.registry_value::<LuaFunction>(key).ok())
+        };
+
+        match callback {
+            Some(callback) => ClipboardAction::from_lua(callback.call::<LuaValue>(text)),
+            None => ClipboardAction::Allow,
+        }
+    }
+}
+
+/// The clipboard state of one view: its policy, handlers and focus.
+pub struct ViewClipboard {
+    state: Rc<ViewState>,
+}
+
+impl ViewClipboard {
+    /// `id` is the view's id, see [`view_userdata`].
+    pub fn new(id: u64) -> Self {
+        let state = Rc::new(ViewState {
+            id,
+            policy: Cell::new(ClipboardPolicy::Allow),
+            handlers: RefCell::new(ViewHandlers::default()),
+        });
+        VIEWS.with(|cell| {
+            let mut views = cell.borrow_mut();
+            views.retain(|view| view.strong_count() > 0);
+            views.push(Rc::downgrade(&state));
+        });
+        ViewClipboard { state }
+    }
+
+    pub fn policy(&self) -> ClipboardPolicy {
+        self.state.policy.get()
+    }
+
+    pub fn set_policy(&self, policy: ClipboardPolicy) {
+        self.state.policy.set(policy);
+    }
+
+    pub fn set_handler(
+        &self,
+        lua: &Lua,
+        event: ClipboardEvent,
+        callback: LuaFunction,
+    ) -> LuaResult<()> {
+        let key = Some(lua.create_registry_value(callback)?);
+        let mut handlers = self.state.handlers.borrow_mut();
+        match event {
+            ClipboardEvent::Copy => handlers.copy = key,
+            ClipboardEvent::Cut => handlers.cut = key,
+            ClipboardEvent::Paste => handlers.paste = key,
+        }
+        lua.expire_registry_values();
+        Ok(())
+    }
+
+    /// Keeps track of the focused view, which clipboard access is blamed on
+    /// outside of input events.
+    pub fn set_focused(&self, focused: bool) {
+        FOCUSED_VIEW.with(|cell| {
+            let mut focused_view = cell.borrow_mut();
+            if focused {
+                *focused_view = Rc::downgrade(&self.state);
+            } else if focused_view.ptr_eq(&Rc::downgrade(&self.state)) {
+                *focused_view = Weak::new();
+            }
+        });
+    }
+
+    /// Runs `f` as this view's input, which is when pages copy and paste.
+    ///
+    /// Clipboard access during `f` follows this view's policy and handlers.
+    pub fn with_view<R>(&self, f: impl FnOnce() -> R) -> R {
+        let previous = ACTIVE_VIEW.with(|cell| cell.replace(Some(self.state.clone())));
+        let result = f();
+        ACTIVE_VIEW.with(|cell| *cell.borrow_mut() = previous);
+
+        // nothing was copied after the clear, so it was meant on its own
+        if PENDING_CLEAR.with(|cell| cell.replace(false)) {
+            clear_with_callback(Some(self.state.id));
+        }
+        result
+    }
+}
+
+/// Reports clipboard writes during `f` as cuts.
+pub fn clipboard_while_cutting<R>(f: impl FnOnce() -> R) -> R {
+    let previous = CUTTING.with(|cell| cell.replace(true));
+    let result = f();
+    CUTTING.with(|cell| cell.set(previous));
+    result
+}
+
+fn active_view() -> Option<Rc<ViewState>> {
+    ACTIVE_VIEW.with(|cell| cell.borrow().clone())
+}
+
+/// The view clipboard access is blamed on: the one handling input, or else the focused one.
+fn acting_view() -> Option<Rc<ViewState>> {
+    active_view().or_else(|| FOCUSED_VIEW.with(|cell| cell.borrow().upgrade()))
+}
+
+/// The policy for the current clipboard access.
+///
+/// Outside of a view's input events we can't tell which page is asking, so
+/// every live view's policy has to allow it.
+fn current_policy() -> ClipboardPolicy {
+    if let Some(view) = active_view() {
+        return view.policy.get();
+    }
+
+    VIEWS.with(|cell| {
+        cell.borrow()
+            .iter()
+            .filter_map(|view| view.upgrade())
+            .fold(ClipboardPolicy::Allow, |combined, view| {
+                combined.intersect(view.policy.get())
+            })
+    })
+}
+
+fn main_lua() -> Option<Lua> {
+    CLIPBOARD_LUA.with(|cell| cell.borrow().as_ref().and_then(|lua| lua.try_upgrade()))
+}
+
+/// Runs `f` on the cached system clipboard, `None` if there is none.
+fn with_system_clipboard<R>(f: impl FnOnce(&mut ClipboardContext) -> R) -> Option<R> {
+    SYSTEM_CLIPBOARD.with(|cell| {
+        cell.borrow_mut()
+            .get_or_insert_with(|| ClipboardContext::new().ok())
+            .as_mut()
+            .map(f)
+    })
+}
+
+fn love_system(lua: &Lua) -> Option<LuaTable> {
+    lua.globals()
+        .get::<LuaTable>("love")
+        .ok()?
+        .get::<LuaTable>("system")
+        .ok()
+}
+
+fn with_love_system<R>(f: impl FnOnce(LuaTable) -> Option<R>) -> Option<R> {
+    f(love_system(&main_lua()?)?)
+}
+
+fn read_text() -> Option<String> {
+    match CLIPBOARD_MODE.with(|cell| cell.get()) {
+        ClipboardMode::Memory => MEMORY_CLIPBOARD.with(|cell| {
+            cell.borrow()
+                .get(TEXT_FORMAT)
+                .map(|data| String::from_utf8_lossy(data).into_owned())
+        }),
+        ClipboardMode::Love => read_love_text(),
+        ClipboardMode::System => match with_system_clipboard(|ctx| ctx.get_contents().ok()) {
+            Some(text) => text,
+            None => read_love_text(),
+        },
+    }
+}
+
+fn read_love_text() -> Option<String> {
+    with_love_system(|system| {
+        system
+            .get::<LuaFunction>("getClipboardText")
+            .ok()?
+            .call::<String>(())
+            .ok()
+    })
+}
+
+fn write_text(text: &str) {
+    match CLIPBOARD_MODE.with(|cell| cell.get()) {
+        ClipboardMode::Memory => MEMORY_CLIPBOARD.with(|cell| {
+            let mut formats = cell.borrow_mut();
+            formats.clear();
+            formats.insert(TEXT_FORMAT.to_string(), text.as_bytes().to_vec());
+        }),
+        ClipboardMode::Love => write_love_text(text),
+        ClipboardMode::System => {
+            if with_system_clipboard(|ctx| ctx.set_contents(text.to_string())).is_none() {
+                write_love_text(text);
+            }
+        }
+    }
+}
+
+fn write_love_text(text: &str) {
+    with_love_system(|system| {
+        system
+            .get::<LuaFunction>("setClipboardText")
+            .ok()?
+            .call::<()>(text)
+            .ok()
+    });
+}
+
+fn clear_clipboard() {
+    match CLIPBOARD_MODE.with(|cell| cell.get()) {
+        ClipboardMode::Memory => MEMORY_CLIPBOARD.with(|cell| cell.borrow_mut().clear()),
+        ClipboardMode::Love => write_love_text(""),
+        ClipboardMode::System => {
+            if with_system_clipboard(|ctx| ctx.clear()).is_none() {
+                write_love_text("");
+            }
+        }
+    }
 }
 
 pub struct Clipboard;
 
+fn clear_with_callback(view_id: Option<u64>) {
+    let allow = CLIPBOARD_CLEAR_CALLBACK
+        .with(|cell| cell.borrow().as_ref().map(|cb| cb(view_id)).unwrap_or(true));
+    if allow {
+        clear_clipboard();
+    }
+}
+
 impl platform::Clipboard for Clipboard {
     fn clear(&mut self) {
-        let allow = CLIPBOARD_CLEAR_CALLBACK
-            .with(|cell| cell.borrow().as_ref().map(|cb| cb()).unwrap_or(true));
-        if !allow {
+        if !current_policy().can_write() {
             return;
         }
-        if let Ok(mut ctx) = ClipboardContext::new() {
-            let _ = ctx.clear();
+        // pages clear right before copying, wait to see whether the copy is allowed
+        if active_view().is_some() {
+            PENDING_CLEAR.with(|cell| cell.set(true));
+            return;
         }
+        clear_with_callback(acting_view().map(|view| view.id));
     }
 
     fn read_plain_text(&mut self) -> Option<String> {
-        let get_clipboard = || ClipboardContext::new().ok()?.get_contents().ok();
-        match CLIPBOARD_READ_CALLBACK.with(|cell| cell.borrow().as_ref().map(|cb| cb())) {
-            Some(ClipboardOnGetAction::Override(text)) => Some(text),
-            Some(ClipboardOnGetAction::Allow) | None => get_clipboard(),
-            Some(ClipboardOnGetAction::Deny) => None,
+        if !current_policy().can_read() {
+            return None;
+        }
+        let view = acting_view();
+        if let Some(view) = &view {
+            match view.call_handler(ClipboardEvent::Paste, None) {
+                ClipboardAction::Override(text) => return Some(text),
+                ClipboardAction::Deny => return None,
+                ClipboardAction::Allow => {}
+            }
+        }
+
+        let view_id = view.map(|view| view.id);
+        match CLIPBOARD_READ_CALLBACK.with(|cell| cell.borrow().as_ref().map(|cb| cb(view_id))) {
+            Some(ClipboardAction::Override(text)) => Some(text),
+            Some(ClipboardAction::Allow) | None => read_text(),
+            Some(ClipboardAction::Deny) => None,
         }
     }
 
     fn write_plain_text(&mut self, text: &str) {
+        // the write replaces the contents, so a held back clear isn't needed
+        PENDING_CLEAR.with(|cell| cell.set(false));
+        if !current_policy().can_write() {
+            return;
+        }
+        let view = acting_view();
+        let mut text = text.to_string();
+        if let Some(view) = &view {
+            let event = match CUTTING.with(|cell| cell.get()) {
+                true => ClipboardEvent::Cut,
+                false => ClipboardEvent::Copy,
+            };
+            match view.call_handler(event, Some(&text)) {
+                ClipboardAction::Override(replacement) => text = replacement,
+                ClipboardAction::Deny => return,
+                ClipboardAction::Allow => {}
+            }
+        }
+
+        let view_id = view.map(|view| view.id);
         let allow = CLIPBOARD_WRITE_CALLBACK.with(|cell| {
             cell.borrow()
                 .as_ref()
-                .map(|cb| cb(text.to_string()))
+                .map(|cb| cb(text.clone(), view_id))
                 .unwrap_or(true)
         });
         if !allow {
             return;
         }
-        if let Ok(mut ctx) = ClipboardContext::new() {
-            let _ = ctx.set_contents(text.to_string());
-        }
+        write_text(&text);
     }
 }
 
+pub fn clipboard_init(lua: &Lua) {
+    CLIPBOARD_LUA.with(|cell| *cell.borrow_mut() = Some(lua.weak()));
+}
+
 pub fn clipboard_on_get_text(lua: &Lua, callback: LuaFunction) -> LuaResult<()> {
     let callback_key = lua.create_registry_value(callback)?;
     let lua_clone = lua.clone();
 
     CLIPBOARD_READ_CALLBACK.with(|cell| {
-        *cell.borrow_mut() = Some(Box::new(move || {
-            match lua_clone
-                .registry_value::<LuaFunction>(&callback_key)
-                .and_then(|func| func.call::<LuaValue>(()))
-            {
-                Ok(LuaValue::String(lua_string)) => lua_string
-                    .to_str()
-                    .map(|text| ClipboardOnGetAction::Override(text.to_string()))
-                    .unwrap_or(ClipboardOnGetAction::Allow),
-                Ok(LuaValue::Boolean(false)) => ClipboardOnGetAction::Deny,
-                _ => ClipboardOnGetAction::Allow,
-            }
+        *cell.borrow_mut() = Some(Box::new(move |view_id| {
+            ClipboardAction::from_lua(
+                lua_clone
+                    .registry_value::<LuaFunction>(&callback_key)
+                    .and_then(|func| {
+                        func.call::<LuaValue>(view_id.and_then(|id| view_userdata(&lua_clone, id)))
+                    }),
+            )
         }));
     });
 
@@ -83,10 +509,12 @@ pub fn clipboard_on_set_text(lua: &Lua, callback: LuaFunction) -> LuaResult<()>
     let lua_clone = lua.clone();
 
     CLIPBOARD_WRITE_CALLBACK.with(|cell| {
-        *cell.borrow_mut() = Some(Box::new(move |text| {
+        *cell.borrow_mut() = Some(Box::new(move |text, view_id| {
             lua_clone
                 .registry_value::<LuaFunction>(&callback_key)
-                .and_then(|func| func.call::<bool>(text))
+                .and_then(|func| {
+                    func.call::<bool>((text, view_id.and_then(|id| view_userdata(&lua_clone, id))))
+                })
                 .unwrap_or(true)
         }));
     });
@@ -100,10 +528,12 @@ pub fn clipboard_on_clear(lua: &Lua, callback: LuaFunction) -> LuaResult<()> {
     let lua_clone = lua.clone();
 
     CLIPBOARD_CLEAR_CALLBACK.with(|cell| {
-        *cell.borrow_mut() = Some(Box::new(move || {
+        *cell.borrow_mut() = Some(Box::new(move |view_id| {
             lua_clone
                 .registry_value::<LuaFunction>(&callback_key)
-                .and_then(|func| func.call::<bool>(()))
+                .and_then(|func| {
+                    func.call::<bool>(view_id.and_then(|id| view_userdata(&lua_clone, id)))
+                })
                 .unwrap_or(true)
         }));
     });
@@ -111,3 +541,92 @@ pub fn clipboard_on_clear(lua: &Lua, callback: LuaFunction) -> LuaResult<()> {
     lua.expire_registry_values();
     Ok(())
 }
+
+pub fn clipboard_set_mode(mode: &str) -> LuaResult<()> {
+    let mode = ClipboardMode::parse(mode)?;
+    CLIPBOARD_MODE.with(|cell| cell.set(mode));
+    Ok(())
+}
+
+pub fn clipboard_get_mode() -> &'static str {
+    CLIPBOARD_MODE.with(|cell| cell.get()).name()
+}
+
+/// Reads the clipboard from Lua, which isn't bound by view policies.
+pub fn clipboard_get_text() -> Option<String> {
+    read_text()
+}
+
+pub fn clipboard_set_text(text: &str) {
+    write_text(text);
+}
+
+fn require_memory_mode(format: &str) -> LuaResult<()> {
+    if CLIPBOARD_MODE.with(|cell| cell.get()) == ClipboardMode::Memory {
+        return Ok(());
+    }
+    Err(LuaError::external(format!(
+        "clipboard format '{}' needs the 'memory' clipboard mode",
+        format
+    )))
+}
+
+pub fn clipboard_get_data(format: &str) -> LuaResult<Option<Vec<u8>>> {
+    if format == TEXT_FORMAT {
+        return Ok(read_text().map(String::into_bytes));
+    }
+    require_memory_mode(format)?;
+    Ok(MEMORY_CLIPBOARD.with(|cell| cell.borrow().get(format).cloned()))
+}
+
+/// Stores `data` under `format`, next to the formats already on the clipboard.
+pub fn clipboard_set_data(format: &str, data: Option<Vec<u8>>) -> LuaResult<()> {
+    if format == TEXT_FORMAT && CLIPBOARD_MODE.with(|cell| cell.get()) != ClipboardMode::Memory {
+        write_text(&String::from_utf8_lossy(&data.unwrap_or_default()));
+        return Ok(());
+    }
+    require_memory_mode(format)?;
+    MEMORY_CLIPBOARD.with(|cell| {
+        let mut formats = cell.borrow_mut();
+        match data {
+            Some(data) => formats.insert(format.to_string(), data),
+            None => formats.remove(format),
+        }
+    });
+    Ok(())
+}
+
+pub fn clipboard_get_formats() -> Vec<String> {
+    match CLIPBOARD_MODE.with(|cell| cell.get()) {
+        ClipboardMode::Memory => {
+            MEMORY_CLIPBOARD.with(|cell| cell.borrow().keys().cloned().collect())
+        }
+        _ => read_text()
+            .filter(|text| !text.is_empty())
+            .map(|_| vec![TEXT_FORMAT.to_string()])
+            .unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn policies_combine_to_the_strictest() {
+        use ClipboardPolicy::*;
+
+        assert_eq!(Allow.intersect(ReadOnly), ReadOnly);
+        assert_eq!(ReadOnly.intersect(WriteOnly), Deny);
+        assert_eq!(WriteOnly.intersect(Allow), WriteOnly);
+
+        let store = ViewClipboard::new(1);
+        let game = ViewClipboard::new(2);
+        store.set_policy(Deny);
+        game.set_policy(ReadOnly);
+        assert_eq!(current_policy(), Deny);
+
+        drop(store);
+        assert_eq!(current_policy(), ReadOnly);
+    }
+}
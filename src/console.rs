@@ -0,0 +1,187 @@
+use mlua::prelude::*;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use ul_next::view::{ConsoleMessageLevel, ConsoleMessageSource};
+
+// forwards every page's console output to Lua's `print`
+static CONSOLE_MIRROR: AtomicBool = AtomicBool::new(false);
+
+pub fn source_name(source: ConsoleMessageSource) -> &'static str {
+    match source {
+        ConsoleMessageSource::XML => "xml",
+        ConsoleMessageSource::JS => "javascript",
+        ConsoleMessageSource::Network => "network",
+        ConsoleMessageSource::ConsoleAPI => "console-api",
+        ConsoleMessageSource::Storage => "storage",
+        ConsoleMessageSource::AppCache => "app-cache",
+        ConsoleMessageSource::Rendering => "rendering",
+        ConsoleMessageSource::CSS => "css",
+        ConsoleMessageSource::Security => "security",
+        ConsoleMessageSource::ContentBlocker => "content-blocker",
+        ConsoleMessageSource::Media => "media",
+        ConsoleMessageSource::MediaSource => "media-source",
+        ConsoleMessageSource::WebRTC => "webrtc",
+        ConsoleMessageSource::ITPDebug => "itp-debug",
+        ConsoleMessageSource::PrivateClickMeasurement => "private-click-measurement",
+        ConsoleMessageSource::PaymentRequest => "payment-request",
+        _ => "other",
+    }
+}
+
+pub fn level_name(level: ConsoleMessageLevel) -> &'static str {
+    match level {
+        ConsoleMessageLevel::Log => "log",
+        ConsoleMessageLevel::Warning => "warning",
+        ConsoleMessageLevel::Error => "error",
+        ConsoleMessageLevel::Debug => "debug",
+        ConsoleMessageLevel::Info => "info",
+    }
+}
+
+#[derive(Clone)]
+pub struct ConsoleMessage {
+    pub source: &'static str,
+    pub level: &'static str,
+    pub message: String,
+    pub line: u32,
+    pub column: u32,
+    pub source_id: String,
+}
+
+impl ConsoleMessage {
+    pub fn to_lua_table(&self, lua: &Lua) -> LuaResult<LuaTable> {
+        let table = lua.create_table()?;
+        table.set("source", self.source)?;
+        table.set("level", self.level)?;
+        table.set("message", self.message.as_str())?;
+        table.set("line", self.line)?;
+        table.set("column", self.column)?;
+        table.set("sourceId", self.source_id.as_str())?;
+        Ok(table)
+    }
+
+    /// Prints the message through Lua's `print`, which Love2D sends to its console.
+    fn mirror(&self, lua: &Lua) {
+        let location = match (self.source_id.is_empty(), self.line) {
+            (true, _) => String::new(),
+            (false, 0) => format!("{}: ", self.source_id),
+            (false, line) => format!("{}:{}: ", self.source_id, line),
+        };
+
+        if let Ok(print) = lua.globals().get::<LuaFunction>("print") {
+            let _ = print.call::<()>(format!(
+                "[console {}] {}{}",
+                self.level, location, self.message
+            ));
+        }
+    }
+}
+
+/// Console messages of one view: the Lua callback and the last messages logged.
+#[derive(Default)]
+pub struct ConsoleCapture {
+    pub callback: Option<LuaRegistryKey>,
+    history: VecDeque<ConsoleMessage>,
+    history_size: usize,
+}
+
+impl ConsoleCapture {
+    pub fn history(&self) -> impl Iterator<Item = &ConsoleMessage> {
+        self.history.iter()
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    /// Keeps up to `size` messages, `0` turns the history off.
+    pub fn set_history_size(&mut self, size: usize) {
+        self.history_size = size;
+        while self.history.len() > size {
+            self.history.pop_front();
+        }
+    }
+
+    pub fn record(&mut self, message: &ConsoleMessage) {
+        if self.history_size == 0 {
+            return;
+        }
+        if self.history.len() == self.history_size {
+            self.history.pop_front();
+        }
+        self.history.push_back(message.clone());
+    }
+}
+
+/// Hands a message to the history, the mirror and the Lua callback.
+pub fn dispatch_console_message(
+    lua: &Lua,
+    capture: &RefCell<ConsoleCapture>,
+    message: ConsoleMessage,
+) {
+    // don't keep the capture borrowed while Lua runs, the callback may read the history
+    let callback = {
+        let mut capture = capture.borrow_mut();
+        capture.record(&message);
+        capture
+            .callback
+            .as_ref()
+            .and_then(|key| lua.registry_value::<LuaFunction>(key).ok())
+    };
+
+    if CONSOLE_MIRROR.load(Ordering::Relaxed) {
+        message.mirror(lua);
+    }
+
+    if let Some(callback) = callback {
+        let _ = callback.call::<()>((
+            message.source,
+            message.level,
+            message.message,
+            message.line,
+            message.column,
+            message.source_id,
+        ));
+    }
+}
+
+pub fn console_set_mirror(enabled: bool) {
+    CONSOLE_MIRROR.store(enabled, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(text: &str) -> ConsoleMessage {
+        ConsoleMessage {
+            source: "console-api",
+            level: "log",
+            message: text.to_string(),
+            line: 1,
+            column: 1,
+            source_id: "app.js".to_string(),
+        }
+    }
+
+    #[test]
+    fn history_keeps_the_last_messages() {
+        let mut capture = ConsoleCapture::default();
+        capture.record(&message("ignored"));
+        assert_eq!(capture.history().count(), 0);
+
+        capture.set_history_size(2);
+        for text in ["a", "b", "c"] {
+            capture.record(&message(text));
+        }
+        let texts: Vec<_> = capture.history().map(|m| m.message.as_str()).collect();
+        assert_eq!(texts, ["b", "c"]);
+
+        capture.set_history_size(1);
+        let texts: Vec<_> = capture.history().map(|m| m.message.as_str()).collect();
+        assert_eq!(texts, ["c"]);
+    }
+}
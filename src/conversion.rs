@@ -0,0 +1,1452 @@
+use mlua::UserData;
+use mlua::prelude::*;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::c_void,
+    fmt,
+    mem::ManuallyDrop,
+    ops::Deref,
+    rc::{Rc, Weak},
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+use ul_next::View;
+use ul_next::javascript::{
+    AsJSValue, JSContext, JSObject, JSPropertyAttributes, JSProtectedValue, JSTypedArray,
+    JSTypedArrayType, JSValue,
+};
+
+use crate::proxy::proxied_table;
+
+const TABLE_KINDS_REGISTRY_KEY: &str = "love_ultralight.table_kinds";
+
+/// Largest integer that a JavaScript number can represent exactly (`Number.MAX_SAFE_INTEGER`).
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Tables with integer keys are converted to arrays as long as they are not
+/// more sparse than this ratio (highest index / number of items)...
+const SPARSE_ARRAY_RATIO: i64 = 2;
+/// ...or their highest index is below this value.
+const SPARSE_ARRAY_SAFE: i64 = 10;
+
+/// How typed arrays and `ArrayBuffer`s coming from JavaScript are represented in Lua.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum BinaryFormat {
+    #[default]
+    String,
+    ByteData,
+}
+
+/// How JavaScript `null` is represented in Lua.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum NullFormat {
+    /// `null` and `undefined` both become `nil`, `nil` becomes `null`.
+    #[default]
+    Nil,
+    /// `null` becomes `ultralight.null`, `nil` becomes `undefined`.
+    Sentinel,
+}
+
+/// How JavaScript `Date` objects are represented in Lua.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum DateFormat {
+    /// Seconds since the Unix epoch, as used by `os.time`.
+    #[default]
+    Timestamp,
+    /// A table in the format of `os.date("!*t")`, with an extra `ms` field.
+    Table,
+}
+
+#[derive(Clone)]
+pub struct ConversionOptions {
+    pub binary_format: BinaryFormat,
+    pub null_format: NullFormat,
+    pub date_format: DateFormat,
+    pub max_depth: usize,
+}
+
+impl Default for ConversionOptions {
+    fn default() -> Self {
+        ConversionOptions {
+            binary_format: BinaryFormat::default(),
+            null_format: NullFormat::default(),
+            date_format: DateFormat::default(),
+            max_depth: 64,
+        }
+    }
+}
+
+thread_local! {
+    static CONVERSION_OPTIONS: RefCell<ConversionOptions> = RefCell::new(ConversionOptions::default());
+    // the Lua side of every function given to JavaScript, only touched on the main thread
+    static LUA_FUNCTIONS: RefCell<HashMap<u64, LuaFunctionEntry>> = RefCell::new(HashMap::new());
+}
+
+static NEXT_FUNCTION_ID: AtomicU64 = AtomicU64::new(1);
+// functions finalized by the garbage collector, possibly on another thread
+static FINALIZED_FUNCTIONS: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+fn parse_option<T>(options: &LuaTable, name: &str, values: &[(&str, T)]) -> LuaResult<Option<T>>
+where
+    T: Copy,
+{
+    let Some(value) = options.get::<Option<String>>(name)? else {
+        return Ok(None);
+    };
+
+    values
+        .iter()
+        .find(|(key, _)| *key == value)
+        .map(|(_, option)| Some(*option))
+        .ok_or_else(|| {
+            let expected: Vec<_> = values.iter().map(|(key, _)| format!("'{}'", key)).collect();
+            LuaError::external(format!(
+                "Invalid {} option '{}', expected {}",
+                name,
+                value,
+                expected.join(" or ")
+            ))
+        })
+}
+
+pub fn set_conversion_options(options: LuaTable) -> LuaResult<()> {
+    let binary_format = parse_option(
+        &options,
+        "binary",
+        &[
+            ("string", BinaryFormat::String),
+            ("bytedata", BinaryFormat::ByteData),
+        ],
+    )?;
+    let null_format = parse_option(
+        &options,
+        "null",
+        &[("nil", NullFormat::Nil), ("sentinel", NullFormat::Sentinel)],
+    )?;
+    let date_format = parse_option(
+        &options,
+        "date",
+        &[
+            ("timestamp", DateFormat::Timestamp),
+            ("table", DateFormat::Table),
+        ],
+    )?;
+    let max_depth = options.get::<Option<usize>>("maxDepth")?;
+
+    CONVERSION_OPTIONS.with(|cell| {
+        let mut conversion_options = cell.borrow_mut();
+        if let Some(binary_format) = binary_format {
+            conversion_options.binary_format = binary_format;
+        }
+        if let Some(null_format) = null_format {
+            conversion_options.null_format = null_format;
+        }
+        if let Some(date_format) = date_format {
+            conversion_options.date_format = date_format;
+        }
+        if let Some(max_depth) = max_depth {
+            conversion_options.max_depth = max_depth;
+        }
+    });
+
+    Ok(())
+}
+
+/// Marks a Lua table to be converted to a JavaScript array or object,
+/// regardless of its keys.
+pub fn mark_table(lua: &Lua, table: &LuaTable, kind: &str) -> LuaResult<()> {
+    let kinds = match lua.named_registry_value::<Option<LuaTable>>(TABLE_KINDS_REGISTRY_KEY)? {
+        Some(kinds) => kinds,
+        None => {
+            let kinds = lua.create_table()?;
+            let metatable = lua.create_table()?;
+            metatable.set("__mode", "k")?;
+            kinds.set_metatable(Some(metatable))?;
+            lua.set_named_registry_value(TABLE_KINDS_REGISTRY_KEY, &kinds)?;
+            kinds
+        }
+    };
+
+    kinds.set(table, kind)
+}
+
+fn table_kind(lua: &Lua, table: &LuaTable) -> LuaResult<Option<String>> {
+    match lua.named_registry_value::<Option<LuaTable>>(TABLE_KINDS_REGISTRY_KEY)? {
+        Some(kinds) => kinds.get(table),
+        None => Ok(None),
+    }
+}
+
+/// Identifies the page a JS value was received from.
+///
+/// The view bumps its page generation whenever the main frame starts loading,
+/// so handles kept in Lua can tell that their page is gone.
+#[derive(Clone)]
+pub struct PageContext {
+    current: Rc<Cell<u64>>,
+    generation: u64,
+    // the view showing the page, empty for script contexts
+    view: Weak<View>,
+}
+
+impl PageContext {
+    pub fn new(current: &Rc<Cell<u64>>) -> Self {
+        Self::with_view(current, &Weak::new())
+    }
+
+    pub fn with_view(current: &Rc<Cell<u64>>, view: &Weak<View>) -> Self {
+        PageContext {
+            current: current.clone(),
+            generation: current.get(),
+            view: view.clone(),
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.current.get() == self.generation
+    }
+
+    /// Identifies the page, for maps keyed by page.
+    pub fn key(&self) -> (*const Cell<u64>, u64) {
+        (Rc::as_ptr(&self.current), self.generation)
+    }
+
+    /// Runs `f` while holding the JS lock of the page's view.
+    ///
+    /// Script contexts have no view, JavaScriptCore locks their calls by itself.
+    pub fn with_js_lock<R>(&self, f: impl FnOnce() -> R) -> R {
+        let view = self.view.upgrade();
+        let _lock = view.as_ref().map(|view| view.lock_js_context());
+        f()
+    }
+}
+
+/// A JS value kept alive for Lua, released under the JS lock of its page.
+pub struct PageValue {
+    value: ManuallyDrop<JSProtectedValue>,
+    page: PageContext,
+}
+
+impl PageValue {
+    pub fn new(value: &JSValue, page: &PageContext) -> Self {
+        PageValue {
+            value: ManuallyDrop::new(JSProtectedValue::new(value)),
+            page: page.clone(),
+        }
+    }
+
+    pub fn page(&self) -> &PageContext {
+        &self.page
+    }
+}
+
+impl Deref for PageValue {
+    type Target = JSProtectedValue;
+
+    fn deref(&self) -> &JSProtectedValue {
+        &self.value
+    }
+}
+
+impl Drop for PageValue {
+    fn drop(&mut self) {
+        // SAFETY: the value is never used again
+        self.page
+            .with_js_lock(|| unsafe { ManuallyDrop::drop(&mut self.value) });
+    }
+}
+
+/// Binary data that is passed to JavaScript as a `Uint8Array`.
+///
+/// Created from Lua with `ultralight.bytes(data)`.
+pub struct LuaBytes(pub Vec<u8>);
+
+impl UserData for LuaBytes {
+    fn add_methods<M: mlua::UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method("getString", |lua, this, ()| lua.create_string(&this.0));
+        methods.add_method("getSize", |_, this, ()| Ok(this.0.len()));
+        methods.add_meta_method(LuaMetaMethod::Len, |_, this, ()| Ok(this.0.len()));
+    }
+}
+
+/// Reads the contents of a love `Data` object (`ByteData`, `FileData`, ...).
+///
+/// Returns `None` if the userdata is not a love `Data` object.
+pub fn love_data_to_bytes(ud: &LuaAnyUserData) -> LuaResult<Option<Vec<u8>>> {
+    if !ud.call_method::<bool>("typeOf", "Data").unwrap_or(false) {
+        return Ok(None);
+    }
+
+    let data: LuaString = ud.call_method("getString", ())?;
+    Ok(Some(data.as_bytes().to_vec()))
+}
+
+pub fn lua_bytes_from_value(value: LuaValue) -> LuaResult<LuaBytes> {
+    match value {
+        LuaValue::String(s) => Ok(LuaBytes(s.as_bytes().to_vec())),
+        LuaValue::UserData(ud) => {
+            if let Ok(bytes) = ud.borrow::<LuaBytes>() {
+                return Ok(LuaBytes(bytes.0.clone()));
+            }
+
+            love_data_to_bytes(&ud)?
+                .map(LuaBytes)
+                .ok_or_else(|| LuaError::external("Expected a string or love Data object"))
+        }
+        _ => Err(LuaError::external("Expected a string or love Data object")),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionError {
+    Cycle,
+    TooDeep(usize),
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::Cycle => write!(f, "Cannot convert a value that contains a cycle"),
+            ConversionError::TooDeep(max_depth) => write!(
+                f,
+                "Cannot convert a value nested deeper than {} levels",
+                max_depth
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// The chain of tables/objects currently being converted, used to detect
+/// cycles and enforce the depth limit.
+pub struct Ancestors<T> {
+    stack: Vec<T>,
+    max_depth: usize,
+}
+
+impl<T> Ancestors<T> {
+    pub fn new(max_depth: usize) -> Self {
+        Ancestors {
+            stack: Vec::new(),
+            max_depth,
+        }
+    }
+
+    pub fn enter<F>(&mut self, item: T, is_same: F) -> Result<(), ConversionError>
+    where
+        F: Fn(&T, &T) -> bool,
+    {
+        if self.stack.iter().any(|ancestor| is_same(ancestor, &item)) {
+            return Err(ConversionError::Cycle);
+        }
+
+        if self.stack.len() >= self.max_depth {
+            return Err(ConversionError::TooDeep(self.max_depth));
+        }
+
+        self.stack.push(item);
+        Ok(())
+    }
+
+    pub fn leave(&mut self) {
+        self.stack.pop();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableKey {
+    Index(i64),
+    Other,
+}
+
+impl TableKey {
+    fn from_lua(key: &LuaValue) -> Self {
+        match key {
+            LuaValue::Integer(i) => TableKey::Index(*i),
+            LuaValue::Number(n) if n.fract() == 0.0 && n.abs() < i64::MAX as f64 => {
+                TableKey::Index(*n as i64)
+            }
+            _ => TableKey::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableShape {
+    /// An array of the given length, missing indices are holes.
+    Array(usize),
+    Object,
+}
+
+/// Decides whether a table with the given keys is converted to an array or an object.
+///
+/// Empty tables and tables with only positive integer keys become arrays,
+/// unless they are too sparse.
+pub fn classify_table_keys<I>(keys: I) -> TableShape
+where
+    I: IntoIterator<Item = TableKey>,
+{
+    let mut count = 0;
+    let mut max_index = 0;
+
+    for key in keys {
+        match key {
+            TableKey::Index(i) if i >= 1 => {
+                count += 1;
+                max_index = max_index.max(i);
+            }
+            _ => return TableShape::Object,
+        }
+    }
+
+    if max_index <= SPARSE_ARRAY_SAFE || max_index <= count * SPARSE_ARRAY_RATIO {
+        TableShape::Array(max_index as usize)
+    } else {
+        TableShape::Object
+    }
+}
+
+/// Returns `true` if the integer can be represented exactly as a JavaScript number.
+pub fn is_safe_integer(value: i64) -> bool {
+    value.unsigned_abs() <= MAX_SAFE_INTEGER
+}
+
+/// A JavaScript `BigInt` converted to Lua.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BigIntValue {
+    Integer(i64),
+    /// Decimal digits, for values that do not fit in a Lua integer.
+    Digits(String),
+}
+
+pub fn parse_bigint(digits: &str) -> BigIntValue {
+    match digits.parse::<i64>() {
+        Ok(value) => BigIntValue::Integer(value),
+        Err(_) => BigIntValue::Digits(digits.to_string()),
+    }
+}
+
+/// A UTC date split into the fields used by `os.date("!*t")`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DateParts {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub min: u32,
+    pub sec: u32,
+    pub ms: u32,
+    /// Day of the week, Sunday is 1.
+    pub wday: u32,
+    /// Day of the year, January 1st is 1.
+    pub yday: u32,
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Splits a JavaScript timestamp (milliseconds since the Unix epoch) into UTC date fields.
+///
+/// Returns `None` for invalid dates.
+pub fn date_parts_from_timestamp(timestamp: f64) -> Option<DateParts> {
+    if !timestamp.is_finite() {
+        return None;
+    }
+
+    let timestamp = timestamp.floor() as i64;
+    let days = timestamp.div_euclid(86_400_000);
+    let ms_of_day = timestamp.rem_euclid(86_400_000);
+
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    const DAYS_BEFORE_MONTH: [u32; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    let leap_day = if month > 2 && is_leap_year(year) {
+        1
+    } else {
+        0
+    };
+    let yday = DAYS_BEFORE_MONTH[(month - 1) as usize] + day as u32 + leap_day;
+
+    Some(DateParts {
+        year,
+        month: month as u32,
+        day: day as u32,
+        hour: (ms_of_day / 3_600_000) as u32,
+        min: (ms_of_day / 60_000 % 60) as u32,
+        sec: (ms_of_day / 1000 % 60) as u32,
+        ms: (ms_of_day % 1000) as u32,
+        // 1970-01-01 was a Thursday
+        wday: ((days + 4).rem_euclid(7) + 1) as u32,
+        yday,
+    })
+}
+
+/// Converts a UTC date to a JavaScript timestamp (milliseconds since the Unix epoch).
+///
+/// Like `os.time`, out of range months and days are normalized,
+/// `time_ms` is the time of day in milliseconds and can be negative or exceed a day.
+pub fn timestamp_from_date(year: i64, month: i64, day: i64, time_ms: i64) -> f64 {
+    let year = year + (month - 1).div_euclid(12);
+    let month = (month - 1).rem_euclid(12) + 1;
+
+    // https://howardhinnant.github.io/date_algorithms.html#days_from_civil
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+
+    (days * 86_400_000 + time_ms) as f64
+}
+
+/// Creates a table in the format of `os.date("!*t")` marked as a date,
+/// so it is converted back to a JavaScript `Date`.
+fn date_table(lua: &Lua, parts: &DateParts) -> LuaResult<LuaTable> {
+    let tbl = lua.create_table()?;
+    tbl.set("year", parts.year)?;
+    tbl.set("month", parts.month)?;
+    tbl.set("day", parts.day)?;
+    tbl.set("hour", parts.hour)?;
+    tbl.set("min", parts.min)?;
+    tbl.set("sec", parts.sec)?;
+    tbl.set("ms", parts.ms)?;
+    tbl.set("wday", parts.wday)?;
+    tbl.set("yday", parts.yday)?;
+    tbl.set("isdst", false)?;
+
+    mark_table(lua, &tbl, "date")?;
+    Ok(tbl)
+}
+
+/// Creates a date table from seconds since the Unix epoch, or marks an existing
+/// date table, so that it is converted to a JavaScript `Date`.
+pub fn lua_date(lua: &Lua, value: LuaValue) -> LuaResult<LuaTable> {
+    match value {
+        LuaValue::Table(tbl) => {
+            mark_table(lua, &tbl, "date")?;
+            Ok(tbl)
+        }
+        LuaValue::Integer(_) | LuaValue::Number(_) => {
+            let seconds = lua.unpack::<f64>(value)?;
+            let parts = date_parts_from_timestamp(seconds * 1000.0)
+                .ok_or_else(|| LuaError::external("Invalid date"))?;
+            date_table(lua, &parts)
+        }
+        _ => Err(LuaError::external("Expected a number or a date table")),
+    }
+}
+
+pub fn js_error_to_string(error: &JSValue) -> String {
+    error
+        .as_string()
+        .map(|s| s.to_string())
+        .unwrap_or_else(|_| "Unknown JavaScript error".to_string())
+}
+
+/// Where a script passed from Lua comes from, used in exceptions and console messages.
+pub struct ScriptSource {
+    pub source_url: Option<String>,
+    /// 1-based line number of the first line of the script.
+    pub start_line: i32,
+}
+
+impl ScriptSource {
+    pub fn new(source_url: Option<String>) -> Self {
+        ScriptSource {
+            source_url,
+            start_line: 1,
+        }
+    }
+
+    /// Reads the `sourceUrl` and `startLine` fields of an options table.
+    pub fn from_options(options: Option<LuaTable>) -> LuaResult<Self> {
+        let Some(options) = options else {
+            return Ok(ScriptSource::new(None));
+        };
+
+        Ok(ScriptSource {
+            source_url: options.get("sourceUrl")?,
+            start_line: options.get::<Option<i32>>("startLine")?.unwrap_or(1),
+        })
+    }
+}
+
+/// Details of a JavaScript exception.
+pub struct JSExceptionInfo {
+    pub message: String,
+    pub source_url: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+}
+
+impl JSExceptionInfo {
+    pub fn new(error: &JSValue) -> Self {
+        let mut info = JSExceptionInfo {
+            message: js_error_to_string(error),
+            source_url: None,
+            line: None,
+            column: None,
+        };
+
+        if error.is_object()
+            && let Ok(obj) = error.as_object()
+        {
+            let number = |name: &str| {
+                obj.get_property(name)
+                    .ok()
+                    .filter(|value| value.is_number())
+                    .and_then(|value| value.as_number().ok())
+                    .map(|value| value as u32)
+            };
+
+            info.line = number("line");
+            info.column = number("column");
+            info.source_url = obj
+                .get_property("sourceURL")
+                .ok()
+                .filter(|value| value.is_string())
+                .and_then(|value| value.as_string().ok())
+                .map(|value| value.to_string());
+        }
+
+        info
+    }
+}
+
+impl fmt::Display for JSExceptionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.source_url, self.line) {
+            (Some(source_url), Some(line)) => {
+                write!(f, "{}:{}: {}", source_url, line, self.message)
+            }
+            (None, Some(line)) => write!(f, "line {}: {}", line, self.message),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+fn js_error(error: JSValue) -> LuaError {
+    LuaError::external(js_error_to_string(&error))
+}
+
+pub fn lua_error_to_js_exception<'a>(
+    js_ctx: &'a JSContext,
+    error: LuaError,
+) -> Result<JSValue<'a>, JSValue<'a>> {
+    match JSObject::new_error(js_ctx, JSValue::new_string(js_ctx, &error.to_string())) {
+        Ok(e_obj) => Err(e_obj.into_value()),
+        Err(_) => Ok(JSValue::new_undefined(js_ctx)),
+    }
+}
+
+/// Converts a Lua error to a JS `Error` object, to be thrown or used as a rejection reason.
+pub fn lua_error_to_js_error<'a>(js_ctx: &'a JSContext, error: LuaError) -> JSValue<'a> {
+    match lua_error_to_js_exception(js_ctx, error) {
+        Ok(value) | Err(value) => value,
+    }
+}
+
+/// Calls a Lua function from JavaScript, see [`call_lua_function`].
+pub type LuaCall = for<'a> fn(
+    &Lua,
+    &LuaFunction,
+    &'a JSContext,
+    &[JSValue],
+    &PageContext,
+) -> LuaResult<JSValue<'a>>;
+
+struct LuaFunctionEntry {
+    lua: WeakLua,
+    function: LuaRegistryKey,
+    // the page arguments are received from, looked up on each call
+    page: Box<dyn Fn() -> PageContext>,
+    call: LuaCall,
+}
+
+/// Owned by a JavaScript function, queues its Lua side for release once the
+/// function is finalized.
+struct LuaFunctionId(u64);
+
+impl Drop for LuaFunctionId {
+    fn drop(&mut self) {
+        FINALIZED_FUNCTIONS.lock().unwrap().push(self.0);
+    }
+}
+
+/// Creates a JavaScript function calling `func` through `call`.
+///
+/// The JavaScript function itself only holds an id, the Lua function stays on
+/// the main thread until [`function_update`] releases it.
+pub fn new_lua_function<'a>(
+    lua: &Lua,
+    js_ctx: &'a JSContext,
+    func: LuaFunction,
+    page: impl Fn() -> PageContext + 'static,
+    call: LuaCall,
+) -> LuaResult<JSObject<'a>> {
+    let entry = LuaFunctionEntry {
+        lua: lua.weak(),
+        function: lua.create_registry_value(func)?,
+        page: Box::new(page),
+        call,
+    };
+    let id = NEXT_FUNCTION_ID.fetch_add(1, Ordering::Relaxed);
+    LUA_FUNCTIONS.with(|functions| functions.borrow_mut().insert(id, entry));
+    let id = LuaFunctionId(id);
+
+    Ok(JSObject::new_function_with_callback(
+        js_ctx,
+        move |js_ctx, _this, args| {
+            call_lua_function_by_id(id.0, js_ctx, args)
+                .or_else(|e| lua_error_to_js_exception(js_ctx, e))
+        },
+    ))
+}
+
+fn call_lua_function_by_id<'a>(
+    id: u64,
+    js_ctx: &'a JSContext,
+    args: &[JSValue],
+) -> LuaResult<JSValue<'a>> {
+    // not borrowed during the call, which may create more functions
+    let (lua, func, page, call) = LUA_FUNCTIONS.with(|functions| {
+        let functions = functions.borrow();
+        let entry = functions
+            .get(&id)
+            .ok_or_else(|| LuaError::external("Lua function was released"))?;
+        let lua = entry
+            .lua
+            .try_upgrade()
+            .ok_or_else(|| LuaError::external("Lua state is gone"))?;
+        let func = lua.registry_value::<LuaFunction>(&entry.function)?;
+        LuaResult::Ok((lua, func, (entry.page)(), entry.call))
+    })?;
+
+    call(&lua, &func, js_ctx, args, &page)
+}
+
+/// Releases the Lua side of functions the garbage collector finalized.
+pub fn function_update() {
+    let finalized = std::mem::take(&mut *FINALIZED_FUNCTIONS.lock().unwrap());
+    LUA_FUNCTIONS.with(|functions| {
+        let mut functions = functions.borrow_mut();
+        for id in finalized {
+            functions.remove(&id);
+        }
+    });
+}
+
+/// Calls a Lua function with JS arguments, and converts its results back to
+/// a single JS value.
+///
+/// Multiple results are returned to JavaScript as an array.
+pub fn call_lua_function<'a>(
+    lua: &Lua,
+    func: &LuaFunction,
+    js_ctx: &'a JSContext,
+    args: &[JSValue],
+    page: &PageContext,
+) -> Result<JSValue<'a>, LuaError> {
+    let converter = Converter::new(lua, page);
+
+    let mut lua_args = LuaMultiValue::new();
+    for arg in args {
+        lua_args.push_back(converter.js_to_lua(js_ctx, arg)?);
+    }
+
+    let result = func
+        .call::<mlua::MultiValue>(lua_args)
+        .map_err(|e| mlua::Error::external(e.to_string()))?;
+
+    let mut js_values = Vec::with_capacity(result.len());
+    for val in result {
+        js_values.push(converter.lua_to_js(js_ctx, val)?);
+    }
+
+    match js_values.len() {
+        0 => Ok(JSValue::new_undefined(js_ctx)),
+        1 => Ok(js_values.remove(0)),
+        _ => JSObject::new_array(js_ctx, &js_values)
+            .map(|js_array| js_array.into_value())
+            .map_err(js_error),
+    }
+}
+
+/// Calls a Lua function as `callback(resolve, reject, ...)`,
+/// and returns a promise that is settled when Lua calls `resolve` or `reject`.
+///
+/// Errors raised by the callback itself reject the promise.
+pub fn call_lua_async_function<'a>(
+    lua: &Lua,
+    func: &LuaFunction,
+    js_ctx: &'a JSContext,
+    args: &[JSValue],
+    page: &PageContext,
+) -> Result<JSValue<'a>, LuaError> {
+    let deferred = JSObject::new_deferred_promise(js_ctx).map_err(js_error)?;
+    let settled = Rc::new(Cell::new(false));
+    let converter = Converter::new(lua, page);
+
+    let mut lua_args = LuaMultiValue::new();
+    lua_args.push_back(promise_settler(lua, page, &deferred.resolve, &settled)?);
+    lua_args.push_back(promise_settler(lua, page, &deferred.reject, &settled)?);
+    for arg in args {
+        lua_args.push_back(converter.js_to_lua(js_ctx, arg)?);
+    }
+
+    if let Err(e) = func.call::<()>(lua_args)
+        && !settled.replace(true)
+    {
+        let reason = lua_error_to_js_error(js_ctx, LuaError::external(e.to_string()));
+        deferred
+            .reject
+            .call_as_function(None, &[reason])
+            .map_err(js_error)?;
+    }
+
+    Ok(deferred.promise.into_value())
+}
+
+/// Wraps the `resolve` or `reject` function of a promise into a Lua function.
+///
+/// Only the first call settles the promise, later calls and calls made after
+/// the page was unloaded are ignored.
+fn promise_settler(
+    lua: &Lua,
+    page: &PageContext,
+    function: &JSObject,
+    settled: &Rc<Cell<bool>>,
+) -> LuaResult<LuaValue> {
+    let function = PageValue::new(function, page);
+    let settled = settled.clone();
+
+    let lua_func = lua.create_function(move |lua, value: LuaValue| {
+        let page = &function.page;
+        if settled.replace(true) || !page.is_alive() {
+            return Ok(());
+        }
+
+        page.with_js_lock(|| {
+            let js_ctx = function.context();
+            let value = Converter::new(lua, page).lua_to_js(js_ctx, value)?;
+            let func = function.value().as_object().map_err(js_error)?;
+
+            func.call_as_function(None, &[value]).map_err(js_error)?;
+            Ok(())
+        })
+    })?;
+
+    Ok(LuaValue::Function(lua_func))
+}
+
+/// Converts values between Lua and JavaScript, following the current conversion options.
+pub struct Converter<'l> {
+    lua: &'l Lua,
+    page: PageContext,
+    options: ConversionOptions,
+}
+
+impl<'l> Converter<'l> {
+    pub fn new(lua: &'l Lua, page: &PageContext) -> Self {
+        Converter {
+            lua,
+            page: page.clone(),
+            options: CONVERSION_OPTIONS.with(|cell| cell.borrow().clone()),
+        }
+    }
+
+    pub fn js_to_lua(&self, js_ctx: &JSContext, value: &JSValue) -> LuaResult<LuaValue> {
+        let mut ancestors = Ancestors::new(self.options.max_depth);
+        self.js_to_lua_inner(js_ctx, value, &mut ancestors)
+    }
+
+    pub fn lua_to_js<'a>(&self, js_ctx: &'a JSContext, value: LuaValue) -> LuaResult<JSValue<'a>> {
+        let mut ancestors = Ancestors::new(self.options.max_depth);
+        self.lua_to_js_inner(js_ctx, value, &mut ancestors)
+    }
+
+    /// Brand checks `object` with `Object.prototype.toString`, reached through a
+    /// new object so pages replacing the global `Error` or `Object` don't change
+    /// the answer. Anything failing along the way counts as not an error.
+    fn is_error(&self, js_ctx: &JSContext, object: &JSObject) -> bool {
+        let tag = JSObject::new(js_ctx)
+            .get_property("toString")
+            .and_then(|to_string| to_string.as_object())
+            .and_then(|to_string| to_string.call_as_function(Some(object), &[]))
+            .and_then(|tag| tag.as_string());
+
+        matches!(tag, Ok(tag) if tag.to_string() == "[object Error]")
+    }
+
+    fn null_to_lua(&self) -> LuaValue {
+        match self.options.null_format {
+            NullFormat::Nil => LuaValue::Nil,
+            NullFormat::Sentinel => LuaValue::NULL,
+        }
+    }
+
+    fn nil_to_js<'a>(&self, js_ctx: &'a JSContext) -> JSValue<'a> {
+        match self.options.null_format {
+            NullFormat::Nil => JSValue::new_null(js_ctx),
+            NullFormat::Sentinel => JSValue::new_undefined(js_ctx),
+        }
+    }
+
+    fn js_to_lua_inner<'a>(
+        &self,
+        js_ctx: &'a JSContext,
+        value: &JSValue<'a>,
+        ancestors: &mut Ancestors<JSValue<'a>>,
+    ) -> LuaResult<LuaValue> {
+        if value.is_undefined() {
+            Ok(LuaValue::Nil)
+        } else if value.is_null() {
+            Ok(self.null_to_lua())
+        } else if value.is_boolean() {
+            Ok(LuaValue::Boolean(value.as_boolean()))
+        } else if value.is_number() {
+            Ok(LuaValue::Number(value.as_number().unwrap_or(0.0)))
+        } else if value.is_string() {
+            let string = value.as_string().map_err(js_error)?;
+            Ok(LuaValue::String(
+                self.lua.create_string(string.to_string())?,
+            ))
+        } else if value.is_typed_array() {
+            let typed_array = value.as_typed_array().map_err(js_error)?;
+            let bytes = typed_array.bytes().map_err(js_error)?;
+            self.bytes_to_lua(bytes)
+        } else if value.is_date() {
+            self.date_to_lua(value)
+        } else if value.is_object() {
+            let obj = value.as_object().map_err(js_error)?;
+            if obj.is_function() {
+                return self.js_function_to_lua(&obj);
+            }
+
+            if let Some(table) = proxied_table(self.lua, &obj)? {
+                return Ok(LuaValue::Table(table));
+            }
+
+            if self.is_error(js_ctx, &obj) {
+                return self.js_error_object_to_lua(&obj);
+            }
+
+            ancestors
+                .enter(value.clone(), |a, b| a.is_strict_equal(b))
+                .map_err(LuaError::external)?;
+            let result = if value.is_array() {
+                self.js_array_to_lua(js_ctx, &obj, ancestors)
+            } else {
+                self.js_object_to_lua(js_ctx, &obj, ancestors)
+            };
+            ancestors.leave();
+
+            result
+        } else if value.is_symbol() {
+            Ok(LuaValue::Nil)
+        } else {
+            // the only remaining primitive is BigInt, which the C API has no type for
+            match value.as_string() {
+                Ok(digits) => match parse_bigint(&digits.to_string()) {
+                    BigIntValue::Integer(i) => Ok(LuaValue::Integer(i)),
+                    BigIntValue::Digits(digits) => {
+                        Ok(LuaValue::String(self.lua.create_string(digits)?))
+                    }
+                },
+                Err(_) => Ok(LuaValue::Nil),
+            }
+        }
+    }
+
+    fn js_array_to_lua<'a>(
+        &self,
+        js_ctx: &'a JSContext,
+        obj: &JSObject<'a>,
+        ancestors: &mut Ancestors<JSValue<'a>>,
+    ) -> LuaResult<LuaValue> {
+        let length = obj
+            .get_property("length")
+            .and_then(|length| length.as_number())
+            .map_err(js_error)? as u32;
+
+        let tbl = self.lua.create_table_with_capacity(length as usize, 0)?;
+        for i in 0..length {
+            let item = obj.get_property_at_index(i).map_err(js_error)?;
+            // holes and `undefined` items are left as `nil`
+            tbl.raw_set(i + 1, self.js_to_lua_inner(js_ctx, &item, ancestors)?)?;
+        }
+
+        Ok(LuaValue::Table(tbl))
+    }
+
+    fn js_object_to_lua<'a>(
+        &self,
+        js_ctx: &'a JSContext,
+        obj: &JSObject<'a>,
+        ancestors: &mut Ancestors<JSValue<'a>>,
+    ) -> LuaResult<LuaValue> {
+        let tbl = self.lua.create_table()?;
+
+        let keys = obj.get_property_names();
+        for i in 0..keys.len() {
+            let Some(key) = keys.get(i) else {
+                continue;
+            };
+
+            let value = obj
+                .get_property_for_key(&JSValue::from_jsstring(js_ctx, key.clone()))
+                .map_err(js_error)?;
+            let value = self.js_to_lua_inner(js_ctx, &value, ancestors)?;
+
+            let key_str = key.to_string();
+            if let Ok(num) = key_str.parse::<i64>() {
+                tbl.raw_set(num, value)?;
+            } else {
+                tbl.raw_set(key_str, value)?;
+            }
+        }
+
+        Ok(LuaValue::Table(tbl))
+    }
+
+    fn bytes_to_lua(&self, bytes: &[u8]) -> LuaResult<LuaValue> {
+        let data = self.lua.create_string(bytes)?;
+
+        match self.options.binary_format {
+            BinaryFormat::String => Ok(LuaValue::String(data)),
+            BinaryFormat::ByteData => {
+                let love_table: LuaTable = self.lua.globals().get("love")?;
+                let data_table: LuaTable = love_table.get("data")?;
+                let new_byte_data: LuaFunction = data_table.get("newByteData")?;
+                new_byte_data.call(data)
+            }
+        }
+    }
+
+    fn date_to_lua(&self, value: &JSValue) -> LuaResult<LuaValue> {
+        let timestamp = value.as_number().map_err(js_error)?;
+
+        match self.options.date_format {
+            DateFormat::Timestamp if timestamp.is_finite() => {
+                Ok(LuaValue::Number(timestamp / 1000.0))
+            }
+            DateFormat::Timestamp => Ok(LuaValue::Nil),
+            DateFormat::Table => match date_parts_from_timestamp(timestamp) {
+                Some(parts) => date_table(self.lua, &parts).map(LuaValue::Table),
+                None => Ok(LuaValue::Nil),
+            },
+        }
+    }
+
+    fn lua_date_to_js<'a>(&self, js_ctx: &'a JSContext, tbl: &LuaTable) -> LuaResult<JSValue<'a>> {
+        let field = |name: &str| tbl.get::<Option<i64>>(name).map(|v| v.unwrap_or(0));
+
+        let time_ms = field("hour")? * 3_600_000
+            + field("min")? * 60_000
+            + field("sec")? * 1000
+            + field("ms")?;
+        let timestamp = timestamp_from_date(
+            tbl.get("year")?,
+            tbl.get("month")?,
+            tbl.get("day")?,
+            time_ms,
+        );
+
+        JSObject::new_date(js_ctx, &[JSValue::new_number(js_ctx, timestamp)])
+            .map(|date| date.into_value())
+            .map_err(js_error)
+    }
+
+    fn js_error_object_to_lua(&self, obj: &JSObject) -> LuaResult<LuaValue> {
+        let tbl = self.lua.create_table()?;
+
+        for name in ["name", "message", "stack"] {
+            let value = obj.get_property(name).map_err(js_error)?;
+            if value.is_string() {
+                let value = value.as_string().map_err(js_error)?;
+                tbl.set(name, value.to_string())?;
+            }
+        }
+
+        Ok(LuaValue::Table(tbl))
+    }
+
+    fn js_function_to_lua(&self, function: &JSObject) -> LuaResult<LuaValue> {
+        let function = PageValue::new(function, &self.page);
+
+        let lua_func = self.lua.create_function(move |lua, args: LuaMultiValue| {
+            let page = &function.page;
+            if !page.is_alive() {
+                return Err(LuaError::external(
+                    "JavaScript function called after its page was unloaded",
+                ));
+            }
+
+            page.with_js_lock(|| {
+                let converter = Converter::new(lua, page);
+                let js_ctx = function.context();
+
+                let mut js_args = Vec::with_capacity(args.len());
+                for arg in args {
+                    js_args.push(converter.lua_to_js(js_ctx, arg)?);
+                }
+
+                let func = function.value().as_object().map_err(js_error)?;
+
+                match func.call_as_function(None, &js_args) {
+                    Ok(result) => converter.js_to_lua(js_ctx, &result),
+                    Err(e) => Err(LuaError::external(format!(
+                        "JavaScript error: {}",
+                        js_error_to_string(&e)
+                    ))),
+                }
+            })
+        })?;
+
+        Ok(LuaValue::Function(lua_func))
+    }
+
+    fn lua_to_js_inner<'a>(
+        &self,
+        js_ctx: &'a JSContext,
+        value: LuaValue,
+        ancestors: &mut Ancestors<*const c_void>,
+    ) -> LuaResult<JSValue<'a>> {
+        match value {
+            LuaValue::Nil => Ok(self.nil_to_js(js_ctx)),
+            LuaValue::LightUserData(ud) if ud.0.is_null() => Ok(JSValue::new_null(js_ctx)),
+            LuaValue::Boolean(b) => Ok(JSValue::new_boolean(js_ctx, b)),
+            LuaValue::Integer(i) if is_safe_integer(i) => Ok(JSValue::new_number(js_ctx, i as f64)),
+            LuaValue::Integer(i) => new_bigint(js_ctx, i),
+            LuaValue::Number(n) => Ok(JSValue::new_number(js_ctx, n)),
+            LuaValue::String(s) => Ok(JSValue::new_string(
+                js_ctx,
+                &String::from_utf8_lossy(&s.as_bytes()),
+            )),
+            LuaValue::Function(func) => self.lua_function_to_js(js_ctx, func),
+            LuaValue::UserData(ud) => {
+                let bytes = match ud.borrow::<LuaBytes>() {
+                    Ok(bytes) => Some(bytes.0.clone()),
+                    Err(_) => love_data_to_bytes(&ud)?,
+                };
+
+                match bytes {
+                    Some(bytes) => JSTypedArray::new_copy_from_bytes(
+                        js_ctx,
+                        JSTypedArrayType::Uint8Array,
+                        &bytes,
+                    )
+                    .map(|js_array| js_array.into_value())
+                    .map_err(js_error),
+                    None => Ok(JSValue::new_undefined(js_ctx)),
+                }
+            }
+            LuaValue::Table(tbl) => {
+                ancestors
+                    .enter(tbl.to_pointer(), |a, b| a == b)
+                    .map_err(LuaError::external)?;
+                let result = self.lua_table_to_js(js_ctx, &tbl, ancestors);
+                ancestors.leave();
+
+                result
+            }
+            _ => Ok(JSValue::new_undefined(js_ctx)),
+        }
+    }
+
+    fn lua_table_to_js<'a>(
+        &self,
+        js_ctx: &'a JSContext,
+        tbl: &LuaTable,
+        ancestors: &mut Ancestors<*const c_void>,
+    ) -> LuaResult<JSValue<'a>> {
+        let mut entries = Vec::new();
+        for pair in tbl.pairs::<LuaValue, LuaValue>() {
+            entries.push(pair?);
+        }
+
+        let shape = match table_kind(self.lua, tbl)?.as_deref() {
+            Some("date") => return self.lua_date_to_js(js_ctx, tbl),
+            Some("array") => TableShape::Array(tbl.raw_len()),
+            Some("object") => TableShape::Object,
+            _ => classify_table_keys(entries.iter().map(|(key, _)| TableKey::from_lua(key))),
+        };
+
+        match shape {
+            TableShape::Array(length) => {
+                let mut items: Vec<JSValue<'a>> = Vec::with_capacity(length);
+                for i in 1..=length {
+                    let value = tbl.raw_get::<LuaValue>(i)?;
+                    items.push(self.lua_to_js_inner(js_ctx, value, ancestors)?);
+                }
+
+                JSObject::new_array(js_ctx, &items)
+                    .map(|js_array| js_array.into_value())
+                    .map_err(js_error)
+            }
+            TableShape::Object => {
+                let js_obj = JSObject::new(js_ctx);
+
+                for (key, value) in entries {
+                    let key = match key {
+                        LuaValue::String(s) => String::from_utf8_lossy(&s.as_bytes()).into_owned(),
+                        LuaValue::Integer(i) => i.to_string(),
+                        LuaValue::Number(n) => n.to_string(),
+                        other => {
+                            return Err(LuaError::external(format!(
+                                "Cannot use a {} as a JavaScript property name",
+                                other.type_name()
+                            )));
+                        }
+                    };
+
+                    let js_value = self.lua_to_js_inner(js_ctx, value, ancestors)?;
+                    js_obj
+                        .set_property(&key, &js_value, JSPropertyAttributes::default())
+                        .map_err(js_error)?;
+                }
+
+                Ok(js_obj.into_value())
+            }
+        }
+    }
+
+    fn lua_function_to_js<'a>(
+        &self,
+        js_ctx: &'a JSContext,
+        func: LuaFunction,
+    ) -> LuaResult<JSValue<'a>> {
+        // The Lua function lives as long as JS references it, until the
+        // finalizer queues it for release.
+        let page = self.page.clone();
+        let js_func = new_lua_function(
+            self.lua,
+            js_ctx,
+            func,
+            move || page.clone(),
+            call_lua_function,
+        )?;
+
+        Ok(js_func.into_value())
+    }
+}
+
+fn new_bigint<'a>(js_ctx: &'a JSContext, value: i64) -> LuaResult<JSValue<'a>> {
+    let bigint = js_ctx
+        .global_object()
+        .get_property("BigInt")
+        .and_then(|bigint| bigint.as_object())
+        .map_err(js_error)?;
+
+    bigint
+        .call_as_function(None, &[JSValue::new_string(js_ctx, &value.to_string())])
+        .map_err(js_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_empty_table_as_array() {
+        assert_eq!(classify_table_keys([]), TableShape::Array(0));
+    }
+
+    #[test]
+    fn classify_sequence_as_array() {
+        let keys = (1..=5).map(TableKey::Index);
+        assert_eq!(classify_table_keys(keys), TableShape::Array(5));
+    }
+
+    #[test]
+    fn classify_small_sparse_table_as_array_with_holes() {
+        let keys = [TableKey::Index(1), TableKey::Index(3), TableKey::Index(8)];
+        assert_eq!(classify_table_keys(keys), TableShape::Array(8));
+    }
+
+    #[test]
+    fn classify_very_sparse_table_as_object() {
+        let keys = [TableKey::Index(1), TableKey::Index(1000)];
+        assert_eq!(classify_table_keys(keys), TableShape::Object);
+    }
+
+    #[test]
+    fn classify_mixed_keys_as_object() {
+        let keys = [TableKey::Index(1), TableKey::Other];
+        assert_eq!(classify_table_keys(keys), TableShape::Object);
+    }
+
+    #[test]
+    fn classify_non_positive_indices_as_object() {
+        assert_eq!(
+            classify_table_keys([TableKey::Index(0), TableKey::Index(1)]),
+            TableShape::Object
+        );
+        assert_eq!(
+            classify_table_keys([TableKey::Index(-1)]),
+            TableShape::Object
+        );
+    }
+
+    #[test]
+    fn safe_integer_bounds() {
+        assert!(is_safe_integer(0));
+        assert!(is_safe_integer(9_007_199_254_740_991));
+        assert!(is_safe_integer(-9_007_199_254_740_991));
+        assert!(!is_safe_integer(9_007_199_254_740_992));
+        assert!(!is_safe_integer(-9_007_199_254_740_992));
+        assert!(!is_safe_integer(i64::MIN));
+        assert!(!is_safe_integer(i64::MAX));
+    }
+
+    #[test]
+    fn parse_bigint_in_and_out_of_range() {
+        assert_eq!(parse_bigint("42"), BigIntValue::Integer(42));
+        assert_eq!(
+            parse_bigint("-9223372036854775808"),
+            BigIntValue::Integer(i64::MIN)
+        );
+        assert_eq!(
+            parse_bigint("9223372036854775808"),
+            BigIntValue::Digits("9223372036854775808".to_string())
+        );
+    }
+
+    #[test]
+    fn date_parts_at_epoch() {
+        let parts = date_parts_from_timestamp(0.0).unwrap();
+        assert_eq!(
+            parts,
+            DateParts {
+                year: 1970,
+                month: 1,
+                day: 1,
+                hour: 0,
+                min: 0,
+                sec: 0,
+                ms: 0,
+                wday: 5,
+                yday: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn date_parts_on_leap_day() {
+        // 2024-02-29T13:45:30.250Z
+        let parts = date_parts_from_timestamp(1_709_214_330_250.0).unwrap();
+        assert_eq!((parts.year, parts.month, parts.day), (2024, 2, 29));
+        assert_eq!(
+            (parts.hour, parts.min, parts.sec, parts.ms),
+            (13, 45, 30, 250)
+        );
+        assert_eq!(parts.wday, 5);
+        assert_eq!(parts.yday, 60);
+    }
+
+    #[test]
+    fn date_parts_before_epoch() {
+        // 1969-12-31T23:59:59.999Z
+        let parts = date_parts_from_timestamp(-1.0).unwrap();
+        assert_eq!((parts.year, parts.month, parts.day), (1969, 12, 31));
+        assert_eq!(
+            (parts.hour, parts.min, parts.sec, parts.ms),
+            (23, 59, 59, 999)
+        );
+        assert_eq!(parts.wday, 4);
+        assert_eq!(parts.yday, 365);
+    }
+
+    #[test]
+    fn timestamp_round_trips_through_date_parts() {
+        for timestamp in [
+            0.0,
+            -1.0,
+            951_782_400_000.0,
+            1_709_214_330_250.0,
+            -62_135_596_800_000.0,
+        ] {
+            let parts = date_parts_from_timestamp(timestamp).unwrap();
+            let time_ms = parts.hour as i64 * 3_600_000
+                + parts.min as i64 * 60_000
+                + parts.sec as i64 * 1000
+                + parts.ms as i64;
+
+            assert_eq!(
+                timestamp_from_date(parts.year, parts.month as i64, parts.day as i64, time_ms),
+                timestamp
+            );
+        }
+    }
+
+    #[test]
+    fn timestamp_from_date_normalizes_fields() {
+        // 2023-13-01 is 2024-01-01, and 2024-03-00 is 2024-02-29
+        assert_eq!(
+            timestamp_from_date(2023, 13, 1, 0),
+            timestamp_from_date(2024, 1, 1, 0)
+        );
+        assert_eq!(
+            timestamp_from_date(2024, 3, 0, 0),
+            timestamp_from_date(2024, 2, 29, 0)
+        );
+        assert_eq!(
+            timestamp_from_date(2024, 1, 1, -1),
+            timestamp_from_date(2023, 12, 31, 86_399_999)
+        );
+    }
+
+    #[test]
+    fn date_parts_of_invalid_date() {
+        assert_eq!(date_parts_from_timestamp(f64::NAN), None);
+        assert_eq!(date_parts_from_timestamp(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn ancestors_detect_cycles() {
+        let mut ancestors = Ancestors::new(10);
+        ancestors.enter(1, |a, b| a == b).unwrap();
+        ancestors.enter(2, |a, b| a == b).unwrap();
+        assert_eq!(
+            ancestors.enter(1, |a, b| a == b),
+            Err(ConversionError::Cycle)
+        );
+    }
+
+    #[test]
+    fn ancestors_allow_shared_siblings() {
+        let mut ancestors = Ancestors::new(10);
+        ancestors.enter(1, |a, b| a == b).unwrap();
+        ancestors.enter(2, |a, b| a == b).unwrap();
+        ancestors.leave();
+        // the same value referenced twice, but not by itself, is not a cycle
+        ancestors.enter(2, |a, b| a == b).unwrap();
+    }
+
+    #[test]
+    fn ancestors_enforce_depth_limit() {
+        let mut ancestors = Ancestors::new(2);
+        ancestors.enter(1, |a, b| a == b).unwrap();
+        ancestors.enter(2, |a, b| a == b).unwrap();
+        assert_eq!(
+            ancestors.enter(3, |a, b| a == b),
+            Err(ConversionError::TooDeep(2))
+        );
+    }
+}
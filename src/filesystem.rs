@@ -1,28 +1,156 @@
+use crate::cipher::{AesCtrCipher, Cipher, XorCipher};
+use crate::conversion::lua_bytes_from_value;
+use crate::logger::log_message;
+use crate::love_filesystem::LoveFilesystem;
+use crate::mime::{
+    DEFAULT_CHARSET, UNKNOWN_MIME_TYPE, detect_charset, detect_mime_type, is_text_mime_type,
+    mime_type_for_path, sniff_mime_type,
+};
+use crate::pack::write_pack;
+use crate::sandbox::Sandbox;
+use crate::vfs::{ArchiveFormat, Mount, Source, normalize_path};
 use mlua::prelude::*;
 use std::{
     cell::RefCell,
-    sync::{Arc, LazyLock, Mutex, mpsc},
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::Cursor,
+    path::PathBuf,
+    sync::{
+        Arc, LazyLock, Mutex, OnceLock, RwLock,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc,
+    },
+    thread::{self, LocalKey, ThreadId},
+    time::Duration,
 };
-use ul_next::platform;
+use ul_next::platform::{self, LogLevel};
 
 static REQUIRED_FILES: &[&str] = &["resources/cacert.pem", "resources/icudt67l.dat"];
 
+const DEFAULT_TIMEOUT_MS: u64 = 2000;
+const DEFAULT_CACHE_SIZE: usize = 32;
+
+type CallbackCell = RefCell<Option<Arc<LuaRegistryKey>>>;
+
 thread_local! {
-    static LUA_FILESYSTEM_FILE_EXISTS_CALLBACK: RefCell<Option<Arc<LuaRegistryKey>>> = RefCell::new(None);
-    static LUA_FILESYSTEM_GET_FILE_MIME_TYPE_CALLBACK: RefCell<Option<Arc<LuaRegistryKey>>> = RefCell::new(None);
-    static LUA_FILESYSTEM_GET_FILE_CHARSET_CALLBACK: RefCell<Option<Arc<LuaRegistryKey>>> = RefCell::new(None);
-    static LUA_FILESYSTEM_GET_FILE_DATA_CALLBACK: RefCell<Option<Arc<LuaRegistryKey>>> = RefCell::new(None);
+    static LUA_FILESYSTEM_FILE_EXISTS_CALLBACK: CallbackCell = RefCell::new(None);
+    static LUA_FILESYSTEM_GET_FILE_MIME_TYPE_CALLBACK: CallbackCell = RefCell::new(None);
+    static LUA_FILESYSTEM_GET_FILE_CHARSET_CALLBACK: CallbackCell = RefCell::new(None);
+    static LUA_FILESYSTEM_GET_FILE_DATA_CALLBACK: CallbackCell = RefCell::new(None);
+
+    // used to answer requests made from the main thread itself, which can't wait for `update`
+    static MAIN_LUA: RefCell<Option<WeakLua>> = const { RefCell::new(None) };
+}
+
+static MAIN_THREAD: OnceLock<ThreadId> = OnceLock::new();
+
+// how long the loader thread waits for Lua before giving up on a request
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT_MS);
+
+// set once a Lua callback is registered, so it takes over from the built-in provider
+static HAS_FILE_EXISTS_CALLBACK: AtomicBool = AtomicBool::new(false);
+static HAS_GET_FILE_MIME_TYPE_CALLBACK: AtomicBool = AtomicBool::new(false);
+static HAS_GET_FILE_CHARSET_CALLBACK: AtomicBool = AtomicBool::new(false);
+static HAS_OPEN_FILE_CALLBACK: AtomicBool = AtomicBool::new(false);
+
+static LOVE_FILESYSTEM: RwLock<Option<LoveFilesystem>> = RwLock::new(None);
+
+// files added from Lua, served without waiting for Lua
+static FILES: LazyLock<RwLock<HashMap<String, Arc<Vec<u8>>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+static MOUNTS: RwLock<Vec<Mount>> = RwLock::new(Vec::new());
+
+static SANDBOX: LazyLock<RwLock<Sandbox>> = LazyLock::new(|| RwLock::new(Sandbox::default()));
+
+// paths already reported as denied, a page asks about the same file several times
+static LOGGED_DENIALS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Runs `f` with the built-in provider, unless a Lua callback overrides it.
+fn with_love_filesystem<T>(
+    has_callback: &AtomicBool,
+    f: impl FnOnce(&LoveFilesystem) -> T,
+) -> Option<T> {
+    if has_callback.load(Ordering::Acquire) {
+        return None;
+    }
+
+    LOVE_FILESYSTEM.read().ok()?.as_ref().map(f)
 }
 
+/// Cleans up a requested path and checks it against the sandbox.
+fn sandboxed_path(path: &str) -> Option<String> {
+    let sandbox = SANDBOX.read().ok()?;
+
+    match sandbox.check(path) {
+        Ok(path) => Some(path),
+        Err(denied) => {
+            if sandbox.log_denied
+                && LOGGED_DENIALS
+                    .lock()
+                    .is_ok_and(|mut logged| logged.insert(path.to_string()))
+            {
+                log_message(
+                    LogLevel::Warning,
+                    &format!("denied file request '{}': {}", path, denied),
+                );
+            }
+            None
+        }
+    }
+}
+
+/// Returns whether a file added from Lua or a mount has the given path.
+fn virtual_file_exists(path: &str) -> bool {
+    let Some(path) = normalize_path("", path) else {
+        return false;
+    };
+
+    if FILES.read().is_ok_and(|files| files.contains_key(&path)) {
+        return true;
+    }
+
+    // the latest mounts take precedence
+    MOUNTS
+        .read()
+        .is_ok_and(|mounts| mounts.iter().rev().any(|mount| mount.exists(&path)))
+}
+
+/// Reads a file the way `open_file` would, if that doesn't need Lua.
+fn read_file_without_lua(path: &str) -> Option<Vec<u8>> {
+    read_virtual_file(path)
+        .or_else(|| with_love_filesystem(&HAS_OPEN_FILE_CALLBACK, |fs| fs.read(path)).flatten())
+}
+
+/// Reads a file added from Lua or from a mount.
+fn read_virtual_file(path: &str) -> Option<Vec<u8>> {
+    let path = normalize_path("", path)?;
+
+    if let Some(data) = FILES.read().ok()?.get(&path) {
+        return Some(data.as_ref().clone());
+    }
+
+    MOUNTS
+        .read()
+        .ok()?
+        .iter()
+        .rev()
+        .find_map(|mount| mount.read(&path))
+}
+
+type Request<T> = (String, mpsc::Sender<T>);
+
 static FILESYSTEM_CHANNELS: LazyLock<(
-    mpsc::Sender<(String, mpsc::Sender<bool>)>,
-    Mutex<mpsc::Receiver<(String, mpsc::Sender<bool>)>>,
-    mpsc::Sender<(String, mpsc::Sender<Option<String>>)>,
-    Mutex<mpsc::Receiver<(String, mpsc::Sender<Option<String>>)>>,
-    mpsc::Sender<(String, mpsc::Sender<Option<String>>)>,
-    Mutex<mpsc::Receiver<(String, mpsc::Sender<Option<String>>)>>,
-    mpsc::Sender<(String, mpsc::Sender<Option<Vec<u8>>>)>,
-    Mutex<mpsc::Receiver<(String, mpsc::Sender<Option<Vec<u8>>>)>>,
+    mpsc::Sender<Request<bool>>,
+    Mutex<mpsc::Receiver<Request<bool>>>,
+    mpsc::Sender<Request<Option<String>>>,
+    Mutex<mpsc::Receiver<Request<Option<String>>>>,
+    mpsc::Sender<Request<Option<String>>>,
+    Mutex<mpsc::Receiver<Request<Option<String>>>>,
+    mpsc::Sender<Request<Option<Vec<u8>>>>,
+    Mutex<mpsc::Receiver<Request<Option<Vec<u8>>>>>,
 )> = LazyLock::new(|| {
     let (exists_tx, exists_rx) = mpsc::channel();
     let (mime_tx, mime_rx) = mpsc::channel();
@@ -41,6 +169,28 @@ static FILESYSTEM_CHANNELS: LazyLock<(
     )
 });
 
+/// Asks Lua to answer a request.
+///
+/// From the main thread the callback is called right away, since waiting for
+/// `update` would never end. Other threads wait for the next `update`, `draw`
+/// or `quit`, up to the configured timeout.
+fn ask_lua<T>(
+    sender: &mpsc::Sender<Request<T>>,
+    path: &str,
+    answer: fn(&Lua, &str) -> T,
+) -> Option<T> {
+    if MAIN_THREAD.get() == Some(&thread::current().id()) {
+        let lua = MAIN_LUA.with(|cell| cell.borrow().as_ref().and_then(|lua| lua.try_upgrade()))?;
+        return Some(answer(&lua, path));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    sender.send((path.to_string(), tx)).ok()?;
+
+    let timeout = Duration::from_millis(TIMEOUT_MS.load(Ordering::Relaxed));
+    rx.recv_timeout(timeout).ok()
+}
+
 pub struct FileSystem;
 
 impl platform::FileSystem for FileSystem {
@@ -49,42 +199,72 @@ impl platform::FileSystem for FileSystem {
             return true;
         }
 
-        let (tx, rx) = mpsc::channel::<bool>();
-        if FILESYSTEM_CHANNELS.0.send((path.to_string(), tx)).is_ok() {
-            return rx.recv().unwrap_or(false);
+        let Some(path) = sandboxed_path(path) else {
+            return false;
+        };
+        let path = path.as_str();
+
+        if virtual_file_exists(path) {
+            return true;
         }
 
-        false
+        if let Some(exists) = with_love_filesystem(&HAS_FILE_EXISTS_CALLBACK, |fs| fs.exists(path))
+        {
+            return exists;
+        }
+
+        ask_lua(&FILESYSTEM_CHANNELS.0, path, answer_file_exists).unwrap_or(false)
     }
 
     fn get_file_mime_type(&mut self, path: &str) -> String {
         if REQUIRED_FILES.contains(&path) {
-            return "application/unknown".to_string();
+            return UNKNOWN_MIME_TYPE.to_string();
         }
 
-        let (tx, rx) = mpsc::channel::<Option<String>>();
-        if FILESYSTEM_CHANNELS.2.send((path.to_string(), tx)).is_ok() {
-            if let Ok(Some(mime_type)) = rx.recv() {
-                return mime_type;
-            }
+        let Some(path) = sandboxed_path(path) else {
+            return UNKNOWN_MIME_TYPE.to_string();
+        };
+        let path = path.as_str();
+
+        if !virtual_file_exists(path)
+            && HAS_GET_FILE_MIME_TYPE_CALLBACK.load(Ordering::Acquire)
+            && let Some(Some(mime_type)) =
+                ask_lua(&FILESYSTEM_CHANNELS.2, path, answer_file_mime_type)
+        {
+            return mime_type;
         }
 
-        "application/unknown".to_string()
+        detect_mime_type(path, || read_file_without_lua(path)).to_string()
     }
 
     fn get_file_charset(&mut self, path: &str) -> String {
         if REQUIRED_FILES.contains(&path) {
-            return "utf-8".to_string();
+            return DEFAULT_CHARSET.to_string();
         }
 
-        let (tx, rx) = mpsc::channel::<Option<String>>();
-        if FILESYSTEM_CHANNELS.4.send((path.to_string(), tx)).is_ok() {
-            if let Ok(Some(charset)) = rx.recv() {
-                return charset;
-            }
+        let Some(path) = sandboxed_path(path) else {
+            return DEFAULT_CHARSET.to_string();
+        };
+        let path = path.as_str();
+
+        if !virtual_file_exists(path)
+            && HAS_GET_FILE_CHARSET_CALLBACK.load(Ordering::Acquire)
+            && let Some(Some(charset)) = ask_lua(&FILESYSTEM_CHANNELS.4, path, answer_file_charset)
+        {
+            return charset;
         }
 
-        "utf-8".to_string()
+        // only text files declare a charset, don't read anything else
+        if mime_type_for_path(path).is_some_and(|mime_type| !is_text_mime_type(mime_type)) {
+            return DEFAULT_CHARSET.to_string();
+        }
+
+        read_file_without_lua(path)
+            .and_then(|data| {
+                let mime_type = mime_type_for_path(path).or_else(|| sniff_mime_type(&data))?;
+                detect_charset(mime_type, &data)
+            })
+            .unwrap_or_else(|| DEFAULT_CHARSET.to_string())
     }
 
     fn open_file(&mut self, path: &str) -> Option<Vec<u8>> {
@@ -96,15 +276,84 @@ impl platform::FileSystem for FileSystem {
             };
         }
 
-        let (tx, rx) = mpsc::channel::<Option<Vec<u8>>>();
-        if FILESYSTEM_CHANNELS.6.send((path.to_string(), tx)).is_ok() {
-            return rx.recv().unwrap_or(None);
+        let path = sandboxed_path(path)?;
+        let path = path.as_str();
+
+        if let Some(data) = read_virtual_file(path) {
+            return Some(data);
         }
 
-        None
+        if let Some(data) = with_love_filesystem(&HAS_OPEN_FILE_CALLBACK, |fs| fs.read(path)) {
+            return data;
+        }
+
+        ask_lua(&FILESYSTEM_CHANNELS.6, path, answer_open_file).flatten()
     }
 }
 
+/// Calls the Lua callback stored in `callback_tls`, or returns `default` if there is none.
+fn call_callback<T>(
+    callback_tls: &'static LocalKey<CallbackCell>,
+    lua: &Lua,
+    call_lua: impl FnOnce(&LuaFunction) -> T,
+    default: T,
+) -> T {
+    // clone the key first, the callback may replace itself while running
+    let callback = callback_tls.with(|cell| cell.borrow().clone());
+
+    callback
+        .and_then(|callback| lua.registry_value::<LuaFunction>(&callback).ok())
+        .map(|func| call_lua(&func))
+        .unwrap_or(default)
+}
+
+fn answer_file_exists(lua: &Lua, path: &str) -> bool {
+    call_callback(
+        &LUA_FILESYSTEM_FILE_EXISTS_CALLBACK,
+        lua,
+        |func| func.call(path).unwrap_or(false),
+        false,
+    )
+}
+
+fn answer_file_mime_type(lua: &Lua, path: &str) -> Option<String> {
+    call_callback(
+        &LUA_FILESYSTEM_GET_FILE_MIME_TYPE_CALLBACK,
+        lua,
+        |func| func.call(path).unwrap_or(None),
+        None,
+    )
+}
+
+fn answer_file_charset(lua: &Lua, path: &str) -> Option<String> {
+    call_callback(
+        &LUA_FILESYSTEM_GET_FILE_CHARSET_CALLBACK,
+        lua,
+        |func| func.call(path).unwrap_or(None),
+        None,
+    )
+}
+
+fn answer_open_file(lua: &Lua, path: &str) -> Option<Vec<u8>> {
+    call_callback(
+        &LUA_FILESYSTEM_GET_FILE_DATA_CALLBACK,
+        lua,
+        |func| {
+            func.call(path)
+                .ok()
+                .flatten()
+                .map(|s: LuaString| s.as_bytes().to_vec())
+        },
+        None,
+    )
+}
+
+/// Remembers the main thread, so requests made from it are answered inline.
+pub fn filesystem_init(lua: &Lua) {
+    let _ = MAIN_THREAD.set(thread::current().id());
+    MAIN_LUA.with(|cell| *cell.borrow_mut() = Some(lua.weak()));
+}
+
 pub fn filesystem_set_on_file_exists_callback(lua: &Lua, callback: LuaFunction) -> LuaResult<()> {
     let callback = Arc::new(lua.create_registry_value(callback)?);
 
@@ -112,6 +361,7 @@ pub fn filesystem_set_on_file_exists_callback(lua: &Lua, callback: LuaFunction)
         *cell.borrow_mut() = Some(callback.clone());
         lua.expire_registry_values();
     });
+    HAS_FILE_EXISTS_CALLBACK.store(true, Ordering::Release);
 
     Ok(())
 }
@@ -126,6 +376,7 @@ pub fn filesystem_set_on_get_file_mime_type_callback(
         *cell.borrow_mut() = Some(callback.clone());
         lua.expire_registry_values();
     });
+    HAS_GET_FILE_MIME_TYPE_CALLBACK.store(true, Ordering::Release);
 
     Ok(())
 }
@@ -140,6 +391,7 @@ pub fn filesystem_set_on_get_file_charset_callback(
         *cell.borrow_mut() = Some(callback.clone());
         lua.expire_registry_values();
     });
+    HAS_GET_FILE_CHARSET_CALLBACK.store(true, Ordering::Release);
 
     Ok(())
 }
@@ -151,76 +403,335 @@ pub fn filesystem_set_on_open_file_callback(lua: &Lua, callback: LuaFunction) ->
         *cell.borrow_mut() = Some(callback.clone());
         lua.expire_registry_values();
     });
+    HAS_OPEN_FILE_CALLBACK.store(true, Ordering::Release);
+
+    Ok(())
+}
+
+/// Serves `file:///` requests from the game's files, as seen by `love.filesystem`.
+///
+/// Passing `false` turns the built-in provider off again.
+pub fn filesystem_use_love_filesystem(lua: &Lua, options: LuaValue) -> LuaResult<()> {
+    let love_filesystem = match options {
+        LuaValue::Boolean(false) => None,
+        LuaValue::Nil | LuaValue::Boolean(true) => Some(LoveFilesystem::new(lua, "")?),
+        LuaValue::Table(options) => {
+            let root = options.get::<Option<String>>("root")?.unwrap_or_default();
+            Some(LoveFilesystem::new(lua, &root)?)
+        }
+        other => {
+            return Err(LuaError::external(format!(
+                "expected an options table or false, got {}",
+                other.type_name()
+            )));
+        }
+    };
+
+    *LOVE_FILESYSTEM
+        .write()
+        .map_err(|_| LuaError::external("filesystem provider lock poisoned"))? = love_filesystem;
+
+    Ok(())
+}
+
+/// Sets how long, in seconds, a request may wait for Lua before it fails.
+pub fn filesystem_set_timeout(seconds: f64) -> LuaResult<()> {
+    if seconds.is_nan() || seconds < 0.0 {
+        return Err(LuaError::external("timeout must not be negative"));
+    }
+
+    TIMEOUT_MS.store((seconds * 1000.0) as u64, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Stores a file in memory, so it is served without waiting for Lua.
+pub fn filesystem_add_file(path: &str, data: Vec<u8>) -> LuaResult<()> {
+    let path = normalize_path("", path).ok_or_else(|| LuaError::external("invalid file path"))?;
+
+    FILES
+        .write()
+        .map_err(|_| LuaError::external("files lock poisoned"))?
+        .insert(path, Arc::new(data));
+
+    Ok(())
+}
+
+/// Removes a file stored by [`filesystem_add_file`].
+pub fn filesystem_remove_file(path: &str) -> LuaResult<bool> {
+    let Some(path) = normalize_path("", path) else {
+        return Ok(false);
+    };
+
+    Ok(FILES
+        .write()
+        .map_err(|_| LuaError::external("files lock poisoned"))?
+        .remove(&path)
+        .is_some())
+}
+
+/// Removes every file stored by [`filesystem_add_file`].
+pub fn filesystem_clear_files() -> LuaResult<()> {
+    FILES
+        .write()
+        .map_err(|_| LuaError::external("files lock poisoned"))?
+        .clear();
 
     Ok(())
 }
 
-fn handle_channel<T, F>(
-    receiver: &Mutex<mpsc::Receiver<(String, mpsc::Sender<T>)>>,
+/// How an archive is decrypted before it is opened.
+enum Decryption {
+    Cipher(Box<dyn Cipher>),
+    Function(LuaFunction),
+}
+
+impl Decryption {
+    fn apply(&self, lua: &Lua, mut data: Vec<u8>) -> LuaResult<Vec<u8>> {
+        match self {
+            Decryption::Cipher(cipher) => {
+                cipher.apply(&mut data);
+                Ok(data)
+            }
+            Decryption::Function(decrypt) => {
+                let data = decrypt.call::<LuaValue>(lua.create_string(&data)?)?;
+                Ok(lua_bytes_from_value(data)?.0)
+            }
+        }
+    }
+}
+
+/// Reads the `cipher`, `key` and `iv` options.
+fn cipher_from_options(options: &LuaTable) -> LuaResult<Option<Box<dyn Cipher>>> {
+    let Some(cipher) = options.get::<Option<String>>("cipher")? else {
+        return Ok(None);
+    };
+
+    let key = options
+        .get::<Option<LuaString>>("key")?
+        .ok_or_else(|| LuaError::external("a key is required to use a cipher"))?;
+    let key = key.as_bytes();
+
+    let cipher: Box<dyn Cipher> = match cipher.as_str() {
+        "xor" => Box::new(XorCipher::new(&key).map_err(LuaError::external)?),
+        "aes-ctr" => {
+            let iv = options
+                .get::<Option<LuaString>>("iv")?
+                .ok_or_else(|| LuaError::external("an iv is required to use aes-ctr"))?;
+            Box::new(AesCtrCipher::new(&key, &iv.as_bytes()).map_err(LuaError::external)?)
+        }
+        other => {
+            return Err(LuaError::external(format!(
+                "unknown cipher '{}', expected 'xor' or 'aes-ctr'",
+                other
+            )));
+        }
+    };
+
+    Ok(Some(cipher))
+}
+
+/// Options accepted by `mount`, only used for archives.
+struct MountOptions {
+    format: Option<ArchiveFormat>,
+    decryption: Option<Decryption>,
+    cache_size: usize,
+}
+
+impl MountOptions {
+    fn new(options: Option<LuaTable>) -> LuaResult<Self> {
+        let Some(options) = options else {
+            return Ok(MountOptions {
+                format: None,
+                decryption: None,
+                cache_size: DEFAULT_CACHE_SIZE,
+            });
+        };
+
+        let format = match options.get::<Option<String>>("format")?.as_deref() {
+            None => None,
+            Some("zip") => Some(ArchiveFormat::Zip),
+            Some("pack") => Some(ArchiveFormat::Pack),
+            Some(other) => {
+                return Err(LuaError::external(format!(
+                    "unknown archive format '{}', expected 'zip' or 'pack'",
+                    other
+                )));
+            }
+        };
+
+        let decryption = match options.get::<Option<LuaFunction>>("decrypt")? {
+            Some(decrypt) => Some(Decryption::Function(decrypt)),
+            None => cipher_from_options(&options)?.map(Decryption::Cipher),
+        };
+
+        Ok(MountOptions {
+            format,
+            decryption,
+            cache_size: options
+                .get::<Option<usize>>("cacheSize")?
+                .unwrap_or(DEFAULT_CACHE_SIZE),
+        })
+    }
+
+    fn open_archive(&self, lua: &Lua, data: Vec<u8>) -> LuaResult<Source> {
+        let data = match &self.decryption {
+            Some(decryption) => decryption.apply(lua, data)?,
+            None => data,
+        };
+
+        Source::archive(Box::new(Cursor::new(data)), self.format).map_err(LuaError::external)
+    }
+}
+
+/// Turns the second argument of `mount` into a [`Source`].
+///
+/// Strings name a directory or an archive on disk, tables map paths to
+/// contents, and love `Data` objects hold an archive.
+fn mount_source(lua: &Lua, source: LuaValue, options: &MountOptions) -> LuaResult<Source> {
+    match source {
+        LuaValue::String(path) => {
+            let path = PathBuf::from(path.to_str()?.to_string());
+            if path.is_dir() {
+                Ok(Source::Directory(path))
+            } else if options.decryption.is_some() {
+                let data = std::fs::read(&path).map_err(LuaError::external)?;
+                options.open_archive(lua, data)
+            } else {
+                let file = File::open(&path).map_err(LuaError::external)?;
+                Source::archive(Box::new(file), options.format).map_err(LuaError::external)
+            }
+        }
+        LuaValue::Table(table) => {
+            let mut files = HashMap::new();
+            for pair in table.pairs::<String, LuaValue>() {
+                let (path, data) = pair?;
+                let path = normalize_path("", &path)
+                    .ok_or_else(|| LuaError::external(format!("invalid file path '{}'", path)))?;
+                files.insert(path, Arc::new(lua_bytes_from_value(data)?.0));
+            }
+            Ok(Source::Files(files))
+        }
+        data @ LuaValue::UserData(_) => options.open_archive(lua, lua_bytes_from_value(data)?.0),
+        _ => Err(LuaError::external(
+            "expected a directory, a table of files or a Data object",
+        )),
+    }
+}
+
+/// Makes `source` visible under `prefix`, replacing any mount with the same prefix.
+pub fn filesystem_mount(
     lua: &Lua,
-    callback_tls: &RefCell<Option<Arc<LuaRegistryKey>>>,
-    call_lua: F,
-    default: T,
-) where
-    T: Clone,
-    F: Fn(&LuaFunction, String) -> T,
-{
+    prefix: &str,
+    source: LuaValue,
+    options: Option<LuaTable>,
+) -> LuaResult<()> {
+    let options = MountOptions::new(options)?;
+    let source = mount_source(lua, source, &options)?;
+
+    let mut mount = Mount::new(prefix, source);
+    if mount.source().is_archive() {
+        mount = mount.with_cache(options.cache_size);
+    }
+
+    let mut mounts = MOUNTS
+        .write()
+        .map_err(|_| LuaError::external("mounts lock poisoned"))?;
+
+    mounts.retain(|other| other.prefix() != mount.prefix());
+    mounts.push(mount);
+
+    Ok(())
+}
+
+/// Builds a pack from a table of paths to contents, encrypted with the `cipher` options.
+pub fn filesystem_create_pack(
+    lua: &Lua,
+    files: LuaTable,
+    options: Option<LuaTable>,
+) -> LuaResult<LuaString> {
+    let mut entries = Vec::new();
+    for pair in files.pairs::<String, LuaValue>() {
+        let (path, data) = pair?;
+        let path = normalize_path("", &path)
+            .ok_or_else(|| LuaError::external(format!("invalid file path '{}'", path)))?;
+        entries.push((path, lua_bytes_from_value(data)?.0));
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let (compress, cipher) = match &options {
+        Some(options) => (
+            options.get::<Option<bool>>("compress")?.unwrap_or(true),
+            cipher_from_options(options)?,
+        ),
+        None => (true, None),
+    };
+
+    let mut pack = write_pack(&entries, compress).map_err(LuaError::external)?;
+    if let Some(cipher) = cipher {
+        cipher.apply(&mut pack);
+    }
+
+    lua.create_string(&pack)
+}
+
+/// Removes the mount at `prefix`.
+pub fn filesystem_unmount(prefix: &str) -> LuaResult<bool> {
+    let prefix = normalize_path("", prefix).unwrap_or_default();
+    let mut mounts = MOUNTS
+        .write()
+        .map_err(|_| LuaError::external("mounts lock poisoned"))?;
+
+    let count = mounts.len();
+    mounts.retain(|mount| mount.prefix() != prefix);
+
+    Ok(mounts.len() != count)
+}
+
+/// Sets the root files may be read from, and the extensions pages may request.
+///
+/// Passing `nil` restores the defaults: paths are still cleaned up, but any
+/// of the game's files may be read.
+pub fn filesystem_set_sandbox(options: Option<LuaTable>) -> LuaResult<()> {
+    let sandbox = match options {
+        Some(options) => Sandbox::new(
+            &options.get::<Option<String>>("root")?.unwrap_or_default(),
+            options.get::<Option<Vec<String>>>("extensions")?,
+            options.get::<Option<bool>>("logDenied")?.unwrap_or(true),
+        ),
+        None => Sandbox::default(),
+    };
+
+    *SANDBOX
+        .write()
+        .map_err(|_| LuaError::external("sandbox lock poisoned"))? = sandbox;
+    // report paths again under the new rules
+    if let Ok(mut logged) = LOGGED_DENIALS.lock() {
+        logged.clear();
+    }
+
+    Ok(())
+}
+
+fn handle_channel<T>(
+    receiver: &Mutex<mpsc::Receiver<Request<T>>>,
+    lua: &Lua,
+    answer: fn(&Lua, &str) -> T,
+) {
     if let Ok(guard) = receiver.lock() {
         while let Ok((path, sync_tx)) = guard.try_recv() {
-            let result = callback_tls
-                .borrow()
-                .as_ref()
-                .and_then(|callback| lua.registry_value::<LuaFunction>(callback).ok())
-                .map(|func| call_lua(&func, path.clone()))
-                .unwrap_or(default.clone());
-            let _ = sync_tx.send(result);
+            // the sender may have timed out already
+            let _ = sync_tx.send(answer(lua, &path));
         }
     }
 }
 
+/// Answers the requests waiting for Lua.
+///
+/// Called from `update`, `draw` and `quit`, so the loader thread never waits
+/// longer than a frame.
 pub fn filesystem_update(lua: &Lua) {
-    LUA_FILESYSTEM_FILE_EXISTS_CALLBACK.with(|callback_tls| {
-        handle_channel(
-            &FILESYSTEM_CHANNELS.1,
-            lua,
-            callback_tls,
-            |func, path| func.call(path).unwrap_or(false),
-            false,
-        );
-    });
-
-    LUA_FILESYSTEM_GET_FILE_MIME_TYPE_CALLBACK.with(|callback_tls| {
-        handle_channel(
-            &FILESYSTEM_CHANNELS.3,
-            lua,
-            callback_tls,
-            |func, path| func.call(path).unwrap_or(None),
-            None,
-        );
-    });
-
-    LUA_FILESYSTEM_GET_FILE_CHARSET_CALLBACK.with(|callback_tls| {
-        handle_channel(
-            &FILESYSTEM_CHANNELS.5,
-            lua,
-            callback_tls,
-            |func, path| func.call(path).unwrap_or(None),
-            None,
-        );
-    });
-
-    LUA_FILESYSTEM_GET_FILE_DATA_CALLBACK.with(|callback_tls| {
-        handle_channel(
-            &FILESYSTEM_CHANNELS.7,
-            lua,
-            callback_tls,
-            |func, path| {
-                func.call(path)
-                    .ok()
-                    .flatten()
-                    .map(|s: LuaString| s.as_bytes().to_vec())
-            },
-            None,
-        );
-    });
+    handle_channel(&FILESYSTEM_CHANNELS.1, lua, answer_file_exists);
+    handle_channel(&FILESYSTEM_CHANNELS.3, lua, answer_file_mime_type);
+    handle_channel(&FILESYSTEM_CHANNELS.5, lua, answer_file_charset);
+    handle_channel(&FILESYSTEM_CHANNELS.7, lua, answer_open_file);
 }
@@ -0,0 +1,204 @@
+use std::sync::Mutex;
+use ul_next::{
+    bitmap::{BitmapFormat, OwnedBitmap},
+    gpu_driver::{
+        VertexBufferFormat,
+        recording::{GpuTrace, GpuTraceHandle, RecordingGpuDriver},
+    },
+};
+
+// the trace of the driver given to Ultralight
+static TRACE: Mutex<Option<GpuTraceHandle>> = Mutex::new(None);
+
+/// The layout of a geometry's vertices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexFormat {
+    /// Position, color and texture coordinates, drawn with the path shader.
+    Path,
+    /// Path vertices with object coordinates and 28 floats of data, drawn with the fill shader.
+    Quad,
+}
+
+impl VertexFormat {
+    /// The size of one vertex in bytes.
+    pub fn stride(self) -> usize {
+        match self {
+            VertexFormat::Path => 20,
+            VertexFormat::Quad => 140,
+        }
+    }
+}
+
+impl From<VertexBufferFormat> for VertexFormat {
+    fn from(format: VertexBufferFormat) -> Self {
+        match format {
+            VertexBufferFormat::Format_2f_4ub_2f => VertexFormat::Path,
+            VertexBufferFormat::Format_2f_4ub_2f_2f_28f => VertexFormat::Quad,
+        }
+    }
+}
+
+/// The contents of a texture, with rows packed tightly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TexturePixels {
+    /// A texture drawn into, backing a render buffer.
+    RenderTarget,
+    /// Premultiplied RGBA, 4 bytes per pixel.
+    Rgba8(Vec<u8>),
+    /// A single channel, 1 byte per pixel.
+    R8(Vec<u8>),
+}
+
+impl TexturePixels {
+    /// The pixels of a texture Ultralight created or updated, empty bitmaps being render targets.
+    pub fn from_bitmap(bitmap: &OwnedBitmap) -> Self {
+        match bitmap.pixels() {
+            Some(pixels) if !bitmap.is_empty() => Self::from_rows(
+                bitmap.format(),
+                bitmap.width(),
+                bitmap.height(),
+                bitmap.row_bytes(),
+                pixels,
+            ),
+            _ => TexturePixels::RenderTarget,
+        }
+    }
+
+    /// Drops the row padding, and swaps BGRA to the RGBA love expects.
+    fn from_rows(
+        format: BitmapFormat,
+        width: u32,
+        height: u32,
+        row_bytes: u32,
+        pixels: &[u8],
+    ) -> Self {
+        let bpp = match format {
+            BitmapFormat::A8Unorm => 1,
+            BitmapFormat::Bgra8UnormSrgb => 4,
+        };
+        let row_len = width as usize * bpp;
+        let mut packed = Vec::with_capacity(row_len * height as usize);
+        for row in pixels.chunks(row_bytes as usize).take(height as usize) {
+            packed.extend_from_slice(&row[..row_len.min(row.len())]);
+        }
+
+        match format {
+            BitmapFormat::A8Unorm => TexturePixels::R8(packed),
+            BitmapFormat::Bgra8UnormSrgb => {
+                for pixel in packed.chunks_exact_mut(4) {
+                    pixel.swap(0, 2);
+                }
+                TexturePixels::Rgba8(packed)
+            }
+        }
+    }
+}
+
+/// Creates the driver for accelerated views, whose calls `gpu_replay` draws with love.
+///
+/// Ultralight may call the driver from any thread, so it only records.
+/// Only the trace of the latest driver is read by [`gpu_take_trace`].
+pub fn gpu_driver() -> RecordingGpuDriver {
+    let driver = RecordingGpuDriver::new();
+    *TRACE.lock().unwrap() = Some(driver.trace_handle());
+    driver
+}
+
+/// Takes the calls recorded since the last call, oldest first.
+pub fn gpu_take_trace() -> GpuTrace {
+    TRACE
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(GpuTraceHandle::take)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ul_next::gpu_driver::{
+        GpuCommand, GpuDriver as _, IndexBuffer, RenderBuffer, VertexBuffer,
+        recording::GpuTraceEvent,
+    };
+
+    #[test]
+    fn driver_records_calls_in_order() {
+        let mut driver = gpu_driver();
+
+        let texture_id = driver.next_texture_id();
+        let render_buffer_id = driver.next_render_buffer_id();
+        driver.create_render_buffer(
+            render_buffer_id,
+            RenderBuffer {
+                texture_id,
+                width: 4,
+                height: 4,
+                has_stencil_buffer: false,
+                has_depth_buffer: false,
+            },
+        );
+        let geometry_id = driver.next_geometry_id();
+        driver.create_geometry(
+            geometry_id,
+            VertexBuffer {
+                format: VertexBufferFormat::Format_2f_4ub_2f,
+                buffer: vec![0; 60],
+            },
+            IndexBuffer {
+                buffer: vec![0, 1, 2],
+            },
+        );
+        driver.update_command_list(vec![GpuCommand::ClearRenderBuffer { render_buffer_id }]);
+        driver.destroy_geometry(geometry_id);
+
+        let events = gpu_take_trace().events;
+        assert_eq!(events.len(), 4);
+        assert!(matches!(
+            &events[0],
+            GpuTraceEvent::CreateRenderBuffer {
+                render_buffer_id: 1,
+                render_buffer,
+            } if render_buffer.texture_id == 1
+        ));
+        assert!(matches!(
+            &events[1],
+            GpuTraceEvent::CreateGeometry { geometry_id: 1, vertex_buffer, index_buffer }
+                if VertexFormat::from(vertex_buffer.format) == VertexFormat::Path
+                    && vertex_buffer.buffer.len() / VertexFormat::Path.stride() == 3
+                    && index_buffer.buffer == [0, 1, 2]
+        ));
+        assert!(matches!(
+            &events[2],
+            GpuTraceEvent::UpdateCommandList { commands }
+                if matches!(commands[..], [GpuCommand::ClearRenderBuffer { render_buffer_id: 1 }])
+        ));
+        assert!(matches!(
+            events[3],
+            GpuTraceEvent::DestroyGeometry { geometry_id: 1 }
+        ));
+        assert!(gpu_take_trace().events.is_empty());
+    }
+
+    #[test]
+    fn texture_rows_are_packed_and_swizzled() {
+        // 2x2 BGRA with 4 bytes of padding per row
+        let rows = [
+            0, 0, 255, 255, 255, 0, 0, 255, 9, 9, 9, 9, //
+            0, 255, 0, 255, 0, 0, 0, 0, 9, 9, 9, 9,
+        ];
+        assert_eq!(
+            TexturePixels::from_rows(BitmapFormat::Bgra8UnormSrgb, 2, 2, 12, &rows),
+            TexturePixels::Rgba8(vec![
+                255, 0, 0, 255, 0, 0, 255, 255, //
+                0, 255, 0, 255, 0, 0, 0, 0,
+            ])
+        );
+
+        let rows = [1, 2, 0, 0, 3, 4, 0, 0];
+        assert_eq!(
+            TexturePixels::from_rows(BitmapFormat::A8Unorm, 2, 2, 4, &rows),
+            TexturePixels::R8(vec![1, 2, 3, 4])
+        );
+    }
+}
@@ -0,0 +1,538 @@
+use crate::gpu_driver::{TexturePixels, VertexFormat, gpu_take_trace};
+use crate::logger::log_message;
+use mlua::prelude::*;
+use std::{cell::RefCell, collections::HashMap};
+use ul_next::gpu_driver::{GpuCommand, GpuState, ShaderType, recording::GpuTraceEvent};
+use ul_next::platform::LogLevel;
+
+const FILL_SHADER: &str = include_str!("shaders/fill.glsl");
+const PATH_SHADER: &str = include_str!("shaders/path.glsl");
+
+thread_local! {
+    static RESOURCES: RefCell<GpuResources> = RefCell::new(GpuResources::default());
+}
+
+/// A love `Image`, or a `Canvas` when it backs a render buffer.
+struct Texture {
+    texture: LuaRegistryKey,
+    width: u32,
+    height: u32,
+    is_canvas: bool,
+}
+
+struct Geometry {
+    mesh: LuaRegistryKey,
+    format: VertexFormat,
+    vertex_count: usize,
+}
+
+struct Shaders {
+    fill: LuaRegistryKey,
+    path: LuaRegistryKey,
+    // bound to texture slots a draw doesn't use
+    blank: LuaRegistryKey,
+}
+
+/// The love objects standing in for Ultralight's textures, render buffers and geometry.
+#[derive(Default)]
+struct GpuResources {
+    textures: HashMap<u32, Texture>,
+    // render buffer id -> texture id
+    render_buffers: HashMap<u32, u32>,
+    geometries: HashMap<u32, Geometry>,
+    shaders: Option<Shaders>,
+}
+
+impl GpuResources {
+    fn apply(&mut self, lua: &Lua, graphics: &LuaTable, event: GpuTraceEvent) -> LuaResult<()> {
+        match event {
+            GpuTraceEvent::BeginSynchronize | GpuTraceEvent::EndSynchronize => {}
+            GpuTraceEvent::CreateTexture { texture_id, bitmap }
+            | GpuTraceEvent::UpdateTexture { texture_id, bitmap } => self.set_texture(
+                lua,
+                graphics,
+                texture_id,
+                bitmap.width(),
+                bitmap.height(),
+                TexturePixels::from_bitmap(&bitmap),
+            )?,
+            GpuTraceEvent::DestroyTexture { texture_id } => {
+                self.textures.remove(&texture_id);
+            }
+            GpuTraceEvent::CreateRenderBuffer {
+                render_buffer_id,
+                render_buffer,
+            } => {
+                self.render_buffers
+                    .insert(render_buffer_id, render_buffer.texture_id);
+            }
+            GpuTraceEvent::DestroyRenderBuffer { render_buffer_id } => {
+                self.render_buffers.remove(&render_buffer_id);
+            }
+            GpuTraceEvent::CreateGeometry {
+                geometry_id,
+                vertex_buffer,
+                index_buffer,
+            }
+            | GpuTraceEvent::UpdateGeometry {
+                geometry_id,
+                vertex_buffer,
+                index_buffer,
+            } => self.set_geometry(
+                lua,
+                graphics,
+                geometry_id,
+                vertex_buffer.format.into(),
+                &vertex_buffer.buffer,
+                &index_buffer.buffer,
+            )?,
+            GpuTraceEvent::DestroyGeometry { geometry_id } => {
+                self.geometries.remove(&geometry_id);
+            }
+            GpuTraceEvent::UpdateCommandList { commands } => {
+                for command in commands {
+                    self.run(lua, graphics, command)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn set_texture(
+        &mut self,
+        lua: &Lua,
+        graphics: &LuaTable,
+        texture_id: u32,
+        width: u32,
+        height: u32,
+        pixels: TexturePixels,
+    ) -> LuaResult<()> {
+        if width == 0 || height == 0 {
+            self.textures.remove(&texture_id);
+            return Ok(());
+        }
+
+        let (format, pixels) = match pixels {
+            TexturePixels::RenderTarget => {
+                let canvas: LuaAnyUserData = graphics
+                    .get::<LuaFunction>("newCanvas")?
+                    .call((width, height))?;
+                self.textures.insert(
+                    texture_id,
+                    Texture {
+                        texture: lua.create_registry_value(canvas)?,
+                        width,
+                        height,
+                        is_canvas: true,
+                    },
+                );
+                return Ok(());
+            }
+            TexturePixels::Rgba8(pixels) => ("rgba8", pixels),
+            TexturePixels::R8(pixels) => ("r8", pixels),
+        };
+
+        let image: LuaTable = lua.globals().get::<LuaTable>("love")?.get("image")?;
+        let image_data: LuaAnyUserData = image.get::<LuaFunction>("newImageData")?.call((
+            width,
+            height,
+            format,
+            lua.create_string(&pixels)?,
+        ))?;
+
+        // updates of the same size reuse the image
+        if let Some(texture) = self.textures.get(&texture_id)
+            && !texture.is_canvas
+            && texture.width == width
+            && texture.height == height
+        {
+            let texture: LuaAnyUserData = lua.registry_value(&texture.texture)?;
+            return texture.call_method("replacePixels", image_data);
+        }
+
+        let texture: LuaAnyUserData = graphics.get::<LuaFunction>("newImage")?.call(image_data)?;
+        self.textures.insert(
+            texture_id,
+            Texture {
+                texture: lua.create_registry_value(texture)?,
+                width,
+                height,
+                is_canvas: false,
+            },
+        );
+        Ok(())
+    }
+
+    fn set_geometry(
+        &mut self,
+        lua: &Lua,
+        graphics: &LuaTable,
+        geometry_id: u32,
+        format: VertexFormat,
+        vertices: &[u8],
+        indices: &[u32],
+    ) -> LuaResult<()> {
+        let vertex_count = vertices.len() / format.stride();
+        if vertex_count == 0 {
+            self.geometries.remove(&geometry_id);
+            return Ok(());
+        }
+
+        let mesh: LuaAnyUserData = match self.geometries.get(&geometry_id) {
+            Some(geometry)
+                if geometry.format == format && geometry.vertex_count == vertex_count =>
+            {
+                lua.registry_value(&geometry.mesh)?
+            }
+            _ => {
+                let mesh: LuaAnyUserData = graphics.get::<LuaFunction>("newMesh")?.call((
+                    vertex_attributes(lua, format)?,
+                    vertex_count,
+                    "triangles",
+                    "dynamic",
+                ))?;
+                self.geometries.insert(
+                    geometry_id,
+                    Geometry {
+                        mesh: lua.create_registry_value(&mesh)?,
+                        format,
+                        vertex_count,
+                    },
+                );
+                mesh
+            }
+        };
+
+        let data: LuaTable = lua.globals().get::<LuaTable>("love")?.get("data")?;
+        let new_byte_data: LuaFunction = data.get("newByteData")?;
+
+        let vertices = &vertices[..vertex_count * format.stride()];
+        let vertices: LuaAnyUserData = new_byte_data.call(lua.create_string(vertices)?)?;
+        mesh.call_method::<()>("setVertices", vertices)?;
+
+        let indices: Vec<u8> = indices
+            .iter()
+            .flat_map(|index| index.to_ne_bytes())
+            .collect();
+        let indices: LuaAnyUserData = new_byte_data.call(lua.create_string(&indices)?)?;
+        mesh.call_method("setVertexMap", (indices, "uint32"))
+    }
+
+    fn texture(&self, lua: &Lua, texture_id: u32) -> LuaResult<Option<LuaAnyUserData>> {
+        self.textures
+            .get(&texture_id)
+            .map(|texture| lua.registry_value(&texture.texture))
+            .transpose()
+    }
+
+    fn render_target(&self, lua: &Lua, render_buffer_id: u32) -> LuaResult<Option<LuaAnyUserData>> {
+        match self.render_buffers.get(&render_buffer_id) {
+            Some(texture_id) => self.texture(lua, *texture_id),
+            None => Ok(None),
+        }
+    }
+
+    fn shaders(&mut self, lua: &Lua, graphics: &LuaTable) -> LuaResult<&Shaders> {
+        if self.shaders.is_none() {
+            let new_shader: LuaFunction = graphics.get("newShader")?;
+            let image: LuaTable = lua.globals().get::<LuaTable>("love")?.get("image")?;
+            let blank: LuaAnyUserData = image.get::<LuaFunction>("newImageData")?.call((1, 1))?;
+            let blank: LuaAnyUserData = graphics.get::<LuaFunction>("newImage")?.call(blank)?;
+
+            self.shaders = Some(Shaders {
+                fill: lua.create_registry_value(new_shader.call::<LuaAnyUserData>(FILL_SHADER)?)?,
+                path: lua.create_registry_value(new_shader.call::<LuaAnyUserData>(PATH_SHADER)?)?,
+                blank: lua.create_registry_value(blank)?,
+            });
+        }
+        Ok(self.shaders.as_ref().unwrap())
+    }
+
+    fn run(&mut self, lua: &Lua, graphics: &LuaTable, command: GpuCommand) -> LuaResult<()> {
+        match command {
+            GpuCommand::ClearRenderBuffer { render_buffer_id } => {
+                let Some(canvas) = self.render_target(lua, render_buffer_id)? else {
+                    return Ok(());
+                };
+                graphics.call_function::<()>("setCanvas", canvas)?;
+                graphics.call_function::<()>("setScissor", ())?;
+                graphics.call_function("clear", (0, 0, 0, 0))
+            }
+            GpuCommand::DrawGeometry {
+                gpu_state,
+                geometry_id,
+                indices_offset,
+                indices_count,
+            } => {
+                let Some(canvas) = self.render_target(lua, gpu_state.render_buffer_id)? else {
+                    return Ok(());
+                };
+                let Some(geometry) = self.geometries.get(&geometry_id) else {
+                    return Ok(());
+                };
+                let mesh: LuaAnyUserData = lua.registry_value(&geometry.mesh)?;
+
+                let textures = [
+                    gpu_state.texture_1_id,
+                    gpu_state.texture_2_id,
+                    gpu_state.texture_3_id,
+                ]
+                .map(|id| id.map(|id| self.texture(lua, id)));
+
+                let shaders = self.shaders(lua, graphics)?;
+                let shader: LuaAnyUserData = match gpu_state.shader_type {
+                    ShaderType::Fill => lua.registry_value(&shaders.fill)?,
+                    ShaderType::FillPath => lua.registry_value(&shaders.path)?,
+                };
+                let blank: LuaAnyUserData = lua.registry_value(&shaders.blank)?;
+
+                graphics.call_function::<()>("setCanvas", canvas)?;
+                graphics.call_function::<()>("setShader", &shader)?;
+                send_state(lua, &shader, &gpu_state)?;
+                for (name, texture) in ["Texture1", "Texture2", "Texture3"]
+                    .into_iter()
+                    .zip(textures)
+                {
+                    let texture = texture
+                        .transpose()?
+                        .flatten()
+                        .unwrap_or_else(|| blank.clone());
+                    send(lua, &shader, name, texture)?;
+                }
+
+                if gpu_state.enable_blend {
+                    graphics.call_function::<()>("setBlendMode", ("alpha", "premultiplied"))?;
+                } else {
+                    graphics.call_function::<()>("setBlendMode", ("replace", "premultiplied"))?;
+                }
+
+                if gpu_state.enable_scissor {
+                    let rect = gpu_state.scissor_rect;
+                    graphics.call_function::<()>(
+                        "setScissor",
+                        (
+                            rect.left,
+                            rect.top,
+                            (rect.right - rect.left).max(0),
+                            (rect.bottom - rect.top).max(0),
+                        ),
+                    )?;
+                } else {
+                    graphics.call_function::<()>("setScissor", ())?;
+                }
+
+                mesh.call_method::<()>("setDrawRange", (indices_offset + 1, indices_count))?;
+                graphics.call_function("draw", mesh)
+            }
+        }
+    }
+}
+
+/// Ultralight's vertex layout, as love attribute names, types and component counts.
+fn vertex_layout(format: VertexFormat) -> Vec<(&'static str, &'static str, u32)> {
+    let mut attributes = vec![
+        ("VertexPosition", "float", 2),
+        ("in_Color", "byte", 4),
+        ("in_TexCoord", "float", 2),
+    ];
+    if format == VertexFormat::Quad {
+        attributes.extend([
+            ("in_ObjCoord", "float", 2),
+            ("in_Data0", "float", 4),
+            ("in_Data1", "float", 4),
+            ("in_Data2", "float", 4),
+            ("in_Data3", "float", 4),
+            ("in_Data4", "float", 4),
+            ("in_Data5", "float", 4),
+            ("in_Data6", "float", 4),
+        ]);
+    }
+    attributes
+}
+
+/// The love vertex format matching Ultralight's vertex layout.
+fn vertex_attributes(lua: &Lua, format: VertexFormat) -> LuaResult<LuaTable> {
+    let attributes = vertex_layout(format)
+        .into_iter()
+        .map(|attribute| lua.create_sequence_from(attribute.into_lua_multi(lua)?))
+        .collect::<LuaResult<Vec<_>>>()?;
+    lua.create_sequence_from(attributes)
+}
+
+/// Sends a uniform, skipping the ones the shader compiler optimized away.
+fn send(lua: &Lua, shader: &LuaAnyUserData, name: &str, value: impl IntoLuaMulti) -> LuaResult<()> {
+    if !shader.call_method::<bool>("hasUniform", name)? {
+        return Ok(());
+    }
+    let mut args = value.into_lua_multi(lua)?;
+    args.push_front(name.into_lua(lua)?);
+    shader.call_method("send", args)
+}
+
+/// The `State` uniform: time, viewport size and scale, the time is always 0.
+fn state_uniform(state: &GpuState) -> [f32; 4] {
+    [
+        0.0,
+        state.viewport_width as f32,
+        state.viewport_height as f32,
+        1.0,
+    ]
+}
+
+/// The columns of the transform, which Ultralight stores column major.
+fn transform_columns(state: &GpuState) -> [[f32; 4]; 4] {
+    std::array::from_fn(|column| std::array::from_fn(|row| state.transform[column * 4 + row]))
+}
+
+fn send_state(lua: &Lua, shader: &LuaAnyUserData, state: &GpuState) -> LuaResult<()> {
+    // the matrices are column major
+    let matrix = |columns: &[[f32; 4]; 4]| {
+        let columns = columns
+            .iter()
+            .map(|column| lua.create_sequence_from(*column))
+            .collect::<LuaResult<Vec<_>>>()?;
+        lua.create_sequence_from(columns)
+    };
+
+    send(
+        lua,
+        shader,
+        "State",
+        lua.create_sequence_from(state_uniform(state))?,
+    )?;
+    send(
+        lua,
+        shader,
+        "Transform",
+        ("column", matrix(&transform_columns(state))?),
+    )?;
+    send(
+        lua,
+        shader,
+        "scalar",
+        LuaMultiValue::from_iter(
+            state
+                .uniform_scalar
+                .map(|value| LuaValue::Number(value as f64)),
+        ),
+    )?;
+    send(
+        lua,
+        shader,
+        "vector",
+        state
+            .uniform_vector
+            .iter()
+            .map(|vector| lua.create_sequence_from(*vector).map(LuaValue::Table))
+            .collect::<LuaResult<LuaMultiValue>>()?,
+    )?;
+    send(lua, shader, "ClipSize", state.clip_size as i32)?;
+
+    let mut clip = state
+        .clip
+        .iter()
+        .map(|columns| matrix(columns).map(LuaValue::Table))
+        .collect::<LuaResult<LuaMultiValue>>()?;
+    clip.push_front("column".into_lua(lua)?);
+    send(lua, shader, "clip", clip)
+}
+
+/// Draws what accelerated views rendered since the last call into their love `Canvas`es.
+///
+/// Without `love.graphics` (eg, headless), the recorded commands are dropped.
+pub fn gpu_replay(lua: &Lua) -> LuaResult<()> {
+    let events = gpu_take_trace().events;
+    if events.is_empty() {
+        return Ok(());
+    }
+
+    let Some(graphics) = lua
+        .globals()
+        .get::<Option<LuaTable>>("love")?
+        .map(|love| love.get::<Option<LuaTable>>("graphics"))
+        .transpose()?
+        .flatten()
+    else {
+        return Ok(());
+    };
+
+    // leave the game's canvas, shader, blend mode and transform as they were
+    graphics.call_function::<()>("push", "all")?;
+    graphics.call_function::<()>("origin", ())?;
+    RESOURCES.with(|cell| {
+        let mut resources = cell.borrow_mut();
+        // a failing event must not take the buffers and textures created after it down too
+        for event in events {
+            if let Err(e) = resources.apply(lua, &graphics, event) {
+                log_message(
+                    LogLevel::Error,
+                    &format!("failed to replay a GPU command: {}", e),
+                );
+            }
+        }
+    });
+    graphics.call_function::<()>("pop", ())
+}
+
+/// The love `Image` or `Canvas` standing in for texture `texture_id`.
+pub fn gpu_texture(lua: &Lua, texture_id: u32) -> LuaResult<Option<LuaAnyUserData>> {
+    RESOURCES.with(|cell| cell.borrow().texture(lua, texture_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ul_next::rect::Rect;
+
+    fn gpu_state() -> GpuState {
+        GpuState {
+            viewport_width: 640,
+            viewport_height: 480,
+            transform: std::array::from_fn(|i| i as f32),
+            enable_texturing: false,
+            enable_blend: true,
+            shader_type: ShaderType::Fill,
+            render_buffer_id: 1,
+            texture_1_id: None,
+            texture_2_id: None,
+            texture_3_id: None,
+            uniform_scalar: [0.0; 8],
+            uniform_vector: [[0.0; 4]; 8],
+            clip_size: 0,
+            clip: [[[0.0; 4]; 4]; 8],
+            enable_scissor: false,
+            scissor_rect: Rect {
+                left: 0,
+                top: 0,
+                right: 0,
+                bottom: 0,
+            },
+        }
+    }
+
+    #[test]
+    fn vertex_layout_matches_the_vertex_size() {
+        for format in [VertexFormat::Path, VertexFormat::Quad] {
+            let size: u32 = vertex_layout(format)
+                .iter()
+                .map(|(_, kind, components)| match *kind {
+                    "float" => 4 * components,
+                    "byte" => *components,
+                    kind => panic!("unexpected attribute type {}", kind),
+                })
+                .sum();
+            assert_eq!(size as usize, format.stride());
+        }
+        assert_eq!(vertex_layout(VertexFormat::Path)[0].0, "VertexPosition");
+    }
+
+    #[test]
+    fn state_uniforms_follow_the_gpu_state() {
+        let state = gpu_state();
+        assert_eq!(state_uniform(&state), [0.0, 640.0, 480.0, 1.0]);
+
+        let columns = transform_columns(&state);
+        assert_eq!(columns[0], [0.0, 1.0, 2.0, 3.0]);
+        assert_eq!(columns[3], [12.0, 13.0, 14.0, 15.0]);
+    }
+}
@@ -1,4 +1,5 @@
-use crate::ultralight_renderer::renderer_get_lib;
+use crate::clipboard::clipboard_while_cutting;
+use crate::ultralight_renderer::{engine_call, renderer_get_lib};
 use mlua::prelude::*;
 use ul_next::{
     View,
@@ -231,6 +232,10 @@ pub fn keyboard_key(
 
     let modifiers = get_modifiers(lua, modifiers_tbl)?;
     let is_system_key = modifiers.alt;
+    // cuts reach the clipboard as plain writes, tell them apart by the shortcut
+    let is_cut = matches!(event_type, KeyEventType::KeyDown)
+        && ((key == "x" && (modifiers.ctrl || modifiers.meta))
+            || (key == "delete" && modifiers.shift));
 
     let info = KeyEventCreationInfo {
         ty: event_type,
@@ -245,7 +250,11 @@ pub fn keyboard_key(
     };
 
     let event = KeyEvent::new(lib, info).map_err(|e| LuaError::external(e))?;
-    view.fire_key_event(event);
+    if is_cut {
+        clipboard_while_cutting(|| engine_call(|| view.fire_key_event(event)));
+    } else {
+        engine_call(|| view.fire_key_event(event));
+    }
 
     Ok(())
 }
@@ -3,11 +3,26 @@ use mlua::prelude::*;
 
 mod api;
 mod callbacks;
+mod cipher;
 mod clipboard;
+mod console;
+mod conversion;
 mod filesystem;
+mod gpu_driver;
+mod gpu_replay;
 mod keyboard;
+mod logger;
+mod love_filesystem;
+mod memory;
+mod mime;
+mod pack;
+mod proxy;
+mod sandbox;
+mod script_context;
+mod surface;
 mod ultralight_renderer;
 mod ultralight_view;
+mod vfs;
 
 #[mlua::lua_module]
 fn love_ultralight(lua: &Lua) -> LuaResult<LuaTable> {
@@ -0,0 +1,209 @@
+use mlua::prelude::*;
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fs::{File, OpenOptions},
+    io::Write,
+    sync::{LazyLock, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use ul_next::platform::{self, LogLevel};
+
+// messages waiting for the next `update`, the oldest are dropped past this
+const MAX_PENDING_MESSAGES: usize = 1024;
+
+thread_local! {
+    static LUA_LOGGER_CALLBACK: RefCell<Option<LuaRegistryKey>> = const { RefCell::new(None) };
+    // engine messages logged during `logger_capture`
+    static CAPTURED: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+struct LogRecord {
+    level: LogLevel,
+    message: String,
+    timestamp: f64,
+}
+
+struct LogRouting {
+    lua_level: Option<LogLevel>,
+    file: Option<(File, LogLevel)>,
+    stderr_level: LogLevel,
+    pending: VecDeque<LogRecord>,
+}
+
+static LOG_ROUTING: LazyLock<Mutex<LogRouting>> = LazyLock::new(|| {
+    Mutex::new(LogRouting {
+        lua_level: None,
+        file: None,
+        stderr_level: LogLevel::Warning,
+        pending: VecDeque::new(),
+    })
+});
+
+fn level_rank(level: LogLevel) -> u8 {
+    match level {
+        LogLevel::Info => 0,
+        LogLevel::Warning => 1,
+        LogLevel::Error => 2,
+    }
+}
+
+fn level_name(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Info => "info",
+        LogLevel::Warning => "warning",
+        LogLevel::Error => "error",
+    }
+}
+
+fn parse_level(level: Option<String>) -> LuaResult<LogLevel> {
+    match level.as_deref() {
+        None | Some("info") => Ok(LogLevel::Info),
+        Some("warning") => Ok(LogLevel::Warning),
+        Some("error") => Ok(LogLevel::Error),
+        Some(other) => Err(LuaError::external(format!(
+            "unknown log level '{}', expected 'info', 'warning' or 'error'",
+            other
+        ))),
+    }
+}
+
+fn passes(level: LogLevel, filter: LogLevel) -> bool {
+    level_rank(level) >= level_rank(filter)
+}
+
+/// Sends a message to the log file and stderr right away, and queues it for Lua.
+///
+/// Used for the engine's messages and for our own warnings alike.
+pub fn log_message(level: LogLevel, message: &str) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_default();
+
+    let Ok(mut routing) = LOG_ROUTING.lock() else {
+        return;
+    };
+
+    if let Some((file, filter)) = &mut routing.file
+        && passes(level, *filter)
+    {
+        let _ = writeln!(file, "{:.3} [{}] {}", timestamp, level_name(level), message);
+    }
+
+    match routing.lua_level {
+        Some(filter) if passes(level, filter) => {
+            if routing.pending.len() >= MAX_PENDING_MESSAGES {
+                routing.pending.pop_front();
+            }
+            routing.pending.push_back(LogRecord {
+                level,
+                message: message.to_string(),
+                timestamp,
+            });
+        }
+        Some(_) => {}
+        // stderr is only a fallback for when nothing else listens
+        None if routing.file.is_none() && passes(level, routing.stderr_level) => {
+            eprintln!("ultralight [{}] {}", level_name(level), message);
+        }
+        None => {}
+    }
+}
+
+pub struct Logger;
+
+impl platform::Logger for Logger {
+    fn log_message(&mut self, log_level: LogLevel, message: String) {
+        let message = CAPTURED.with(|cell| match cell.borrow_mut().as_mut() {
+            Some(captured) => {
+                captured.push(message);
+                None
+            }
+            None => Some(message),
+        });
+        if let Some(message) = message {
+            log_message(log_level, &message);
+        }
+    }
+}
+
+/// Runs `f`, collecting what the engine logs meanwhile instead of routing it.
+pub fn logger_capture(f: impl FnOnce()) -> Vec<String> {
+    let previous = CAPTURED.with(|cell| cell.replace(Some(Vec::new())));
+    f();
+    CAPTURED
+        .with(|cell| cell.replace(previous))
+        .unwrap_or_default()
+}
+
+pub fn logger_set_callback(
+    lua: &Lua,
+    callback: Option<LuaFunction>,
+    level: Option<String>,
+) -> LuaResult<()> {
+    let level = parse_level(level)?;
+    let callback = callback
+        .map(|callback| lua.create_registry_value(callback))
+        .transpose()?;
+
+    let mut routing = LOG_ROUTING
+        .lock()
+        .map_err(|_| LuaError::external("logger lock poisoned"))?;
+    routing.lua_level = callback.as_ref().map(|_| level);
+    if callback.is_none() {
+        routing.pending.clear();
+    }
+
+    LUA_LOGGER_CALLBACK.with(|cell| *cell.borrow_mut() = callback);
+    lua.expire_registry_values();
+
+    Ok(())
+}
+
+pub fn logger_set_file(path: Option<String>, level: Option<String>) -> LuaResult<()> {
+    let level = parse_level(level)?;
+    let file = match path {
+        Some(path) => Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| {
+                    LuaError::external(format!("cannot open log file '{}': {}", path, e))
+                })?,
+        ),
+        None => None,
+    };
+
+    LOG_ROUTING
+        .lock()
+        .map_err(|_| LuaError::external("logger lock poisoned"))?
+        .file = file.map(|file| (file, level));
+
+    Ok(())
+}
+
+/// Hands the queued messages to the Lua callback.
+pub fn logger_update(lua: &Lua) {
+    let records: Vec<LogRecord> = match LOG_ROUTING.lock() {
+        Ok(mut routing) => routing.pending.drain(..).collect(),
+        Err(_) => return,
+    };
+    if records.is_empty() {
+        return;
+    }
+
+    let callback = LUA_LOGGER_CALLBACK.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .and_then(|key| lua.registry_value::<LuaFunction>(key).ok())
+    });
+    let Some(callback) = callback else {
+        return;
+    };
+
+    for record in records {
+        let _ = callback.call::<()>((level_name(record.level), record.message, record.timestamp));
+    }
+}
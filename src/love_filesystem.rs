@@ -0,0 +1,61 @@
+use crate::vfs::{Source, normalize_path};
+use mlua::prelude::*;
+use std::path::{Path, PathBuf};
+
+/// Reads files the way `love.filesystem` sees them, without calling into Lua.
+///
+/// The save directory is searched first, then the game source, so files
+/// written by the game override the ones it shipped with.
+pub struct LoveFilesystem {
+    root: String,
+    save_directory: Option<Source>,
+    // a directory, a .love file, or the executable of a fused game
+    source: Option<Source>,
+}
+
+impl LoveFilesystem {
+    pub fn new(lua: &Lua, root: &str) -> LuaResult<Self> {
+        let love_filesystem = lua
+            .globals()
+            .get::<LuaTable>("love")?
+            .get::<LuaTable>("filesystem")?;
+
+        let save_directory = love_filesystem
+            .get::<LuaFunction>("getSaveDirectory")?
+            .call::<Option<String>>(())?
+            .map(|path| Source::Directory(PathBuf::from(path)));
+
+        let source = match love_filesystem
+            .get::<LuaFunction>("getSource")?
+            .call::<Option<String>>(())?
+        {
+            Some(path) if Path::new(&path).is_dir() => Some(Source::Directory(path.into())),
+            Some(path) => Some(Source::open_archive(Path::new(&path)).map_err(LuaError::external)?),
+            None => None,
+        };
+
+        Ok(LoveFilesystem {
+            root: normalize_path("", root).unwrap_or_default(),
+            save_directory,
+            source,
+        })
+    }
+
+    fn sources(&self) -> impl Iterator<Item = &Source> {
+        self.save_directory.iter().chain(self.source.iter())
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        let Some(path) = normalize_path(&self.root, path) else {
+            return false;
+        };
+
+        self.sources().any(|source| source.exists(&path))
+    }
+
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let path = normalize_path(&self.root, path)?;
+
+        self.sources().find_map(|source| source.read(&path))
+    }
+}
@@ -0,0 +1,155 @@
+use crate::logger::logger_capture;
+use crate::ultralight_renderer::{
+    in_engine_call, renderer_log_memory_usage, renderer_purge_memory,
+};
+use crate::ultralight_view::{UltralightView, live_views};
+use mlua::prelude::*;
+use std::{
+    cell::Cell,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// the `love.handlers.lowmemory` we wrapped, put back when automatic purging stops
+const PREVIOUS_HANDLER_KEY: &str = "love-ultralight.memory.lowmemory";
+
+#[derive(Default, Clone, Copy)]
+struct MemoryCounters {
+    purges: u64,
+    automatic_purges: u64,
+    low_memory_events: u64,
+    last_purge: Option<f64>,
+}
+
+thread_local! {
+    static COUNTERS: Cell<MemoryCounters> = Cell::new(MemoryCounters::default());
+    static AUTO_PURGE: Cell<bool> = const { Cell::new(false) };
+}
+
+fn update_counters(f: impl FnOnce(&mut MemoryCounters)) {
+    COUNTERS.with(|cell| {
+        let mut counters = cell.get();
+        f(&mut counters);
+        cell.set(counters);
+    });
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// Runs a JavaScript garbage collection in every view.
+fn collect_js_garbage(lua: &Lua) -> LuaResult<()> {
+    for userdata in live_views(lua)? {
+        // a view busy running one of its own callbacks is skipped
+        if let Ok(view) = userdata.borrow::<UltralightView>() {
+            view.view.lock_js_context().garbage_collect();
+        }
+    }
+    Ok(())
+}
+
+/// Frees as much memory as the engine can spare: JavaScript garbage, caches
+/// and the memory of views that don't need it.
+///
+/// Ultralight forbids purging from inside its own callbacks, so this fails
+/// when called from a view's event handlers or JavaScript.
+pub fn memory_purge(lua: &Lua) -> LuaResult<()> {
+    if in_engine_call() {
+        return Err(LuaError::external(
+            "memory can't be purged from inside a view's callbacks",
+        ));
+    }
+
+    collect_js_garbage(lua)?;
+    renderer_purge_memory();
+    update_counters(|counters| {
+        counters.purges += 1;
+        counters.last_purge = Some(now());
+    });
+    Ok(())
+}
+
+pub fn memory_get_stats(lua: &Lua) -> LuaResult<LuaTable> {
+    let views = lua.create_table()?;
+    let mut surface_bytes = 0;
+
+    for userdata in live_views(lua)? {
+        let Ok(view) = userdata.borrow::<UltralightView>() else {
+            continue;
+        };
+        // GPU views render into textures we can't measure, they report 0
+        let bytes = view
+            .view
+            .surface()
+            .map(|surface| surface.bytes_size())
+            .unwrap_or(0);
+        surface_bytes += bytes;
+
+        let entry = lua.create_table()?;
+        entry.set("view", &userdata)?;
+        entry.set("width", view.view.width())?;
+        entry.set("height", view.view.height())?;
+        entry.set("surfaceBytes", bytes)?;
+        views.push(entry)?;
+    }
+
+    let counters = COUNTERS.with(|cell| cell.get());
+    let stats = lua.create_table()?;
+    stats.set("viewCount", views.raw_len())?;
+    stats.set("views", views)?;
+    stats.set("surfaceBytes", surface_bytes)?;
+    stats.set("purgeCount", counters.purges)?;
+    stats.set("autoPurgeCount", counters.automatic_purges)?;
+    stats.set("lowMemoryCount", counters.low_memory_events)?;
+    stats.set("lastPurgeTime", counters.last_purge)?;
+    stats.set("autoPurge", AUTO_PURGE.with(|cell| cell.get()))?;
+    stats.set("report", logger_capture(renderer_log_memory_usage))?;
+
+    Ok(stats)
+}
+
+fn love_handlers(lua: &Lua) -> LuaResult<LuaTable> {
+    lua.globals()
+        .get::<LuaTable>("love")
+        .and_then(|love| love.get::<LuaTable>("handlers"))
+        .map_err(|_| LuaError::external("love.handlers is not available"))
+}
+
+/// Purges memory whenever love reports `lowmemory`, after love's own handler.
+pub fn memory_set_auto_purge(lua: &Lua, enabled: bool) -> LuaResult<()> {
+    if AUTO_PURGE.with(|cell| cell.get()) == enabled {
+        return Ok(());
+    }
+
+    let handlers = love_handlers(lua)?;
+
+    if enabled {
+        let previous: Option<LuaFunction> = handlers.get("lowmemory")?;
+        lua.set_named_registry_value(PREVIOUS_HANDLER_KEY, previous)?;
+
+        let handler = lua.create_function(|lua, args: LuaMultiValue| {
+            update_counters(|counters| counters.low_memory_events += 1);
+
+            if let Some(previous) =
+                lua.named_registry_value::<Option<LuaFunction>>(PREVIOUS_HANDLER_KEY)?
+            {
+                previous.call::<()>(args)?;
+            }
+
+            memory_purge(lua)?;
+            update_counters(|counters| counters.automatic_purges += 1);
+            Ok(())
+        })?;
+        handlers.set("lowmemory", handler)?;
+    } else {
+        let previous: Option<LuaFunction> = lua.named_registry_value(PREVIOUS_HANDLER_KEY)?;
+        handlers.set("lowmemory", previous)?;
+        lua.unset_named_registry_value(PREVIOUS_HANDLER_KEY)?;
+    }
+
+    AUTO_PURGE.with(|cell| cell.set(enabled));
+    Ok(())
+}
@@ -0,0 +1,282 @@
+use std::path::Path;
+
+pub const UNKNOWN_MIME_TYPE: &str = "application/unknown";
+pub const DEFAULT_CHARSET: &str = "utf-8";
+
+static MIME_TYPES: phf::Map<&'static str, &'static str> = phf_macros::phf_map! {
+    "html" => "text/html",
+    "htm" => "text/html",
+    "css" => "text/css",
+    "js" => "text/javascript",
+    "mjs" => "text/javascript",
+    "json" => "application/json",
+    "txt" => "text/plain",
+    "xml" => "application/xml",
+    "svg" => "image/svg+xml",
+    "png" => "image/png",
+    "jpg" => "image/jpeg",
+    "jpeg" => "image/jpeg",
+    "gif" => "image/gif",
+    "webp" => "image/webp",
+    "ico" => "image/x-icon",
+    "ttf" => "font/ttf",
+    "otf" => "font/otf",
+    "woff" => "font/woff",
+    "woff2" => "font/woff2",
+    "mp3" => "audio/mpeg",
+    "ogg" => "audio/ogg",
+    "wav" => "audio/wav",
+    "mp4" => "video/mp4",
+    "webm" => "video/webm",
+    "wasm" => "application/wasm",
+    // Ultralight image sources, served as raw pixels
+    "imgsrc" => "image/x-imgsrc",
+};
+
+// signatures checked when the extension doesn't tell the type
+static SIGNATURES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"wOFF", "font/woff"),
+    (b"wOF2", "font/woff2"),
+    (b"OTTO", "font/otf"),
+    (b"\x00\x01\x00\x00", "font/ttf"),
+    (b"\x00asm", "application/wasm"),
+    (b"ID3", "audio/mpeg"),
+    (b"OggS", "audio/ogg"),
+    (b"\x1a\x45\xdf\xa3", "video/webm"),
+];
+
+/// Guesses a MIME type from the file extension.
+pub fn mime_type_for_path(path: &str) -> Option<&'static str> {
+    Path::new(path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(|extension| MIME_TYPES.get(extension.to_ascii_lowercase().as_str()))
+        .copied()
+}
+
+/// Guesses a MIME type from the first bytes of a file.
+pub fn sniff_mime_type(data: &[u8]) -> Option<&'static str> {
+    if let Some((_, mime_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| data.starts_with(signature))
+    {
+        return Some(mime_type);
+    }
+
+    if data.len() >= 12 && &data[..4] == b"RIFF" {
+        match &data[8..12] {
+            b"WEBP" => return Some("image/webp"),
+            b"WAVE" => return Some("audio/wav"),
+            _ => {}
+        }
+    }
+
+    if data.len() >= 8 && &data[4..8] == b"ftyp" {
+        return Some("video/mp4");
+    }
+
+    let text = skip_bom(data);
+    let start = text.iter().position(|byte| !byte.is_ascii_whitespace())?;
+    let head = String::from_utf8_lossy(&text[start..text.len().min(start + 512)]).to_lowercase();
+
+    if head.starts_with("<!doctype html") || head.starts_with("<html") {
+        Some("text/html")
+    } else if head.starts_with("<svg") || (head.starts_with("<?xml") && head.contains("<svg")) {
+        Some("image/svg+xml")
+    } else if head.starts_with("<?xml") {
+        Some("application/xml")
+    } else {
+        None
+    }
+}
+
+/// Guesses the MIME type of a file, from its extension or else its contents.
+///
+/// `contents` is only called for files without a known extension.
+pub fn detect_mime_type(path: &str, contents: impl FnOnce() -> Option<Vec<u8>>) -> &'static str {
+    mime_type_for_path(path)
+        .or_else(|| contents().and_then(|data| sniff_mime_type(&data)))
+        .unwrap_or(UNKNOWN_MIME_TYPE)
+}
+
+/// Whether files of this type may declare a charset.
+pub fn is_text_mime_type(mime_type: &str) -> bool {
+    mime_type.starts_with("text/")
+        || matches!(
+            mime_type,
+            "application/json" | "application/xml" | "image/svg+xml"
+        )
+}
+
+fn skip_bom(data: &[u8]) -> &[u8] {
+    data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data)
+}
+
+/// Finds the `charset` a `<meta>` tag declares, in lowercase HTML.
+///
+/// Both `<meta charset="...">` and `<meta http-equiv="Content-Type" content="...; charset=...">`
+/// count, `charset=` anywhere else does not.
+fn meta_charset(html: &str) -> Option<&str> {
+    let mut rest = html;
+    while let Some(start) = rest.find("<meta") {
+        let tag = &rest[start + "<meta".len()..];
+        let (tag, after) = tag.split_at(tag.find('>').unwrap_or(tag.len()));
+        rest = after;
+
+        if !tag.starts_with(|c: char| c.is_ascii_whitespace() || c == '/') {
+            continue;
+        }
+
+        let attribute = tag.match_indices("charset=").find(|(index, _)| {
+            tag[..*index].ends_with(|c: char| c.is_ascii_whitespace() || c == '/')
+        });
+        if let Some((index, _)) = attribute {
+            return Some(&tag[index + "charset=".len()..]);
+        }
+
+        if !tag.contains("http-equiv") {
+            continue;
+        }
+        let Some(content) = tag.find("content=") else {
+            continue;
+        };
+        if let Some(index) = tag[content..].find("charset=") {
+            return Some(&tag[content + index + "charset=".len()..]);
+        }
+    }
+    None
+}
+
+/// Finds the charset of a text file, from its byte order mark, or else a
+/// `<meta>` declaration in HTML or a leading `@charset` rule in CSS.
+pub fn detect_charset(mime_type: &str, data: &[u8]) -> Option<String> {
+    if data.starts_with(b"\xef\xbb\xbf") {
+        return Some("utf-8".to_string());
+    }
+    if data.starts_with(b"\xff\xfe") {
+        return Some("utf-16le".to_string());
+    }
+    if data.starts_with(b"\xfe\xff") {
+        return Some("utf-16be".to_string());
+    }
+
+    // declarations must come early in the file to count, like in browsers
+    let head = String::from_utf8_lossy(&data[..data.len().min(1024)]);
+
+    let declaration = match mime_type {
+        // the rule must be the very first thing in the file, spelled exactly like this
+        "text/css" => head.strip_prefix("@charset \"")?.to_lowercase(),
+        "text/html" => meta_charset(&head.to_lowercase())?.to_string(),
+        _ => return None,
+    };
+
+    let charset: String = declaration
+        .trim_start_matches([' ', '"', '\''])
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+        .collect();
+
+    (!charset.is_empty()).then_some(charset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mime_type_for_path_uses_the_extension() {
+        assert_eq!(mime_type_for_path("ui/index.HTML"), Some("text/html"));
+        assert_eq!(mime_type_for_path("fonts/a.woff2"), Some("font/woff2"));
+        assert_eq!(mime_type_for_path("README"), None);
+    }
+
+    #[test]
+    fn detect_mime_type_sniffs_files_without_extension() {
+        assert_eq!(
+            detect_mime_type("logo", || Some(b"\x89PNG\r\n\x1a\n....".to_vec())),
+            "image/png"
+        );
+        assert_eq!(
+            detect_mime_type("page", || Some(b"\n  <!DOCTYPE html><p>".to_vec())),
+            "text/html"
+        );
+        assert_eq!(
+            detect_mime_type("icon", || Some(b"<?xml version=\"1.0\"?>\n<svg>".to_vec())),
+            "image/svg+xml"
+        );
+        assert_eq!(
+            detect_mime_type("sound", || Some(b"RIFF\0\0\0\0WAVEfmt ".to_vec())),
+            "audio/wav"
+        );
+        assert_eq!(detect_mime_type("blob", || None), UNKNOWN_MIME_TYPE);
+        assert_eq!(
+            detect_mime_type("style.css", || panic!("contents not needed")),
+            "text/css"
+        );
+    }
+
+    #[test]
+    fn detect_charset_reads_boms_and_declarations() {
+        assert_eq!(
+            detect_charset("text/javascript", b"\xff\xfe<\0"),
+            Some("utf-16le".to_string())
+        );
+        assert_eq!(
+            detect_charset("text/html", b"<head><meta charset=\"ISO-8859-1\">"),
+            Some("iso-8859-1".to_string())
+        );
+        assert_eq!(
+            detect_charset(
+                "text/html",
+                b"<meta http-equiv=\"Content-Type\" content=\"text/html; charset=windows-1252\">"
+            ),
+            Some("windows-1252".to_string())
+        );
+        assert_eq!(
+            detect_charset("text/css", b"@charset \"Shift_JIS\";\nbody {}"),
+            Some("shift_jis".to_string())
+        );
+        assert_eq!(detect_charset("text/html", b"<p>no charset</p>"), None);
+    }
+
+    #[test]
+    fn detect_charset_looks_past_http_equiv_without_content() {
+        assert_eq!(
+            detect_charset(
+                "text/html",
+                b"<meta http-equiv=\"X-UA-Compatible\"><meta charset=\"koi8-r\">"
+            ),
+            Some("koi8-r".to_string())
+        );
+    }
+
+    #[test]
+    fn detect_charset_ignores_charset_outside_declarations() {
+        assert_eq!(
+            detect_charset(
+                "text/javascript",
+                b"const header = 'text/html; charset=latin1';"
+            ),
+            None
+        );
+        assert_eq!(
+            detect_charset("text/css", b"/* @charset \"latin1\"; */ body {}"),
+            None
+        );
+        assert_eq!(
+            detect_charset("text/html", b"<p>use charset=latin1</p><meta name=\"x\">"),
+            None
+        );
+        assert_eq!(
+            detect_charset(
+                "text/html",
+                b"<meta name=\"note\" content=\"charset=latin1\">"
+            ),
+            None
+        );
+    }
+}
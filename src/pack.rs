@@ -0,0 +1,201 @@
+use crate::vfs::{ArchiveReader, MAX_ARCHIVE_FILE_SIZE, read_capped};
+use flate2::{Compression, read::DeflateDecoder, write::DeflateEncoder};
+use std::{
+    collections::HashMap,
+    io::{self, Read, SeekFrom, Write},
+    sync::Mutex,
+};
+
+pub const PACK_MAGIC: &[u8; 4] = b"LUPK";
+const PACK_VERSION: u32 = 1;
+const FLAG_DEFLATE: u8 = 1;
+
+struct PackEntry {
+    offset: u64,
+    size: u64,
+    compressed: bool,
+}
+
+/// A minimal indexed archive of UI assets.
+///
+/// Layout, all integers little endian:
+///
+/// ```text
+/// magic "LUPK" | version: u32 | entry count: u32
+/// entries: flags: u8 | path length: u16 | path (utf-8) | offset: u64 | size: u64
+/// file data, at the offsets given in the index
+/// ```
+///
+/// Bit 0 of the flags marks entries compressed with raw deflate.
+pub struct Pack {
+    reader: Mutex<Box<dyn ArchiveReader>>,
+    entries: HashMap<String, PackEntry>,
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+impl Pack {
+    pub fn new(mut reader: Box<dyn ArchiveReader>) -> io::Result<Self> {
+        reader.seek(SeekFrom::Start(0))?;
+
+        if &read_array::<4>(&mut reader)? != PACK_MAGIC {
+            return Err(invalid_data("not a pack file"));
+        }
+        if u32::from_le_bytes(read_array(&mut reader)?) != PACK_VERSION {
+            return Err(invalid_data("unsupported pack version"));
+        }
+
+        let count = u32::from_le_bytes(read_array(&mut reader)?);
+        let index_start = reader.stream_position()?;
+        let len = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(index_start))?;
+
+        let mut entries = HashMap::new();
+
+        for _ in 0..count {
+            let [flags] = read_array::<1>(&mut reader)?;
+            let path_len = u16::from_le_bytes(read_array(&mut reader)?);
+
+            let mut path = vec![0; path_len as usize];
+            reader.read_exact(&mut path)?;
+            let path =
+                String::from_utf8(path).map_err(|_| invalid_data("pack path is not utf-8"))?;
+
+            let entry = PackEntry {
+                offset: u64::from_le_bytes(read_array(&mut reader)?),
+                size: u64::from_le_bytes(read_array(&mut reader)?),
+                compressed: flags & FLAG_DEFLATE != 0,
+            };
+            if entry
+                .offset
+                .checked_add(entry.size)
+                .is_none_or(|end| end > len)
+            {
+                return Err(invalid_data("pack entry is out of bounds"));
+            }
+            entries.insert(path, entry);
+        }
+
+        Ok(Pack {
+            reader: Mutex::new(reader),
+            entries,
+        })
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.entries.contains_key(path)
+    }
+
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let entry = self.entries.get(path)?;
+
+        let data = {
+            let mut reader = self.reader.lock().ok()?;
+            reader.seek(SeekFrom::Start(entry.offset)).ok()?;
+            read_capped(reader.as_mut().take(entry.size), MAX_ARCHIVE_FILE_SIZE)?
+        };
+        // the pack may have shrunk since it was opened
+        if data.len() as u64 != entry.size {
+            return None;
+        }
+
+        if !entry.compressed {
+            return Some(data);
+        }
+
+        read_capped(DeflateDecoder::new(data.as_slice()), MAX_ARCHIVE_FILE_SIZE)
+    }
+}
+
+/// Builds a pack holding `files`, compressing each entry when `compress` is set.
+pub fn write_pack(files: &[(String, Vec<u8>)], compress: bool) -> io::Result<Vec<u8>> {
+    let mut contents = Vec::with_capacity(files.len());
+    for (_, data) in files {
+        if compress {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data)?;
+            contents.push(encoder.finish()?);
+        } else {
+            contents.push(data.clone());
+        }
+    }
+
+    let index_len: usize = files
+        .iter()
+        .map(|(path, _)| 1 + 2 + path.len() + 8 + 8)
+        .sum();
+    let mut offset = (4 + 4 + 4 + index_len) as u64;
+
+    let mut pack = Vec::new();
+    pack.extend_from_slice(PACK_MAGIC);
+    pack.extend_from_slice(&PACK_VERSION.to_le_bytes());
+    pack.extend_from_slice(&(files.len() as u32).to_le_bytes());
+
+    for ((path, _), data) in files.iter().zip(&contents) {
+        let path_len =
+            u16::try_from(path.len()).map_err(|_| invalid_data("pack path is too long"))?;
+
+        pack.push(if compress { FLAG_DEFLATE } else { 0 });
+        pack.extend_from_slice(&path_len.to_le_bytes());
+        pack.extend_from_slice(path.as_bytes());
+        pack.extend_from_slice(&offset.to_le_bytes());
+        pack.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        offset += data.len() as u64;
+    }
+
+    for data in contents {
+        pack.extend_from_slice(&data);
+    }
+
+    Ok(pack)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn files() -> Vec<(String, Vec<u8>)> {
+        vec![
+            ("index.html".to_string(), b"<h1>hi</h1>".repeat(20)),
+            ("css/app.css".to_string(), b"body{}".to_vec()),
+        ]
+    }
+
+    #[test]
+    fn pack_round_trips() {
+        for compress in [false, true] {
+            let bytes = write_pack(&files(), compress).unwrap();
+            let pack = Pack::new(Box::new(Cursor::new(bytes))).unwrap();
+
+            assert!(pack.contains("css/app.css"));
+            assert!(!pack.contains("missing.js"));
+            assert_eq!(pack.read("index.html"), Some(b"<h1>hi</h1>".repeat(20)));
+            assert_eq!(pack.read("css/app.css"), Some(b"body{}".to_vec()));
+        }
+    }
+
+    #[test]
+    fn pack_rejects_other_files() {
+        assert!(Pack::new(Box::new(Cursor::new(b"PK\x03\x04".to_vec()))).is_err());
+        assert!(Pack::new(Box::new(Cursor::new(b"LUPK".to_vec()))).is_err());
+    }
+
+    #[test]
+    fn pack_rejects_entries_past_the_end() {
+        let mut bytes = write_pack(&files(), false).unwrap();
+        // grow the size of the first entry, stored right before the second one
+        let size_at = 4 + 4 + 4 + 1 + 2 + "index.html".len() + 8;
+        bytes[size_at..size_at + 8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        assert!(Pack::new(Box::new(Cursor::new(bytes))).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::conversion::{Converter, PageContext, PageValue, lua_error_to_js_error};
+use crate::ultralight_renderer::renderer_get_lib;
+use mlua::prelude::*;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::c_void,
+    sync::{
+        Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+use ul_next::javascript::{AsJSValue, JSClass, JSClassDelegate, JSContext, JSObject, JSValue};
+
+thread_local! {
+    static TABLE_CLASS: JSClass<LuaTableProxy> = JSClass::new(renderer_get_lib(), "LuaTable");
+    // the Lua side of every live proxy, only touched on the main thread
+    static PROXIES: RefCell<HashMap<u64, ProxiedTable>> = RefCell::new(HashMap::new());
+    static PROXY_LUA: RefCell<Option<WeakLua>> = const { RefCell::new(None) };
+    // one proxy per table and page, so reading a table twice gives the same object
+    static PROXY_CACHE: RefCell<HashMap<ProxyCacheKey, PageValue>> = RefCell::new(HashMap::new());
+}
+
+type ProxyCacheKey = ((*const Cell<u64>, u64), *const c_void);
+
+static NEXT_PROXY_ID: AtomicU64 = AtomicU64::new(1);
+// proxies finalized by the garbage collector, possibly on another thread
+static FINALIZED_PROXIES: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+
+struct ProxiedTable {
+    table: LuaRegistryKey,
+    page: PageContext,
+}
+
+/// A JavaScript object reading and writing a Lua table directly.
+///
+/// Nested tables are exposed as proxies too, other values are converted
+/// each time they are accessed.
+///
+/// The proxy itself only holds an id, the table it gives access to stays on
+/// the main thread until [`proxy_update`] releases it.
+pub struct LuaTableProxy {
+    id: u64,
+}
+
+impl LuaTableProxy {
+    fn state(&self) -> LuaResult<(Lua, LuaTable, PageContext)> {
+        let lua = PROXY_LUA
+            .with(|cell| cell.borrow().as_ref().and_then(WeakLua::try_upgrade))
+            .ok_or_else(|| LuaError::external("Lua state is gone"))?;
+        let (table, page) = PROXIES.with(|proxies| {
+            let proxies = proxies.borrow();
+            let proxied = proxies
+                .get(&self.id)
+                .ok_or_else(|| LuaError::external("Lua table proxy was finalized"))?;
+            LuaResult::Ok((lua.registry_value(&proxied.table)?, proxied.page.clone()))
+        })?;
+        Ok((lua, table, page))
+    }
+}
+
+fn property_key(lua: &Lua, name: &str) -> LuaResult<LuaValue> {
+    match name.parse::<i64>() {
+        Ok(index) => Ok(LuaValue::Integer(index)),
+        Err(_) => lua.create_string(name).map(LuaValue::String),
+    }
+}
+
+impl JSClassDelegate for LuaTableProxy {
+    fn has_property(&self, _ctx: &JSContext, _object: &JSObject, name: &str) -> bool {
+        let value = self
+            .state()
+            .and_then(|(lua, table, _)| table.get::<LuaValue>(property_key(&lua, name)?));
+
+        matches!(value, Ok(value) if !value.is_nil())
+    }
+
+    fn get_property<'c>(
+        &self,
+        ctx: &'c JSContext,
+        _object: &JSObject<'c>,
+        name: &str,
+    ) -> Result<Option<JSValue<'c>>, JSValue<'c>> {
+        let result = (|| {
+            let (lua, table, page) = self.state()?;
+            let value = table.get::<LuaValue>(property_key(&lua, name)?)?;
+
+            match value {
+                LuaValue::Nil => Ok(None),
+                LuaValue::Table(table) => {
+                    expose_table(&lua, ctx, table, &page).map(|obj| Some(obj.into_value()))
+                }
+                value => Converter::new(&lua, &page).lua_to_js(ctx, value).map(Some),
+            }
+        })();
+
+        result.map_err(|e| lua_error_to_js_error(ctx, e))
+    }
+
+    fn set_property<'c>(
+        &self,
+        ctx: &'c JSContext,
+        _object: &JSObject<'c>,
+        name: &str,
+        value: &JSValue<'c>,
+    ) -> Result<bool, JSValue<'c>> {
+        let result = (|| {
+            let (lua, table, page) = self.state()?;
+            let value = Converter::new(&lua, &page).js_to_lua(ctx, value)?;
+            table.set(property_key(&lua, name)?, value)
+        })();
+
+        result
+            .map(|()| true)
+            .map_err(|e| lua_error_to_js_error(ctx, e))
+    }
+
+    fn delete_property<'c>(
+        &self,
+        ctx: &'c JSContext,
+        _object: &JSObject<'c>,
+        name: &str,
+    ) -> Result<bool, JSValue<'c>> {
+        let result = (|| {
+            let (lua, table, _) = self.state()?;
+            table.set(property_key(&lua, name)?, LuaValue::Nil)
+        })();
+
+        result
+            .map(|()| true)
+            .map_err(|e| lua_error_to_js_error(ctx, e))
+    }
+
+    fn property_names(&self, _ctx: &JSContext, _object: &JSObject) -> Vec<String> {
+        let Ok((_, table, _)) = self.state() else {
+            return Vec::new();
+        };
+
+        table
+            .pairs::<LuaValue, LuaValue>()
+            .filter_map(|pair| match pair {
+                Ok((LuaValue::String(key), _)) => Some(key.to_string_lossy()),
+                Ok((LuaValue::Integer(key), _)) => Some(key.to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn finalize(&mut self) {
+        // the registry key must be released on the main thread
+        FINALIZED_PROXIES.lock().unwrap().push(self.id);
+    }
+}
+
+/// Creates a JavaScript object giving live access to a Lua table.
+///
+/// A page always gets the same object for the same table. The cache keeps the
+/// object alive until the page is gone.
+pub fn expose_table<'a>(
+    lua: &Lua,
+    js_ctx: &'a JSContext,
+    table: LuaTable,
+    page: &PageContext,
+) -> LuaResult<JSObject<'a>> {
+    let cache_key = (page.key(), table.to_pointer());
+    let cached = PROXY_CACHE.with(|cache| {
+        cache
+            .borrow()
+            .get(&cache_key)
+            .map(|proxy| proxy.value_in(js_ctx))
+    });
+    if let Some(proxy) = cached {
+        return proxy
+            .as_object()
+            .map_err(|_| LuaError::external("cached Lua table proxy is not an object"));
+    }
+
+    let proxied = ProxiedTable {
+        table: lua.create_registry_value(table)?,
+        page: page.clone(),
+    };
+    let id = NEXT_PROXY_ID.fetch_add(1, Ordering::Relaxed);
+    PROXY_LUA.with(|cell| *cell.borrow_mut() = Some(lua.weak()));
+    PROXIES.with(|proxies| proxies.borrow_mut().insert(id, proxied));
+    let proxy = LuaTableProxy { id };
+
+    let proxy = TABLE_CLASS.with(|class| class.make_object(js_ctx, proxy));
+    PROXY_CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .insert(cache_key, PageValue::new(&proxy, page))
+    });
+    Ok(proxy)
+}
+
+/// Returns the Lua table behind a proxy created by [`expose_table`].
+pub fn proxied_table(lua: &Lua, object: &JSObject) -> LuaResult<Option<LuaTable>> {
+    TABLE_CLASS.with(|class| match class.data(object) {
+        Some(proxy) => PROXIES.with(|proxies| match proxies.borrow().get(&proxy.id) {
+            Some(proxied) => lua.registry_value(&proxied.table).map(Some),
+            None => Ok(None),
+        }),
+        None => Ok(None),
+    })
+}
+
+/// Forgets the proxies of unloaded pages, and releases the tables of proxies
+/// the garbage collector finalized.
+pub fn proxy_update() {
+    // released once the cache is no longer borrowed, under the lock of their view
+    let unloaded: Vec<_> = PROXY_CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .extract_if(|_, proxy| !proxy.page().is_alive())
+            .collect()
+    });
+    drop(unloaded);
+
+    let finalized = std::mem::take(&mut *FINALIZED_PROXIES.lock().unwrap());
+    PROXIES.with(|proxies| {
+        let mut proxies = proxies.borrow_mut();
+        for id in finalized {
+            proxies.remove(&id);
+        }
+    });
+}
@@ -0,0 +1,191 @@
+use crate::vfs::{PathError, normalize_path, resolve_path};
+use std::{collections::HashSet, fmt};
+
+/// Why a file request was refused.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Denied {
+    InvalidEncoding,
+    Traversal,
+    NotAFile,
+    Extension,
+    OutsideRoot,
+}
+
+impl From<PathError> for Denied {
+    fn from(error: PathError) -> Self {
+        match error {
+            PathError::Traversal => Denied::Traversal,
+            PathError::NotAFile => Denied::NotAFile,
+        }
+    }
+}
+
+impl fmt::Display for Denied {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Denied::InvalidEncoding => write!(f, "invalid percent-encoding"),
+            Denied::Traversal => write!(f, "path leaves the filesystem root"),
+            Denied::NotAFile => write!(f, "path does not name a file"),
+            Denied::Extension => write!(f, "file extension is not allowed"),
+            Denied::OutsideRoot => write!(f, "file is outside the sandbox root"),
+        }
+    }
+}
+
+/// Limits which files pages may request.
+///
+/// Every path is cleaned up and checked here before it reaches Lua or a
+/// built-in backend. The root doesn't change the path, it only limits which
+/// paths may be requested, see [`Sandbox::contains`].
+pub struct Sandbox {
+    root: String,
+    extensions: Option<HashSet<String>>,
+    pub log_denied: bool,
+}
+
+impl Default for Sandbox {
+    fn default() -> Self {
+        Sandbox {
+            root: String::new(),
+            extensions: None,
+            log_denied: true,
+        }
+    }
+}
+
+impl Sandbox {
+    pub fn new(root: &str, extensions: Option<Vec<String>>, log_denied: bool) -> Self {
+        Sandbox {
+            root: normalize_path("", root).unwrap_or_default(),
+            extensions: extensions.map(|extensions| {
+                extensions
+                    .iter()
+                    .map(|extension| extension.trim_start_matches('.').to_ascii_lowercase())
+                    .collect()
+            }),
+            log_denied,
+        }
+    }
+
+    /// Turns a requested path into a clean relative path, or refuses it.
+    pub fn check(&self, path: &str) -> Result<String, Denied> {
+        let path = resolve_path("", &percent_decode(strip_query(path))?)?;
+
+        if let Some(extensions) = &self.extensions {
+            let name = path.rsplit('/').next().unwrap_or_default();
+            let extension = name.rsplit_once('.').map(|(_, extension)| extension);
+
+            if !extension
+                .is_some_and(|extension| extensions.contains(&extension.to_ascii_lowercase()))
+            {
+                return Err(Denied::Extension);
+            }
+        }
+
+        if !self.contains(&path) {
+            return Err(Denied::OutsideRoot);
+        }
+
+        Ok(path)
+    }
+
+    /// Whether `path`, a clean requested path, is inside the root.
+    pub fn contains(&self, path: &str) -> bool {
+        self.root.is_empty()
+            || path
+                .strip_prefix(self.root.as_str())
+                .is_some_and(|rest| rest.starts_with('/'))
+    }
+}
+
+fn strip_query(path: &str) -> &str {
+    match path.find(['?', '#']) {
+        Some(end) => &path[..end],
+        None => path,
+    }
+}
+
+/// Decodes `%XX` escapes, refusing malformed ones and anything that isn't UTF-8.
+fn percent_decode(path: &str) -> Result<String, Denied> {
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3).ok_or(Denied::InvalidEncoding)?;
+            let hex = std::str::from_utf8(hex).map_err(|_| Denied::InvalidEncoding)?;
+            decoded.push(u8::from_str_radix(hex, 16).map_err(|_| Denied::InvalidEncoding)?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+
+    if decoded.contains(&0) {
+        return Err(Denied::InvalidEncoding);
+    }
+
+    String::from_utf8(decoded).map_err(|_| Denied::InvalidEncoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_cleans_up_paths() {
+        let sandbox = Sandbox::default();
+
+        assert_eq!(
+            sandbox.check("/css\\.\\app.css?v=2#top"),
+            Ok("css/app.css".to_string())
+        );
+        assert_eq!(
+            sandbox.check("my%20page/a/../index.html"),
+            Ok("my page/index.html".to_string())
+        );
+        assert_eq!(sandbox.check("bad%2"), Err(Denied::InvalidEncoding));
+        assert_eq!(sandbox.check("nul%00.html"), Err(Denied::InvalidEncoding));
+        assert_eq!(sandbox.check("/"), Err(Denied::NotAFile));
+    }
+
+    #[test]
+    fn check_refuses_to_leave_the_root() {
+        let sandbox = Sandbox::new("ui/", None, false);
+
+        // the root limits which paths may be requested, they are left as they are
+        assert_eq!(
+            sandbox.check("ui/index.html"),
+            Ok("ui/index.html".to_string())
+        );
+        assert_eq!(sandbox.check("index.html"), Err(Denied::OutsideRoot));
+        assert_eq!(sandbox.check("ui.lua"), Err(Denied::OutsideRoot));
+        assert!(sandbox.contains("ui/index.html"));
+        assert!(!sandbox.contains("main.lua"));
+        assert!(!sandbox.contains("ui.lua"));
+        assert!(Sandbox::default().contains("main.lua"));
+
+        assert_eq!(sandbox.check("../main.lua"), Err(Denied::Traversal));
+        assert_eq!(
+            sandbox.check("a/%2e%2e/%2E%2E/conf.lua"),
+            Err(Denied::Traversal)
+        );
+        assert_eq!(sandbox.check("C:/Windows/win.ini"), Err(Denied::Traversal));
+    }
+
+    #[test]
+    fn check_applies_the_extension_allowlist() {
+        let sandbox = Sandbox::new(
+            "",
+            Some(vec![".html".to_string(), "CSS".to_string()]),
+            false,
+        );
+
+        assert!(sandbox.check("index.HTML").is_ok());
+        assert!(sandbox.check("theme.css").is_ok());
+        assert_eq!(sandbox.check("main.lua"), Err(Denied::Extension));
+        assert_eq!(sandbox.check("Makefile"), Err(Denied::Extension));
+    }
+}
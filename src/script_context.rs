@@ -0,0 +1,150 @@
+use crate::conversion::{
+    Converter, JSExceptionInfo, PageContext, ScriptSource, js_error_to_string,
+};
+use crate::ultralight_renderer::renderer_try_get_lib;
+use mlua::UserData;
+use mlua::prelude::*;
+use std::{cell::Cell, rc::Rc};
+use ul_next::javascript::{JSContext, JSObject, JSValue};
+
+/// A JavaScript context that is not attached to a view.
+pub struct ScriptContext {
+    ctx: JSContext,
+    // bumped when the context is dropped, so JS functions kept by Lua stop working
+    generation: Rc<Cell<u64>>,
+}
+
+impl Drop for ScriptContext {
+    fn drop(&mut self) {
+        self.generation.set(self.generation.get() + 1);
+    }
+}
+
+impl ScriptContext {
+    pub fn new(options: Option<LuaTable>) -> LuaResult<Self> {
+        let group = match &options {
+            Some(options) => options
+                .get::<Option<LuaUserDataRef<ScriptContext>>>("group")?
+                .map(|other| other.ctx.group()),
+            None => None,
+        };
+
+        let ctx = match group {
+            Some(group) => JSContext::new_in_group(&group),
+            None => JSContext::new(renderer_try_get_lib()?),
+        };
+
+        if let Some(options) = &options {
+            if let Some(name) = options.get::<Option<String>>("name")? {
+                ctx.set_name(&name);
+            }
+            if let Some(inspectable) = options.get::<Option<bool>>("inspectable")? {
+                ctx.set_inspectable(inspectable);
+            }
+        }
+
+        Ok(ScriptContext {
+            ctx,
+            generation: Rc::new(Cell::new(0)),
+        })
+    }
+
+    fn page(&self) -> PageContext {
+        PageContext::new(&self.generation)
+    }
+
+    /// Finds the function at a dotted path like `"marked.parse"`, and the object it belongs to.
+    fn lookup_function<'a>(
+        ctx: &'a JSContext,
+        path: &str,
+    ) -> Result<(JSObject<'a>, JSObject<'a>), JSValue<'a>> {
+        let mut this = ctx.global_object();
+        let mut names = path.split('.').peekable();
+
+        while let Some(name) = names.next() {
+            let value = this.get_property(name)?;
+            if !value.is_object() {
+                return Err(JSValue::new_string(
+                    ctx,
+                    &format!("'{}' is not a function", path),
+                ));
+            }
+
+            let object = value.as_object()?;
+            if names.peek().is_none() {
+                if !object.is_function() {
+                    break;
+                }
+                return Ok((object, this));
+            }
+            this = object;
+        }
+
+        Err(JSValue::new_string(
+            ctx,
+            &format!("'{}' is not a function", path),
+        ))
+    }
+}
+
+fn script_result(lua: &Lua, result: LuaResult<LuaValue>) -> LuaResult<(LuaValue, LuaValue)> {
+    match result {
+        Ok(value) => Ok((value, LuaValue::Nil)),
+        Err(e) => Ok((
+            LuaValue::Nil,
+            LuaValue::String(lua.create_string(e.to_string())?),
+        )),
+    }
+}
+
+impl UserData for ScriptContext {
+    fn add_methods<M: mlua::UserDataMethods<Self>>(methods: &mut M) {
+        methods.add_method(
+            "evaluate",
+            |lua, this, (script, options): (String, Option<LuaTable>)| {
+                let source = ScriptSource::from_options(options)?;
+                let converter = Converter::new(lua, &this.page());
+
+                let result = match this.ctx.evaluate_script_with_source(
+                    &script,
+                    source.source_url.as_deref(),
+                    source.start_line,
+                ) {
+                    Ok(value) => converter.js_to_lua(&this.ctx, &value),
+                    Err(e) => Err(LuaError::external(JSExceptionInfo::new(&e).to_string())),
+                };
+
+                script_result(lua, result)
+            },
+        );
+
+        methods.add_method(
+            "call",
+            |lua, this, (path, args): (String, LuaMultiValue)| {
+                let converter = Converter::new(lua, &this.page());
+
+                let result = (|| {
+                    let (function, function_this) = Self::lookup_function(&this.ctx, &path)
+                        .map_err(|e| LuaError::external(js_error_to_string(&e)))?;
+
+                    let mut js_args = Vec::with_capacity(args.len());
+                    for arg in args {
+                        js_args.push(converter.lua_to_js(&this.ctx, arg)?);
+                    }
+
+                    match function.call_as_function(Some(&function_this), &js_args) {
+                        Ok(value) => converter.js_to_lua(&this.ctx, &value),
+                        Err(e) => Err(LuaError::external(JSExceptionInfo::new(&e).to_string())),
+                    }
+                })();
+
+                script_result(lua, result)
+            },
+        );
+
+        methods.add_method("garbageCollect", |_, this, ()| {
+            this.ctx.garbage_collect();
+            Ok(())
+        });
+    }
+}
@@ -0,0 +1,165 @@
+use mlua::prelude::*;
+
+// swaps the BGRA pixels Ultralight paints back into RGBA on the GPU
+const SURFACE_SHADER: &str = r#"
+vec4 effect(vec4 color, Image tex, vec2 texture_coords, vec2 screen_coords)
+{
+    return Texel(tex, texture_coords).bgra * color;
+}
+"#;
+
+// the Lua function reading the address of an `ImageData` through the FFI, compiled once
+const ADDRESS_FUNCTION_KEY: &str = "love-ultralight.surface.address";
+
+/// A love `ImageData` holding a copy of a view's pixels.
+///
+/// The bytes are copied as Ultralight paints them, premultiplied BGRA, even
+/// though love sees an `"rgba8"` `ImageData`. The shader from
+/// [`surface_new_shader`] swaps the channels back when drawing.
+///
+/// Lua may release the `ImageData` at any time, so its memory is looked up
+/// again, and checked, before every copy.
+pub struct SurfaceImageData {
+    image_data: LuaRegistryKey,
+    address: usize,
+    width: u32,
+    height: u32,
+    // whether the last pixels of the view were copied in
+    synced: bool,
+}
+
+impl SurfaceImageData {
+    /// Creates an `ImageData` of `width` x `height`.
+    ///
+    /// Gives `None` for empty sizes, or when `love.image` is not loaded.
+    pub fn new(lua: &Lua, width: u32, height: u32) -> LuaResult<Option<Self>> {
+        if width == 0 || height == 0 {
+            return Ok(None);
+        }
+
+        let Some(image) = lua
+            .globals()
+            .get::<Option<LuaTable>>("love")?
+            .map(|love| love.get::<Option<LuaTable>>("image"))
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(None);
+        };
+
+        // holds BGRA bytes, love has no BGRA format for `ImageData`
+        let image_data = image
+            .get::<LuaFunction>("newImageData")?
+            .call::<LuaAnyUserData>((width, height, "rgba8"))?;
+        let address = image_data_address(lua, &image_data)?;
+
+        Ok(Some(SurfaceImageData {
+            image_data: lua.create_registry_value(image_data)?,
+            address,
+            width,
+            height,
+            synced: false,
+        }))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Whether the last pixels of the view were copied in.
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    /// Marks the copy as outdated, for changes `copy_from` won't hear about.
+    pub fn invalidate(&mut self) {
+        self.synced = false;
+    }
+
+    /// Copies `pixels`, BGRA rows `row_bytes` apart, into the `ImageData`.
+    ///
+    /// Fails if Lua released the `ImageData`, or its memory moved.
+    pub fn copy_from(&mut self, lua: &Lua, pixels: &[u8], row_bytes: usize) -> LuaResult<()> {
+        let image_data = self.image_data(lua)?;
+        // love raises an error for released objects
+        let address = image_data_address(lua, &image_data).map_err(|e| {
+            LuaError::external(format!("the View's ImageData can't be used anymore: {}", e))
+        })?;
+        if address != self.address {
+            return Err(LuaError::external("the View's ImageData memory moved"));
+        }
+        let (width, height): (u32, u32) = image_data.call_method("getDimensions", ())?;
+        let width_bytes = width as usize * 4;
+        if (width, height) != (self.width, self.height)
+            || row_bytes < width_bytes
+            || pixels.len() < row_bytes * (height as usize - 1) + width_bytes
+        {
+            return Err(LuaError::external(
+                "the View's ImageData has the wrong size",
+            ));
+        }
+
+        // SAFETY: the `ImageData` is alive and `width_bytes * height` bytes long,
+        // and no Lua runs until the copy is done.
+        let target = unsafe {
+            std::slice::from_raw_parts_mut(address as *mut u8, width_bytes * height as usize)
+        };
+        for (target_row, row) in target
+            .chunks_exact_mut(width_bytes)
+            .zip(pixels.chunks(row_bytes))
+        {
+            target_row.copy_from_slice(&row[..width_bytes]);
+        }
+        self.synced = true;
+        Ok(())
+    }
+
+    pub fn image_data(&self, lua: &Lua) -> LuaResult<LuaAnyUserData> {
+        lua.registry_value(&self.image_data)
+    }
+}
+
+/// The address of the pixels of `image_data`.
+fn image_data_address(lua: &Lua, image_data: &LuaAnyUserData) -> LuaResult<usize> {
+    // love 11 hands out a light userdata, later versions only an FFI pointer
+    if let Ok(LuaValue::LightUserData(pointer)) = image_data.call_method("getPointer", ()) {
+        return Ok(pointer.0 as usize);
+    }
+
+    // written through a pointer, a Lua number could not hold every address
+    let mut address: usize = 0;
+    let slot = LuaLightUserData((&raw mut address).cast());
+    address_function(lua)?.call::<()>((image_data, slot))?;
+    if address == 0 {
+        return Err(LuaError::external("ImageData has no pixels to copy into"));
+    }
+    Ok(address)
+}
+
+fn address_function(lua: &Lua) -> LuaResult<LuaFunction> {
+    if let Some(function) = lua.named_registry_value::<Option<LuaFunction>>(ADDRESS_FUNCTION_KEY)? {
+        return Ok(function);
+    }
+
+    let function = lua
+        .load(
+            "local data, slot = ... \
+             local ffi = require('ffi') \
+             ffi.cast('uintptr_t*', slot)[0] = ffi.cast('uintptr_t', data:getFFIPointer())",
+        )
+        .into_function()?;
+    lua.set_named_registry_value(ADDRESS_FUNCTION_KEY, &function)?;
+    Ok(function)
+}
+
+/// Creates a love `Shader` that draws `ImageData` painted by Ultralight in the right colors.
+pub fn surface_new_shader(lua: &Lua) -> LuaResult<LuaValue> {
+    let graphics: LuaTable = lua.globals().get::<LuaTable>("love")?.get("graphics")?;
+    graphics
+        .get::<LuaFunction>("newShader")?
+        .call(SURFACE_SHADER)
+}
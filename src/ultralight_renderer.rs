@@ -1,11 +1,78 @@
 use crate::clipboard::Clipboard;
 use crate::filesystem::FileSystem;
+use crate::gpu_driver::gpu_driver;
+use crate::logger::Logger;
 use mlua::prelude::*;
-use std::{cell::RefCell, sync::Arc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 use ul_next::{Library, config::Config, platform, renderer::Renderer};
 
+pub const MAIN_DISPLAY_ID: u32 = 0;
+
 thread_local! {
     static ULTRALIGHT_RENDERER: RefCell<Option<UltralightRenderer>> = RefCell::new(None);
+    // displays `draw` refreshes by itself, the others are only refreshed on request
+    static DISPLAY_CLOCKS: RefCell<BTreeMap<u32, DisplayClock>> =
+        RefCell::new(BTreeMap::from([(MAIN_DISPLAY_ID, DisplayClock::new(0.0))]));
+    // how many calls into the engine are running, any Lua code run meanwhile is a callback
+    static ENGINE_CALLS: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Leaves an engine call when dropped, see [`engine_call`].
+struct EngineCall;
+
+impl Drop for EngineCall {
+    fn drop(&mut self) {
+        ENGINE_CALLS.set(ENGINE_CALLS.get() - 1);
+    }
+}
+
+/// Runs `f`, which hands control to the engine and may call back into Lua.
+pub fn engine_call<T>(f: impl FnOnce() -> T) -> T {
+    ENGINE_CALLS.set(ENGINE_CALLS.get() + 1);
+    let _call = EngineCall;
+    f()
+}
+
+/// Whether Lua code is running inside a callback of the engine, see [`engine_call`].
+pub fn in_engine_call() -> bool {
+    ENGINE_CALLS.get() > 0
+}
+
+/// When a display was last refreshed and how often it should be.
+struct DisplayClock {
+    // seconds between refreshes, `0` refreshes on every frame
+    interval: f64,
+    last_refresh: Option<f64>,
+}
+
+impl DisplayClock {
+    fn new(interval: f64) -> Self {
+        DisplayClock {
+            interval,
+            last_refresh: None,
+        }
+    }
+
+    /// Whether the display is due at `now`, moving the clock on if it is.
+    fn tick(&mut self, now: f64) -> bool {
+        let due = match self.last_refresh {
+            Some(last) => now - last >= self.interval,
+            None => true,
+        };
+        if due {
+            // keep a steady pace unless we fell more than a refresh behind
+            self.last_refresh = match self.last_refresh {
+                Some(last) if now - last < self.interval * 2.0 => Some(last + self.interval),
+                _ => Some(now),
+            };
+        }
+        due
+    }
 }
 
 pub struct UltralightRenderer {
@@ -25,6 +92,9 @@ impl UltralightRenderer {
         platform::enable_platform_fontloader(ul_lib.clone());
         platform::set_filesystem(ul_lib.clone(), FileSystem);
         platform::set_clipboard(ul_lib.clone(), Clipboard);
+        platform::set_logger(ul_lib.clone(), Logger);
+        // only used by views created with `accelerated = true`
+        platform::set_gpu_driver(ul_lib.clone(), gpu_driver());
 
         let renderer = Renderer::create(config)
             .map_err(|e| LuaError::external(format!("Failed to create renderer: {}", e)))?;
@@ -45,12 +115,23 @@ impl UltralightRenderer {
     }
 
     pub fn update(&self) {
-        self.renderer.update();
+        engine_call(|| self.renderer.update());
+    }
+
+    pub fn refresh_display(&self, display_id: u32) {
+        engine_call(|| self.renderer.refresh_display(display_id));
+    }
+
+    pub fn render(&self) {
+        engine_call(|| self.renderer.render());
+    }
+
+    pub fn purge_memory(&self) {
+        self.renderer.purge_memory();
     }
 
-    pub fn draw(&self) {
-        self.renderer.refresh_display(0);
-        self.renderer.render();
+    pub fn log_memory_usage(&self) {
+        self.renderer.log_memory_usage();
     }
 
     pub fn get_lib(&self) -> Arc<Library> {
@@ -63,11 +144,17 @@ impl UltralightRenderer {
 }
 
 pub fn renderer_get_lib() -> Arc<Library> {
+    renderer_try_get_lib().expect("Renderer not initialized")
+}
+
+/// Like [`renderer_get_lib`], returning an error instead of panicking when
+/// there is no renderer, before the module is loaded or after `quit`.
+pub fn renderer_try_get_lib() -> LuaResult<Arc<Library>> {
     ULTRALIGHT_RENDERER.with(|cell| {
         cell.borrow()
             .as_ref()
-            .expect("Renderer not initialized")
-            .get_lib()
+            .map(UltralightRenderer::get_lib)
+            .ok_or_else(|| LuaError::external("Ultralight renderer is not initialized"))
     })
 }
 
@@ -99,10 +186,74 @@ pub fn renderer_update() {
     });
 }
 
-pub fn renderer_draw() {
+/// Sets how often `draw` refreshes `display_id`, in refreshes per second.
+///
+/// `0` refreshes it on every frame, `None` leaves it to [`renderer_refresh_display`].
+pub fn renderer_set_display_rate(display_id: u32, rate: Option<f64>) -> LuaResult<()> {
+    if let Some(rate) = rate
+        && (rate.is_nan() || rate < 0.0)
+    {
+        return Err(LuaError::external(format!(
+            "display refresh rate must be positive or 0, got {}",
+            rate
+        )));
+    }
+
+    DISPLAY_CLOCKS.with(|cell| {
+        let mut clocks = cell.borrow_mut();
+        match rate {
+            Some(rate) if rate > 0.0 => clocks.insert(display_id, DisplayClock::new(1.0 / rate)),
+            Some(_) => clocks.insert(display_id, DisplayClock::new(0.0)),
+            None => clocks.remove(&display_id),
+        };
+    });
+    Ok(())
+}
+
+/// The time by `love.timer`, so refreshes follow the game's clock.
+fn love_time(lua: &Lua) -> f64 {
+    lua.globals()
+        .get::<LuaTable>("love")
+        .and_then(|love| love.get::<LuaTable>("timer"))
+        .and_then(|timer| timer.get::<LuaFunction>("getTime"))
+        .and_then(|get_time| get_time.call::<f64>(()))
+        .unwrap_or_else(|_| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs_f64())
+                .unwrap_or_default()
+        })
+}
+
+/// Refreshes every display that is due, see [`renderer_set_display_rate`].
+pub fn renderer_refresh_displays(lua: &Lua) {
+    let now = love_time(lua);
+    let due: Vec<u32> = DISPLAY_CLOCKS.with(|cell| {
+        cell.borrow_mut()
+            .iter_mut()
+            .filter_map(|(display_id, clock)| clock.tick(now).then_some(*display_id))
+            .collect()
+    });
+
+    for display_id in due {
+        renderer_refresh_display(display_id);
+    }
+}
+
+/// Advances animations of the views on `display_id`.
+pub fn renderer_refresh_display(display_id: u32) {
+    ULTRALIGHT_RENDERER.with(|cell| {
+        if let Some(ref renderer) = *cell.borrow() {
+            renderer.refresh_display(display_id);
+        }
+    });
+}
+
+/// Paints every view that needs it.
+pub fn renderer_render() {
     ULTRALIGHT_RENDERER.with(|cell| {
         if let Some(ref renderer) = *cell.borrow() {
-            renderer.draw();
+            renderer.render();
         }
     });
 }
@@ -115,3 +266,48 @@ pub fn renderer_quit() {
         *cell.borrow_mut() = None;
     });
 }
+
+pub fn renderer_purge_memory() {
+    ULTRALIGHT_RENDERER.with(|cell| {
+        if let Some(ref renderer) = *cell.borrow() {
+            renderer.purge_memory();
+        }
+    });
+}
+
+pub fn renderer_log_memory_usage() {
+    ULTRALIGHT_RENDERER.with(|cell| {
+        if let Some(ref renderer) = *cell.borrow() {
+            renderer.log_memory_usage();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_clock_keeps_its_rate() {
+        // a 30 Hz display drawn at 120 frames per second
+        let mut clock = DisplayClock::new(1.0 / 30.0);
+        let refreshes = (0..120)
+            .filter(|frame| clock.tick(*frame as f64 / 120.0))
+            .count();
+        assert_eq!(refreshes, 30);
+
+        let mut every_frame = DisplayClock::new(0.0);
+        assert!(every_frame.tick(1.0));
+        assert!(every_frame.tick(1.0));
+    }
+
+    #[test]
+    fn engine_calls_nest() {
+        assert!(!in_engine_call());
+        engine_call(|| {
+            engine_call(|| assert!(in_engine_call()));
+            assert!(in_engine_call());
+        });
+        assert!(!in_engine_call());
+    }
+}
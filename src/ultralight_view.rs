@@ -1,12 +1,77 @@
 use crate::callbacks::UltralightViewCallbacks;
+use crate::clipboard::{ClipboardEvent, ClipboardPolicy, ViewClipboard};
+use crate::conversion::{JSExceptionInfo, ScriptSource};
+use crate::gpu_replay::gpu_texture;
 use crate::keyboard::keyboard_key;
-use crate::ultralight_renderer::{renderer_get_lib, renderer_get_renderer};
+use crate::surface::SurfaceImageData;
+use crate::ultralight_renderer::{
+    MAIN_DISPLAY_ID, engine_call, renderer_get_lib, renderer_get_renderer,
+};
 use mlua::UserData;
 use mlua::prelude::*;
+use std::cell::Cell;
+use std::rc::Rc;
 use ul_next::event::KeyEventType;
 use ul_next::event::{MouseButton, MouseEvent, MouseEventType, ScrollEvent, ScrollEventType};
 use ul_next::{View, view::ViewConfig};
 
+// weak-valued table of view id -> view userdata, for finding views from Rust
+const VIEWS_REGISTRY_KEY: &str = "love-ultralight.views";
+
+// suspended views are moved to this display, which is never refreshed
+const SUSPENDED_DISPLAY_ID: u32 = u32::MAX;
+
+thread_local! {
+    static NEXT_VIEW_ID: Cell<u64> = const { Cell::new(1) };
+}
+
+pub fn views_init(lua: &Lua) -> LuaResult<()> {
+    let views = lua.create_table()?;
+    views.set_metatable(Some(lua.create_table_from([("__mode", "v")])?))?;
+    lua.set_named_registry_value(VIEWS_REGISTRY_KEY, views)
+}
+
+/// Finds the userdata of the view with `id`, if Lua still holds it.
+pub fn view_userdata(lua: &Lua, id: u64) -> Option<LuaAnyUserData> {
+    lua.named_registry_value::<LuaTable>(VIEWS_REGISTRY_KEY)
+        .ok()?
+        .get::<Option<LuaAnyUserData>>(id)
+        .ok()?
+}
+
+/// Every view Lua still holds, oldest first.
+pub fn live_views(lua: &Lua) -> LuaResult<Vec<LuaAnyUserData>> {
+    let mut views = lua
+        .named_registry_value::<LuaTable>(VIEWS_REGISTRY_KEY)?
+        .pairs::<u64, LuaAnyUserData>()
+        .collect::<LuaResult<Vec<_>>>()?;
+    views.sort_by_key(|(id, _)| *id);
+    Ok(views.into_iter().map(|(_, view)| view).collect())
+}
+
+/// Refuses the display id suspended views are moved to.
+pub fn check_display_id(display_id: u32) -> LuaResult<()> {
+    if display_id == SUSPENDED_DISPLAY_ID {
+        return Err(LuaError::external(format!(
+            "display {} is reserved for suspended views",
+            display_id
+        )));
+    }
+    Ok(())
+}
+
+/// Keeps hidden and suspended views out of the next paint.
+pub fn views_skip_hidden(lua: &Lua) -> LuaResult<()> {
+    for userdata in live_views(lua)? {
+        if let Ok(view) = userdata.borrow::<UltralightView>()
+            && !view.is_painting()
+        {
+            view.view.set_needs_paint(false);
+        }
+    }
+    Ok(())
+}
+
 fn parse_mouse_button(button_code: u8) -> MouseButton {
     match button_code {
         1 => MouseButton::Left,
@@ -40,26 +105,56 @@ fn fire_mouse_event(
 
     let event = MouseEvent::new(lib, event_type, x, y, button).map_err(mlua::Error::external)?;
 
-    view.fire_mouse_event(event);
+    engine_call(|| view.fire_mouse_event(event));
 
     Ok(())
 }
 
+/// The last pixels handed out by `getFrameBuffer`.
+struct FrameCache {
+    pixels: LuaRegistryKey,
+    width: u32,
+    height: u32,
+}
+
 pub struct UltralightView {
+    pub(crate) id: u64,
     pub(crate) callbacks: UltralightViewCallbacks,
-    pub(crate) view: View,
+    pub(crate) view: Rc<View>,
+    pub(crate) clipboard: ViewClipboard,
+    visible: bool,
+    // the display to go back to on resume
+    suspended: Option<u32>,
+    frame_cache: Option<FrameCache>,
+    // the copy of the pixels handed out by `getImageData`
+    image_data: Option<SurfaceImageData>,
 }
 
 impl UltralightView {
-    pub fn new(lua: &Lua) -> LuaResult<Self> {
+    /// Creates a view, `options.display` picks the display it is shown on
+    /// and `options.accelerated` renders it on the GPU.
+    pub fn new(lua: &Lua, options: Option<LuaTable>) -> LuaResult<Self> {
         let ul_lib = renderer_get_lib();
         let renderer = renderer_get_renderer();
 
+        let (display_id, accelerated) = match &options {
+            Some(options) => (
+                options.get::<Option<u32>>("display")?,
+                options.get::<Option<bool>>("accelerated")?,
+            ),
+            None => (None, None),
+        };
+        let display_id = display_id.unwrap_or(MAIN_DISPLAY_ID);
+        let accelerated = accelerated.unwrap_or(false);
+        check_display_id(display_id)?;
+
         let view_config = ViewConfig::start()
             .is_transparent(true)
             .font_family_fixed("Consolas")
             .font_family_sans_serif("Segoe UI")
             .font_family_standard("Segoe UI")
+            .display_id(display_id)
+            .is_accelerated(accelerated)
             .build(ul_lib)
             .ok_or_else(|| mlua::Error::external("Failed to create view config"))?;
 
@@ -77,11 +172,35 @@ impl UltralightView {
             .create_view(800, 600, &view_config, Some(&session))
             .ok_or_else(|| mlua::Error::external("Failed to create view"))?;
 
+        let view = Rc::new(view);
+        let id = NEXT_VIEW_ID.with(|cell| cell.replace(cell.get() + 1));
+
         Ok(UltralightView {
+            id,
+            callbacks: UltralightViewCallbacks::new(lua.clone(), Rc::downgrade(&view)),
             view,
-            callbacks: UltralightViewCallbacks::new(lua.clone()),
+            clipboard: ViewClipboard::new(id),
+            visible: true,
+            suspended: None,
+            frame_cache: None,
+            image_data: None,
         })
     }
+
+    /// Whether the view is painted and its pixels copied.
+    fn is_painting(&self) -> bool {
+        self.visible && self.suspended.is_none()
+    }
+
+    /// Creates a view and registers it, so Rust code can hand it back to Lua.
+    pub fn create(lua: &Lua, options: Option<LuaTable>) -> LuaResult<LuaAnyUserData> {
+        let view = Self::new(lua, options)?;
+        let id = view.id;
+        let userdata = lua.create_userdata(view)?;
+        lua.named_registry_value::<LuaTable>(VIEWS_REGISTRY_KEY)?
+            .set(id, &userdata)?;
+        Ok(userdata)
+    }
 }
 
 impl UserData for UltralightView {
@@ -121,7 +240,20 @@ impl UserData for UltralightView {
         });
 
         methods.add_method_mut("getFrameBuffer", |lua, this, ()| {
-            let mut surface = this.view.surface().unwrap();
+            let Some(mut surface) = this.view.surface() else {
+                return Err(LuaError::external(
+                    "accelerated views have no frame buffer, use getTexture",
+                ));
+            };
+
+            // nothing new was painted, hand out the last copy again
+            if let Some(cache) = &this.frame_cache
+                && (!this.is_painting() || surface.dirty_bounds().is_empty())
+            {
+                let pixels = lua.registry_value::<LuaString>(&cache.pixels)?;
+                return Ok((pixels, cache.width, cache.height));
+            }
+
             let width = surface.width();
             let height = surface.height();
 
@@ -135,16 +267,137 @@ impl UserData for UltralightView {
                 .create_string(&pixels)
                 .map_err(|e| mlua::Error::external(e))?;
 
+            surface.clear_dirty_bounds();
+            // `getImageData` would not see these changes anymore
+            if let Some(image_data) = &mut this.image_data {
+                image_data.invalidate();
+            }
+            this.frame_cache = Some(FrameCache {
+                pixels: lua.create_registry_value(&pixels_res)?,
+                width,
+                height,
+            });
+            lua.expire_registry_values();
+
             Ok((pixels_res, width, height))
         });
 
+        methods.add_method("getTexture", |lua, this, ()| {
+            let Some(target) = this.view.render_target().filter(|target| !target.is_empty) else {
+                return Ok((None, None));
+            };
+            let Some(texture) = gpu_texture(lua, target.texture_id)? else {
+                return Ok((None, None));
+            };
+
+            // the texture may be padded, the quad picks the view's part of it
+            let (texture_width, texture_height) =
+                (target.texture_width as f32, target.texture_height as f32);
+            let graphics: LuaTable = lua.globals().get::<LuaTable>("love")?.get("graphics")?;
+            let quad: LuaAnyUserData = graphics.get::<LuaFunction>("newQuad")?.call((
+                target.uv_coords.left * texture_width,
+                target.uv_coords.top * texture_height,
+                target.width,
+                target.height,
+                texture_width,
+                texture_height,
+            ))?;
+
+            Ok((Some(texture), Some(quad)))
+        });
+
+        methods.add_method("isAccelerated", |_, this, ()| {
+            Ok(this.view.is_accelerated())
+        });
+
+        methods.add_method_mut("getImageData", |lua, this, ()| {
+            // accelerated views have no surface, they draw through `gpu_replay`
+            let Some(mut surface) = this.view.surface() else {
+                return Ok((None, false));
+            };
+
+            let (width, height) = (surface.width(), surface.height());
+            if !this
+                .image_data
+                .as_ref()
+                .is_some_and(|data| data.width() == width && data.height() == height)
+            {
+                this.image_data = SurfaceImageData::new(lua, width, height)?;
+            }
+            let Some(image_data) = &mut this.image_data else {
+                return Ok((None, false));
+            };
+
+            let painted =
+                this.visible && this.suspended.is_none() && !surface.dirty_bounds().is_empty();
+            let changed = painted || !image_data.is_synced();
+            if changed {
+                let row_bytes = surface.row_bytes() as usize;
+                let pixels = surface
+                    .lock_pixels()
+                    .ok_or_else(|| LuaError::external("the View's pixels are not available"))?;
+                image_data.copy_from(lua, &pixels, row_bytes)?;
+            }
+            if painted {
+                surface.clear_dirty_bounds();
+                // `getFrameBuffer` would not see these changes anymore
+                this.frame_cache = None;
+            }
+
+            Ok((Some(image_data.image_data(lua)?), changed))
+        });
+
+        methods.add_method_mut("setVisible", |_, this, visible: bool| {
+            if visible && !this.visible {
+                this.view.set_needs_paint(true);
+            }
+            this.visible = visible;
+            Ok(())
+        });
+
+        methods.add_method("isVisible", |_, this, ()| Ok(this.visible));
+
+        methods.add_method_mut("suspend", |_, this, ()| {
+            if this.suspended.is_none() {
+                this.suspended = Some(this.view.get_display_id());
+                this.view.set_display_id(SUSPENDED_DISPLAY_ID);
+            }
+            Ok(())
+        });
+
+        methods.add_method_mut("resume", |_, this, ()| {
+            if let Some(display_id) = this.suspended.take() {
+                this.view.set_display_id(display_id);
+                this.view.set_needs_paint(true);
+            }
+            Ok(())
+        });
+
+        methods.add_method("isSuspended", |_, this, ()| Ok(this.suspended.is_some()));
+
+        methods.add_method_mut("setDisplay", |_, this, display_id: u32| {
+            check_display_id(display_id)?;
+            match &mut this.suspended {
+                // takes effect on resume
+                Some(suspended_display_id) => *suspended_display_id = display_id,
+                None => this.view.set_display_id(display_id),
+            }
+            Ok(())
+        });
+
+        methods.add_method("getDisplay", |_, this, ()| {
+            Ok(this.suspended.unwrap_or_else(|| this.view.get_display_id()))
+        });
+
         methods.add_method("focus", |_, this, ()| {
             this.view.focus();
+            this.clipboard.set_focused(true);
             Ok(())
         });
 
         methods.add_method("unfocus", |_, this, ()| {
             this.view.unfocus();
+            this.clipboard.set_focused(false);
             Ok(())
         });
 
@@ -154,7 +407,32 @@ impl UserData for UltralightView {
             Ok(this.view.has_input_focus())
         });
 
+        methods.add_method("setClipboardPolicy", |_, this, policy: String| {
+            this.clipboard.set_policy(ClipboardPolicy::parse(&policy)?);
+            Ok(())
+        });
+
+        methods.add_method("getClipboardPolicy", |_, this, ()| {
+            Ok(this.clipboard.policy().name())
+        });
+
+        methods.add_method("onCopy", |lua, this, callback: LuaFunction| {
+            this.clipboard
+                .set_handler(lua, ClipboardEvent::Copy, callback)
+        });
+
+        methods.add_method("onCut", |lua, this, callback: LuaFunction| {
+            this.clipboard
+                .set_handler(lua, ClipboardEvent::Cut, callback)
+        });
+
+        methods.add_method("onPaste", |lua, this, callback: LuaFunction| {
+            this.clipboard
+                .set_handler(lua, ClipboardEvent::Paste, callback)
+        });
+
         methods.add_method_mut("loadURL", |_, this, url: String| {
+            this.callbacks.ensure_console_capture(&this.view);
             this.view
                 .load_url(&url)
                 .map_err(|e| mlua::Error::external(format!("Failed to load URL: {}", e)))?;
@@ -163,6 +441,7 @@ impl UserData for UltralightView {
         });
 
         methods.add_method_mut("loadHTML", |_, this, html: String| {
+            this.callbacks.ensure_console_capture(&this.view);
             this.view
                 .load_html(&html)
                 .map_err(|e| mlua::Error::external(format!("Failed to load HTML: {}", e)))?;
@@ -213,23 +492,27 @@ impl UserData for UltralightView {
         });
 
         methods.add_method("mousePress", |_, this, (x, y, button): (i32, i32, u8)| {
-            fire_mouse_event(
-                &this.view,
-                MouseEventType::MouseDown,
-                x,
-                y,
-                parse_mouse_button(button),
-            )
+            this.clipboard.with_view(|| {
+                fire_mouse_event(
+                    &this.view,
+                    MouseEventType::MouseDown,
+                    x,
+                    y,
+                    parse_mouse_button(button),
+                )
+            })
         });
 
         methods.add_method("mouseRelease", |_, this, (x, y, button): (i32, i32, u8)| {
-            fire_mouse_event(
-                &this.view,
-                MouseEventType::MouseUp,
-                x,
-                y,
-                parse_mouse_button(button),
-            )
+            this.clipboard.with_view(|| {
+                fire_mouse_event(
+                    &this.view,
+                    MouseEventType::MouseUp,
+                    x,
+                    y,
+                    parse_mouse_button(button),
+                )
+            })
         });
 
         methods.add_method("mouseMove", |_, this, (x, y): (i32, i32)| {
@@ -247,7 +530,7 @@ impl UserData for UltralightView {
             let event = ScrollEvent::new(lib, ScrollEventType::ScrollByPixel, x, y)
                 .map_err(mlua::Error::external)?;
 
-            this.view.fire_scroll_event(event);
+            engine_call(|| this.view.fire_scroll_event(event));
             this.view.set_needs_paint(true);
             Ok(())
         });
@@ -255,42 +538,48 @@ impl UserData for UltralightView {
         methods.add_method(
             "keyPress",
             |lua, this, (scancode, is_repeat, modifiers): (String, bool, Option<LuaTable>)| {
-                keyboard_key(
-                    lua,
-                    &this.view,
-                    KeyEventType::KeyDown,
-                    &scancode,
-                    Some(is_repeat),
-                    modifiers,
-                )
+                this.clipboard.with_view(|| {
+                    keyboard_key(
+                        lua,
+                        &this.view,
+                        KeyEventType::KeyDown,
+                        &scancode,
+                        Some(is_repeat),
+                        modifiers,
+                    )
+                })
             },
         );
 
         methods.add_method(
             "keyRelease",
             |lua, this, (scancode, modifiers): (String, Option<LuaTable>)| {
-                keyboard_key(
-                    lua,
-                    &this.view,
-                    KeyEventType::KeyUp,
-                    &scancode,
-                    None,
-                    modifiers,
-                )
+                this.clipboard.with_view(|| {
+                    keyboard_key(
+                        lua,
+                        &this.view,
+                        KeyEventType::KeyUp,
+                        &scancode,
+                        None,
+                        modifiers,
+                    )
+                })
             },
         );
 
         methods.add_method(
             "textInput",
             |lua, this, (text, modifiers): (String, Option<LuaTable>)| {
-                keyboard_key(lua, &this.view, KeyEventType::Char, &text, None, modifiers)
+                this.clipboard.with_view(|| {
+                    keyboard_key(lua, &this.view, KeyEventType::Char, &text, None, modifiers)
+                })
             },
         );
 
         methods.add_method(
             "evaluateScript",
             |lua, this, script: String| -> LuaResult<(LuaValue, LuaValue)> {
-                match this.view.evaluate_script(&script) {
+                match engine_call(|| this.view.evaluate_script(&script)) {
                     Ok(Ok(value)) => {
                         let lua_value = lua.create_string(&value).map_err(mlua::Error::external)?;
                         Ok((LuaValue::String(lua_value), LuaValue::Nil))
@@ -311,6 +600,48 @@ impl UserData for UltralightView {
             },
         );
 
+        methods.add_method_mut(
+            "evaluate",
+            |lua, this, (script, options): (String, Option<LuaTable>)| {
+                let source = ScriptSource::from_options(options)?;
+
+                match this.callbacks.evaluate(lua, &this.view, &script, &source) {
+                    Ok(value) => Ok((value, LuaValue::Nil)),
+                    Err(e) => Ok((
+                        LuaValue::Nil,
+                        LuaValue::String(lua.create_string(e.to_string())?),
+                    )),
+                }
+            },
+        );
+
+        methods.add_method(
+            "checkSyntax",
+            |lua, this, (script, source_url): (String, Option<String>)| {
+                let source = ScriptSource::new(source_url);
+                let ctx = this.view.lock_js_context();
+
+                match ctx.check_script_syntax_with_source(
+                    &script,
+                    source.source_url.as_deref(),
+                    source.start_line,
+                ) {
+                    Ok(ok) => Ok((ok, LuaValue::Nil, LuaValue::Nil, LuaValue::Nil)),
+                    Err(e) => {
+                        let info = JSExceptionInfo::new(&e);
+                        Ok((
+                            false,
+                            LuaValue::String(lua.create_string(&info.message)?),
+                            info.line
+                                .map_or(LuaValue::Nil, |line| LuaValue::Integer(line as i64)),
+                            info.column
+                                .map_or(LuaValue::Nil, |column| LuaValue::Integer(column as i64)),
+                        ))
+                    }
+                }
+            },
+        );
+
         methods.add_method_mut(
             "addFunction",
             |lua, this, (namespace, name, callback): (String, String, LuaFunction)| {
@@ -319,6 +650,21 @@ impl UserData for UltralightView {
             },
         );
 
+        methods.add_method_mut(
+            "addAsyncFunction",
+            |lua, this, (namespace, name, callback): (String, String, LuaFunction)| {
+                this.callbacks
+                    .add_async_function(lua, &this.view, namespace, name, callback)
+            },
+        );
+
+        methods.add_method_mut(
+            "exposeObject",
+            |lua, this, (name, table): (String, LuaTable)| {
+                this.callbacks.expose_object(lua, &this.view, name, table)
+            },
+        );
+
         // Event handlers
         methods.add_method_mut("onDocumentReady", |lua, this, callback: LuaFunction| {
             this.callbacks
@@ -383,5 +729,26 @@ impl UserData for UltralightView {
             this.callbacks
                 .set_add_console_message_callback(lua, &this.view, callback)
         });
+
+        methods.add_method_mut("setConsoleHistorySize", |_, this, size: usize| {
+            this.callbacks.ensure_console_capture(&this.view);
+            this.callbacks.console().borrow_mut().set_history_size(size);
+            Ok(())
+        });
+
+        methods.add_method("getConsoleHistory", |lua, this, ()| {
+            let console = this.callbacks.console().borrow();
+            lua.create_sequence_from(
+                console
+                    .history()
+                    .map(|message| message.to_lua_table(lua))
+                    .collect::<LuaResult<Vec<_>>>()?,
+            )
+        });
+
+        methods.add_method("clearConsoleHistory", |_, this, ()| {
+            this.callbacks.console().borrow_mut().clear_history();
+            Ok(())
+        });
     }
 }
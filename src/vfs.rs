@@ -0,0 +1,288 @@
+use crate::pack::{PACK_MAGIC, Pack};
+use lru::LruCache;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use zip::ZipArchive;
+
+/// The largest file read out of an archive, so a corrupt or hostile index
+/// can't make us allocate more than that.
+pub const MAX_ARCHIVE_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Anything a zip archive can be read from.
+pub trait ArchiveReader: Read + Seek + Send + Sync {}
+
+impl<T: Read + Seek + Send + Sync> ArchiveReader for T {}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ArchiveFormat {
+    Zip,
+    Pack,
+}
+
+impl ArchiveFormat {
+    /// Tells the format from the first bytes of the archive.
+    fn detect(reader: &mut dyn ArchiveReader) -> io::Result<Self> {
+        let mut magic = [0; 4];
+        reader.seek(SeekFrom::Start(0))?;
+        let is_pack = reader.read_exact(&mut magic).is_ok() && &magic == PACK_MAGIC;
+        reader.seek(SeekFrom::Start(0))?;
+
+        Ok(if is_pack {
+            ArchiveFormat::Pack
+        } else {
+            ArchiveFormat::Zip
+        })
+    }
+}
+
+/// A tree of files, addressed by normalized relative paths.
+pub enum Source {
+    Directory(PathBuf),
+    Archive(Mutex<ZipArchive<Box<dyn ArchiveReader>>>),
+    Pack(Pack),
+    Files(HashMap<String, Arc<Vec<u8>>>),
+}
+
+impl Source {
+    pub fn open_archive(path: &Path) -> io::Result<Self> {
+        Self::archive(Box::new(File::open(path)?), None)
+    }
+
+    /// Opens a zip archive or a [`Pack`], detecting the format if `format` is `None`.
+    pub fn archive(
+        mut reader: Box<dyn ArchiveReader>,
+        format: Option<ArchiveFormat>,
+    ) -> io::Result<Self> {
+        let format = match format {
+            Some(format) => format,
+            None => ArchiveFormat::detect(reader.as_mut())?,
+        };
+
+        match format {
+            ArchiveFormat::Zip => Ok(Source::Archive(Mutex::new(ZipArchive::new(reader)?))),
+            ArchiveFormat::Pack => Ok(Source::Pack(Pack::new(reader)?)),
+        }
+    }
+
+    /// Whether reading from this source is worth caching.
+    pub fn is_archive(&self) -> bool {
+        matches!(self, Source::Archive(_) | Source::Pack(_))
+    }
+
+    /// Returns whether the file at `path` exists. `path` must already be normalized.
+    pub fn exists(&self, path: &str) -> bool {
+        match self {
+            Source::Directory(directory) => directory.join(path).is_file(),
+            Source::Archive(archive) => archive
+                .lock()
+                .ok()
+                .and_then(|mut archive| archive.by_name(path).ok().map(|file| !file.is_dir()))
+                .unwrap_or(false),
+            Source::Pack(pack) => pack.contains(path),
+            Source::Files(files) => files.contains_key(path),
+        }
+    }
+
+    /// Reads the file at `path`. `path` must already be normalized.
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        match self {
+            Source::Directory(directory) => std::fs::read(directory.join(path)).ok(),
+            Source::Archive(archive) => {
+                let mut archive = archive.lock().ok()?;
+                let file = archive.by_name(path).ok()?;
+                read_capped(file, MAX_ARCHIVE_FILE_SIZE)
+            }
+            Source::Pack(pack) => pack.read(path),
+            Source::Files(files) => files.get(path).map(|data| data.as_ref().clone()),
+        }
+    }
+}
+
+/// Reads `reader` to the end, giving `None` past `limit` bytes.
+///
+/// The buffer grows with the data actually read, never with a size the archive declares.
+pub fn read_capped(reader: impl Read, limit: u64) -> Option<Vec<u8>> {
+    let mut data = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut data).ok()?;
+    (data.len() as u64 <= limit).then_some(data)
+}
+
+/// A [`Source`] made visible under a path prefix.
+pub struct Mount {
+    prefix: String,
+    source: Source,
+    // recently read files, so archives don't decompress them again
+    cache: Option<Mutex<LruCache<String, Arc<Vec<u8>>>>>,
+}
+
+impl Mount {
+    pub fn new(prefix: &str, source: Source) -> Self {
+        Mount {
+            prefix: normalize_path("", prefix).unwrap_or_default(),
+            source,
+            cache: None,
+        }
+    }
+
+    /// Keeps up to `capacity` files in memory after they are read.
+    pub fn with_cache(mut self, capacity: usize) -> Self {
+        self.cache =
+            NonZeroUsize::new(capacity).map(|capacity| Mutex::new(LruCache::new(capacity)));
+        self
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Returns the path relative to the mount, if `path` is inside it.
+    fn relative_path<'p>(&self, path: &'p str) -> Option<&'p str> {
+        if self.prefix.is_empty() {
+            return Some(path);
+        }
+
+        path.strip_prefix(self.prefix.as_str())?.strip_prefix('/')
+    }
+
+    pub fn exists(&self, path: &str) -> bool {
+        self.relative_path(path)
+            .is_some_and(|path| self.source.exists(path))
+    }
+
+    pub fn read(&self, path: &str) -> Option<Vec<u8>> {
+        let path = self.relative_path(path)?;
+
+        let Some(cache) = &self.cache else {
+            return self.source.read(path);
+        };
+
+        if let Some(data) = cache.lock().ok()?.get(path) {
+            return Some(data.as_ref().clone());
+        }
+
+        let data = self.source.read(path)?;
+        cache
+            .lock()
+            .ok()?
+            .put(path.to_string(), Arc::new(data.clone()));
+        Some(data)
+    }
+}
+
+/// Why a path could not be resolved.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PathError {
+    /// The path climbs out of the root, or names a drive or stream.
+    Traversal,
+    /// The path names the root itself.
+    NotAFile,
+}
+
+/// Joins `path` to `root`, resolving `.` and `..` and refusing paths that climb out of `root`.
+pub fn resolve_path(root: &str, path: &str) -> Result<String, PathError> {
+    let mut parts: Vec<&str> = root
+        .split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect();
+    let root_len = parts.len();
+
+    for part in path.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.len() == root_len {
+                    return Err(PathError::Traversal);
+                }
+                parts.pop();
+            }
+            // drive letters and alternate streams would escape on Windows
+            part if part.contains(':') => return Err(PathError::Traversal),
+            part => parts.push(part),
+        }
+    }
+
+    if parts.len() == root_len {
+        return Err(PathError::NotAFile);
+    }
+
+    Ok(parts.join("/"))
+}
+
+/// Like [`resolve_path`], for callers that don't need to know why a path was refused.
+pub fn normalize_path(root: &str, path: &str) -> Option<String> {
+    resolve_path(root, path).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_path_stays_inside_root() {
+        assert_eq!(
+            normalize_path("ui/", "/index.html"),
+            Some("ui/index.html".to_string())
+        );
+        assert_eq!(
+            normalize_path("ui", "./css/../app.js"),
+            Some("ui/app.js".to_string())
+        );
+        assert_eq!(
+            resolve_path("ui", "./css/../../../app.js"),
+            Err(PathError::Traversal)
+        );
+        assert_eq!(
+            resolve_path("", "C:/Windows/win.ini"),
+            Err(PathError::Traversal)
+        );
+        assert_eq!(
+            normalize_path("", "assets\\logo.png"),
+            Some("assets/logo.png".to_string())
+        );
+        assert_eq!(normalize_path("", "../"), None);
+        assert_eq!(resolve_path("ui", "css/.."), Err(PathError::NotAFile));
+    }
+
+    #[test]
+    fn mount_only_serves_paths_under_its_prefix() {
+        let files = HashMap::from([("theme.css".to_string(), Arc::new(b"body{}".to_vec()))]);
+        let mount = Mount::new("/themes/", Source::Files(files));
+
+        assert_eq!(mount.prefix(), "themes");
+        assert!(mount.exists("themes/theme.css"));
+        assert!(!mount.exists("themes.css"));
+        assert!(!mount.exists("theme.css"));
+        assert_eq!(mount.read("themes/theme.css"), Some(b"body{}".to_vec()));
+    }
+
+    #[test]
+    fn archive_detects_packs() {
+        let files = [("a.txt".to_string(), b"a".to_vec())];
+        let pack = crate::pack::write_pack(&files, false).unwrap();
+
+        let source = Source::archive(Box::new(io::Cursor::new(pack)), None).unwrap();
+        assert!(matches!(source, Source::Pack(_)));
+
+        let mount = Mount::new("", source).with_cache(1);
+        assert_eq!(mount.read("a.txt"), Some(b"a".to_vec()));
+        assert_eq!(mount.read("a.txt"), Some(b"a".to_vec()));
+    }
+
+    #[test]
+    fn read_capped_stops_at_the_limit() {
+        let data = vec![7; 16];
+        assert_eq!(read_capped(data.as_slice(), 16), Some(data.clone()));
+        assert_eq!(read_capped(data.as_slice(), 15), None);
+        assert!(read_capped(io::repeat(0), 64).is_none());
+    }
+}
@@ -35,6 +35,7 @@ pub enum BitmapError {
 type BitmapResult<T> = std::result::Result<T, BitmapError>;
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The supported bitmap formats.
 pub enum BitmapFormat {
     /// Alpha channel only, 8-bits per pixel.
@@ -369,6 +370,14 @@ impl Drop for Bitmap {
 /// binding to the underlying C library.
 ///
 /// To create an `Ultralight` bitmap, use [`OwnedBitmap::to_bitmap`].
+///
+/// When deserialized, the sizes are checked against each other and the pixel buffer.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "OwnedBitmapFields")
+)]
 pub struct OwnedBitmap {
     width: u32,
     height: u32,
@@ -380,6 +389,74 @@ pub struct OwnedBitmap {
     is_empty: bool,
 }
 
+/// The fields of a deserialized [`OwnedBitmap`], before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct OwnedBitmapFields {
+    width: u32,
+    height: u32,
+    format: BitmapFormat,
+    bpp: u32,
+    row_bytes: u32,
+    bytes_size: usize,
+    pixels: Option<Vec<u8>>,
+    is_empty: bool,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<OwnedBitmapFields> for OwnedBitmap {
+    type Error = String;
+
+    fn try_from(fields: OwnedBitmapFields) -> Result<Self, Self::Error> {
+        let bpp = fields.format.bytes_per_pixel();
+        if fields.bpp != bpp {
+            return Err(format!(
+                "bitmap has {} bytes per pixel, {:?} has {}",
+                fields.bpp, fields.format, bpp
+            ));
+        }
+        if u64::from(fields.row_bytes) < u64::from(fields.width) * u64::from(bpp) {
+            return Err(format!(
+                "bitmap rows of {} bytes can't hold {} pixels",
+                fields.row_bytes, fields.width
+            ));
+        }
+        let size = u64::from(fields.row_bytes) * u64::from(fields.height);
+        if fields.bytes_size as u64 != size {
+            return Err(format!(
+                "bitmap has {} bytes, {} rows of {} bytes need {}",
+                fields.bytes_size, fields.height, fields.row_bytes, size
+            ));
+        }
+        if let Some(pixels) = &fields.pixels {
+            if pixels.len() != fields.bytes_size {
+                return Err(format!(
+                    "bitmap has {} bytes of pixels, expected {}",
+                    pixels.len(),
+                    fields.bytes_size
+                ));
+            }
+        }
+        if fields.is_empty != (fields.bytes_size == 0) {
+            return Err(format!(
+                "bitmap of {} bytes can't have is_empty = {}",
+                fields.bytes_size, fields.is_empty
+            ));
+        }
+
+        Ok(Self {
+            width: fields.width,
+            height: fields.height,
+            format: fields.format,
+            bpp: fields.bpp,
+            row_bytes: fields.row_bytes,
+            bytes_size: fields.bytes_size,
+            pixels: fields.pixels,
+            is_empty: fields.is_empty,
+        })
+    }
+}
+
 impl OwnedBitmap {
     /// Create an [`OwnedBitmap`] from a [`Bitmap`].
     ///
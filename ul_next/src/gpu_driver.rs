@@ -8,10 +8,16 @@
 //! and `Metal` in the [`AppCore`](https://github.com/ultralight-ux/AppCore) repository.
 //!
 //! This library also have a custom GPU driver for [`glium`].
+//!
+//! To test a driver without a GPU, [`recording::RecordingGpuDriver`] saves every call
+//! into a [`recording::GpuTrace`], which can be replayed into any driver, such as
+//! the small CPU renderer [`rasterizer::CpuRasterizer`].
 
 #[cfg(feature = "glium")]
 #[cfg_attr(docsrs, doc(cfg(feature = "glium")))]
 pub mod glium;
+pub mod rasterizer;
+pub mod recording;
 
 use std::slice;
 
@@ -21,7 +27,8 @@ use crate::{
     rect::Rect,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// RenderBuffer description. (See [`GpuDriver::create_render_buffer`]).
 pub struct RenderBuffer {
     /// The backing texture id for this render buffer.
@@ -48,7 +55,8 @@ impl From<ul_sys::ULRenderBuffer> for RenderBuffer {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(non_camel_case_types)]
 /// Vertex buffer format types
 pub enum VertexBufferFormat {
@@ -79,6 +87,8 @@ impl TryFrom<ul_sys::ULVertexBufferFormat> for VertexBufferFormat {
 //       a specific format? like what we did in `glium` gpu_driver.
 /// Vertex buffer, the buffer is used for `quad` or `path` rendering based on
 /// the `format`. (See [`GpuDriver::create_geometry`]).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct VertexBuffer {
     /// The format of the raw data. Either path or quad vertices.
     pub format: VertexBufferFormat,
@@ -103,6 +113,8 @@ impl TryFrom<ul_sys::ULVertexBuffer> for VertexBuffer {
 }
 
 /// Index buffer. (See [`GpuDriver::create_geometry`]).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct IndexBuffer {
     pub buffer: Vec<u32>,
 }
@@ -155,6 +167,7 @@ macro_rules! from_ul_arr {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Shader types, used by [`GpuState::shader_type`]
 ///
 /// Each of these correspond to a vertex/pixel shader pair to be used.
@@ -181,6 +194,7 @@ impl TryFrom<ul_sys::ULShaderType> for ShaderType {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The GPU state description to be used when handling draw command.
 /// (See [`GpuCommand::DrawGeometry`]).
 pub struct GpuState {
@@ -262,6 +276,7 @@ impl TryFrom<ul_sys::ULGPUState> for GpuState {
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// The GPU command to be executed.
 ///
 /// This describes a command to be executed on the GPU.
@@ -311,6 +326,15 @@ impl TryFrom<ul_sys::ULCommand> for GpuCommand {
     }
 }
 
+/// Returns `counter` and advances it, for the `next_*_id` functions of the drivers in this crate.
+///
+/// Ids start at the counter's initial value and wrap back to `1`, as `0` is reserved.
+pub(crate) fn next_id(counter: &mut u32) -> u32 {
+    let id = *counter;
+    *counter = counter.checked_add(1).unwrap_or(1);
+    id
+}
+
 // TODO: we should not return `0` in ids, should we enforce it?
 /// `GpuDriver` trait, dispatches GPU calls to the native driver.
 ///
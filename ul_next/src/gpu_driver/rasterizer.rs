@@ -0,0 +1,367 @@
+//! A small CPU renderer for Ultralight GPU commands.
+//!
+//! [`CpuRasterizer`] only knows how to draw solid fills, which is enough to
+//! check the output of simple pages (backgrounds, boxes and borders) in tests,
+//! without a GPU context.
+//! Draws it can't handle are skipped and counted, see [`CpuRasterizer::skipped_draws`].
+use std::collections::HashMap;
+
+use crate::bitmap::OwnedBitmap;
+
+use super::{
+    next_id, GpuCommand, GpuDriver, GpuState, IndexBuffer, RenderBuffer, VertexBuffer,
+    VertexBufferFormat,
+};
+
+// fill type of `Format_2f_4ub_2f_2f_28f` vertices drawing a plain color
+const FILL_TYPE_SOLID: u32 = 0;
+
+/// The pixels of a render buffer drawn by [`CpuRasterizer`].
+///
+/// Pixels are premultiplied RGBA, 4 bytes each, with rows packed tightly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RasterBuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl RasterBuffer {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Width in pixels.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Height in pixels.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// All the pixels, row by row.
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// The premultiplied RGBA color at `(x, y)`, or `None` if out of bounds.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[offset..offset + 4].try_into().ok()
+    }
+
+    fn blend(&mut self, x: u32, y: u32, color: [f32; 4], enable_blend: bool) {
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel = &mut self.pixels[offset..offset + 4];
+        let keep = if enable_blend { 1.0 - color[3] } else { 0.0 };
+        for (channel, source) in pixel.iter_mut().zip(color) {
+            let value = source + (*channel as f32 / 255.0) * keep;
+            *channel = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        }
+    }
+}
+
+struct Vertex {
+    x: f32,
+    y: f32,
+    color: [f32; 4],
+}
+
+struct Geometry {
+    vertex_buffer: VertexBuffer,
+    index_buffer: IndexBuffer,
+}
+
+impl Geometry {
+    /// Reads vertex `index`, or `None` if out of bounds.
+    ///
+    /// Quad vertices which are not solid fills are also `None`.
+    fn vertex(&self, index: u32) -> Option<Vertex> {
+        let stride = match self.vertex_buffer.format {
+            VertexBufferFormat::Format_2f_4ub_2f => 20,
+            VertexBufferFormat::Format_2f_4ub_2f_2f_28f => 140,
+        };
+        let start = index as usize * stride;
+        let bytes = self.vertex_buffer.buffer.get(start..start + stride)?;
+        let float =
+            |offset: usize| f32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        if self.vertex_buffer.format == VertexBufferFormat::Format_2f_4ub_2f_2f_28f
+            && (float(28) + 0.5) as u32 != FILL_TYPE_SOLID
+        {
+            return None;
+        }
+
+        Some(Vertex {
+            x: float(0),
+            y: float(4),
+            color: [
+                bytes[8] as f32 / 255.0,
+                bytes[9] as f32 / 255.0,
+                bytes[10] as f32 / 255.0,
+                bytes[11] as f32 / 255.0,
+            ],
+        })
+    }
+}
+
+/// A [`GpuDriver`] drawing solid fills into memory.
+///
+/// Anything needing a shader effect (images, gradients, glyphs, rounded
+/// corners, clips, ...) is skipped, so pages using them will render partially.
+/// Textures are not read, only render buffers are kept.
+pub struct CpuRasterizer {
+    next_texture_id: u32,
+    next_render_buffer_id: u32,
+    next_geometry_id: u32,
+    render_buffers: HashMap<u32, RasterBuffer>,
+    // the render buffer backing each texture drawn into
+    render_targets: HashMap<u32, u32>,
+    geometries: HashMap<u32, Geometry>,
+    skipped_draws: usize,
+}
+
+impl Default for CpuRasterizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CpuRasterizer {
+    /// Create a rasterizer with nothing in it.
+    pub fn new() -> Self {
+        Self {
+            next_texture_id: 1,
+            next_render_buffer_id: 1,
+            next_geometry_id: 1,
+            render_buffers: HashMap::new(),
+            render_targets: HashMap::new(),
+            geometries: HashMap::new(),
+            skipped_draws: 0,
+        }
+    }
+
+    /// The pixels of render buffer `render_buffer_id`.
+    pub fn render_buffer(&self, render_buffer_id: u32) -> Option<&RasterBuffer> {
+        self.render_buffers.get(&render_buffer_id)
+    }
+
+    /// The pixels of the render buffer backed by texture `texture_id`.
+    ///
+    /// This is the texture id an accelerated view reports in its render target.
+    pub fn texture(&self, texture_id: u32) -> Option<&RasterBuffer> {
+        self.render_targets
+            .get(&texture_id)
+            .and_then(|render_buffer_id| self.render_buffers.get(render_buffer_id))
+    }
+
+    /// The number of draw commands skipped since creation, because they were not solid fills.
+    pub fn skipped_draws(&self) -> usize {
+        self.skipped_draws
+    }
+
+    fn draw(&mut self, state: &GpuState, geometry_id: u32, offset: u32, count: u32) {
+        let (Some(geometry), Some(target)) = (
+            self.geometries.get(&geometry_id),
+            self.render_buffers.get_mut(&state.render_buffer_id),
+        ) else {
+            self.skipped_draws += 1;
+            return;
+        };
+
+        let indices = geometry
+            .index_buffer
+            .buffer
+            .get(offset as usize..(offset as usize).saturating_add(count as usize));
+        let vertices = indices.and_then(|indices| {
+            indices
+                .iter()
+                .map(|&index| geometry.vertex(index))
+                .collect::<Option<Vec<_>>>()
+        });
+        let Some(vertices) = vertices.filter(|_| state.clip_size == 0) else {
+            self.skipped_draws += 1;
+            return;
+        };
+
+        // the area the draw may touch
+        let mut bounds = (0, 0, target.width as i32, target.height as i32);
+        if state.enable_scissor {
+            let scissor = &state.scissor_rect;
+            bounds = (
+                bounds.0.max(scissor.left),
+                bounds.1.max(scissor.top),
+                bounds.2.min(scissor.right),
+                bounds.3.min(scissor.bottom),
+            );
+        }
+
+        let m = &state.transform;
+        let transformed = vertices
+            .into_iter()
+            .map(|vertex| Vertex {
+                x: m[0] * vertex.x + m[4] * vertex.y + m[12],
+                y: m[1] * vertex.x + m[5] * vertex.y + m[13],
+                color: vertex.color,
+            })
+            .collect::<Vec<_>>();
+
+        for triangle in transformed.chunks_exact(3) {
+            fill_triangle(target, triangle, bounds, state.enable_blend);
+        }
+    }
+}
+
+fn edge(a: &Vertex, b: &Vertex, x: f32, y: f32) -> f32 {
+    (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+}
+
+// whether a pixel center exactly on the edge from `a` to `b` is drawn,
+// so pixels on an edge shared by two triangles are drawn once
+fn owns_edge(a: &Vertex, b: &Vertex) -> bool {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+fn fill_triangle(
+    target: &mut RasterBuffer,
+    triangle: &[Vertex],
+    bounds: (i32, i32, i32, i32),
+    enable_blend: bool,
+) {
+    let [a, mut b, mut c] = [&triangle[0], &triangle[1], &triangle[2]];
+    let mut area = edge(a, b, c.x, c.y);
+    if area == 0.0 {
+        return;
+    }
+    // wind every triangle the same way, so the edge rule holds
+    if area < 0.0 {
+        std::mem::swap(&mut b, &mut c);
+        area = -area;
+    }
+
+    let min_x = a.x.min(b.x).min(c.x).floor().max(bounds.0 as f32) as i32;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(bounds.1 as f32) as i32;
+    let max_x = a.x.max(b.x).max(c.x).ceil().min(bounds.2 as f32) as i32;
+    let max_y = a.y.max(b.y).max(c.y).ceil().min(bounds.3 as f32) as i32;
+
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let weights = [edge(b, c, px, py), edge(c, a, px, py), edge(a, b, px, py)];
+            let owned = [owns_edge(b, c), owns_edge(c, a), owns_edge(a, b)];
+            let inside = weights
+                .iter()
+                .zip(owned)
+                .all(|(&weight, owned)| weight > 0.0 || (weight == 0.0 && owned));
+            if !inside {
+                continue;
+            }
+
+            let mut color = [0.0; 4];
+            for (channel, value) in color.iter_mut().enumerate() {
+                *value = (weights[0] * a.color[channel]
+                    + weights[1] * b.color[channel]
+                    + weights[2] * c.color[channel])
+                    / area;
+            }
+            target.blend(x as u32, y as u32, color, enable_blend);
+        }
+    }
+}
+
+impl GpuDriver for CpuRasterizer {
+    fn begin_synchronize(&mut self) {}
+
+    fn end_synchronize(&mut self) {}
+
+    fn next_texture_id(&mut self) -> u32 {
+        next_id(&mut self.next_texture_id)
+    }
+
+    fn create_texture(&mut self, _texture_id: u32, _bitmap: OwnedBitmap) {}
+
+    fn update_texture(&mut self, _texture_id: u32, _bitmap: OwnedBitmap) {}
+
+    fn destroy_texture(&mut self, texture_id: u32) {
+        self.render_targets.remove(&texture_id);
+    }
+
+    fn next_render_buffer_id(&mut self) -> u32 {
+        next_id(&mut self.next_render_buffer_id)
+    }
+
+    fn create_render_buffer(&mut self, render_buffer_id: u32, render_buffer: RenderBuffer) {
+        self.render_buffers.insert(
+            render_buffer_id,
+            RasterBuffer::new(render_buffer.width, render_buffer.height),
+        );
+        self.render_targets
+            .insert(render_buffer.texture_id, render_buffer_id);
+    }
+
+    fn destroy_render_buffer(&mut self, render_buffer_id: u32) {
+        self.render_buffers.remove(&render_buffer_id);
+        self.render_targets
+            .retain(|_, target| *target != render_buffer_id);
+    }
+
+    fn next_geometry_id(&mut self) -> u32 {
+        next_id(&mut self.next_geometry_id)
+    }
+
+    fn create_geometry(
+        &mut self,
+        geometry_id: u32,
+        vertex_buffer: VertexBuffer,
+        index_buffer: IndexBuffer,
+    ) {
+        self.geometries.insert(
+            geometry_id,
+            Geometry {
+                vertex_buffer,
+                index_buffer,
+            },
+        );
+    }
+
+    fn update_geometry(
+        &mut self,
+        geometry_id: u32,
+        vertex_buffer: VertexBuffer,
+        index_buffer: IndexBuffer,
+    ) {
+        self.create_geometry(geometry_id, vertex_buffer, index_buffer);
+    }
+
+    fn destroy_geometry(&mut self, geometry_id: u32) {
+        self.geometries.remove(&geometry_id);
+    }
+
+    fn update_command_list(&mut self, commands: Vec<GpuCommand>) {
+        for command in commands {
+            match command {
+                GpuCommand::ClearRenderBuffer { render_buffer_id } => {
+                    if let Some(buffer) = self.render_buffers.get_mut(&render_buffer_id) {
+                        buffer.pixels.fill(0);
+                    }
+                }
+                GpuCommand::DrawGeometry {
+                    gpu_state,
+                    geometry_id,
+                    indices_offset,
+                    indices_count,
+                } => self.draw(&gpu_state, geometry_id, indices_offset, indices_count),
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! A [`GpuDriver`] that records every call into a [`GpuTrace`].
+//!
+//! This is useful to test the GPU plumbing without a GPU context, for example
+//! to check the order of commands a page produces, or to save a trace on one
+//! machine and replay it into a real driver on another
+//! (with the `serde` feature, traces can be serialized).
+//!
+//! Example:
+//! ```no_run,ignore
+//! let driver = RecordingGpuDriver::new();
+//! let trace = driver.trace_handle();
+//! platform::set_gpu_driver(lib.clone(), driver);
+//!
+//! // ... create the renderer and an accelerated view, then render
+//! renderer.render();
+//!
+//! let mut rasterizer = CpuRasterizer::new();
+//! trace.take().replay(&mut rasterizer);
+//! ```
+use std::sync::{Arc, Mutex};
+
+use crate::bitmap::OwnedBitmap;
+
+use super::{next_id, GpuCommand, GpuDriver, IndexBuffer, RenderBuffer, VertexBuffer};
+
+/// A call made to a [`GpuDriver`], with its arguments.
+///
+/// The `next_*_id` calls are not recorded, the ids show up in the `create_*` events instead.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GpuTraceEvent {
+    /// [`GpuDriver::begin_synchronize`]
+    BeginSynchronize,
+    /// [`GpuDriver::end_synchronize`]
+    EndSynchronize,
+    /// [`GpuDriver::create_texture`]
+    CreateTexture {
+        texture_id: u32,
+        bitmap: OwnedBitmap,
+    },
+    /// [`GpuDriver::update_texture`]
+    UpdateTexture {
+        texture_id: u32,
+        bitmap: OwnedBitmap,
+    },
+    /// [`GpuDriver::destroy_texture`]
+    DestroyTexture { texture_id: u32 },
+    /// [`GpuDriver::create_render_buffer`]
+    CreateRenderBuffer {
+        render_buffer_id: u32,
+        render_buffer: RenderBuffer,
+    },
+    /// [`GpuDriver::destroy_render_buffer`]
+    DestroyRenderBuffer { render_buffer_id: u32 },
+    /// [`GpuDriver::create_geometry`]
+    CreateGeometry {
+        geometry_id: u32,
+        vertex_buffer: VertexBuffer,
+        index_buffer: IndexBuffer,
+    },
+    /// [`GpuDriver::update_geometry`]
+    UpdateGeometry {
+        geometry_id: u32,
+        vertex_buffer: VertexBuffer,
+        index_buffer: IndexBuffer,
+    },
+    /// [`GpuDriver::destroy_geometry`]
+    DestroyGeometry { geometry_id: u32 },
+    /// [`GpuDriver::update_command_list`]
+    UpdateCommandList { commands: Vec<GpuCommand> },
+}
+
+/// The calls recorded by a [`RecordingGpuDriver`], oldest first.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GpuTrace {
+    pub events: Vec<GpuTraceEvent>,
+}
+
+impl GpuTrace {
+    /// All the commands of the trace, in the order they were sent.
+    pub fn commands(&self) -> impl Iterator<Item = &GpuCommand> {
+        self.events.iter().flat_map(|event| match event {
+            GpuTraceEvent::UpdateCommandList { commands } => commands.as_slice(),
+            _ => &[],
+        })
+    }
+
+    /// Replay the trace into another driver, in the recorded order.
+    ///
+    /// The driver receives the recorded ids, its own `next_*_id` functions are not called.
+    pub fn replay<D: GpuDriver + ?Sized>(&self, driver: &mut D) {
+        for event in &self.events {
+            match event.clone() {
+                GpuTraceEvent::BeginSynchronize => driver.begin_synchronize(),
+                GpuTraceEvent::EndSynchronize => driver.end_synchronize(),
+                GpuTraceEvent::CreateTexture { texture_id, bitmap } => {
+                    driver.create_texture(texture_id, bitmap)
+                }
+                GpuTraceEvent::UpdateTexture { texture_id, bitmap } => {
+                    driver.update_texture(texture_id, bitmap)
+                }
+                GpuTraceEvent::DestroyTexture { texture_id } => driver.destroy_texture(texture_id),
+                GpuTraceEvent::CreateRenderBuffer {
+                    render_buffer_id,
+                    render_buffer,
+                } => driver.create_render_buffer(render_buffer_id, render_buffer),
+                GpuTraceEvent::DestroyRenderBuffer { render_buffer_id } => {
+                    driver.destroy_render_buffer(render_buffer_id)
+                }
+                GpuTraceEvent::CreateGeometry {
+                    geometry_id,
+                    vertex_buffer,
+                    index_buffer,
+                } => driver.create_geometry(geometry_id, vertex_buffer, index_buffer),
+                GpuTraceEvent::UpdateGeometry {
+                    geometry_id,
+                    vertex_buffer,
+                    index_buffer,
+                } => driver.update_geometry(geometry_id, vertex_buffer, index_buffer),
+                GpuTraceEvent::DestroyGeometry { geometry_id } => {
+                    driver.destroy_geometry(geometry_id)
+                }
+                GpuTraceEvent::UpdateCommandList { commands } => {
+                    driver.update_command_list(commands)
+                }
+            }
+        }
+    }
+}
+
+/// A handle to the trace of a [`RecordingGpuDriver`], which can be read
+/// after the driver was passed to
+/// [`platform::set_gpu_driver`](crate::platform::set_gpu_driver).
+#[derive(Clone)]
+pub struct GpuTraceHandle {
+    trace: Arc<Mutex<GpuTrace>>,
+}
+
+impl GpuTraceHandle {
+    /// Take the events recorded so far, leaving the trace empty.
+    pub fn take(&self) -> GpuTrace {
+        std::mem::take(&mut *self.trace.lock().unwrap())
+    }
+
+    /// Copy the events recorded so far.
+    pub fn snapshot(&self) -> GpuTrace {
+        self.trace.lock().unwrap().clone()
+    }
+}
+
+/// A [`GpuDriver`] that draws nothing, and records every call into a [`GpuTrace`].
+///
+/// Ids are given out from `1` upward, separately for textures, render buffers and geometry.
+pub struct RecordingGpuDriver {
+    trace: Arc<Mutex<GpuTrace>>,
+    next_texture_id: u32,
+    next_render_buffer_id: u32,
+    next_geometry_id: u32,
+}
+
+impl Default for RecordingGpuDriver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RecordingGpuDriver {
+    /// Create a driver with an empty trace.
+    pub fn new() -> Self {
+        Self {
+            trace: Arc::new(Mutex::new(GpuTrace::default())),
+            next_texture_id: 1,
+            next_render_buffer_id: 1,
+            next_geometry_id: 1,
+        }
+    }
+
+    /// Get a handle to read the trace, even after the driver is moved away.
+    pub fn trace_handle(&self) -> GpuTraceHandle {
+        GpuTraceHandle {
+            trace: self.trace.clone(),
+        }
+    }
+
+    fn record(&self, event: GpuTraceEvent) {
+        self.trace.lock().unwrap().events.push(event);
+    }
+}
+
+impl GpuDriver for RecordingGpuDriver {
+    fn begin_synchronize(&mut self) {
+        self.record(GpuTraceEvent::BeginSynchronize);
+    }
+
+    fn end_synchronize(&mut self) {
+        self.record(GpuTraceEvent::EndSynchronize);
+    }
+
+    fn next_texture_id(&mut self) -> u32 {
+        next_id(&mut self.next_texture_id)
+    }
+
+    fn create_texture(&mut self, texture_id: u32, bitmap: OwnedBitmap) {
+        self.record(GpuTraceEvent::CreateTexture { texture_id, bitmap });
+    }
+
+    fn update_texture(&mut self, texture_id: u32, bitmap: OwnedBitmap) {
+        self.record(GpuTraceEvent::UpdateTexture { texture_id, bitmap });
+    }
+
+    fn destroy_texture(&mut self, texture_id: u32) {
+        self.record(GpuTraceEvent::DestroyTexture { texture_id });
+    }
+
+    fn next_render_buffer_id(&mut self) -> u32 {
+        next_id(&mut self.next_render_buffer_id)
+    }
+
+    fn create_render_buffer(&mut self, render_buffer_id: u32, render_buffer: RenderBuffer) {
+        self.record(GpuTraceEvent::CreateRenderBuffer {
+            render_buffer_id,
+            render_buffer,
+        });
+    }
+
+    fn destroy_render_buffer(&mut self, render_buffer_id: u32) {
+        self.record(GpuTraceEvent::DestroyRenderBuffer { render_buffer_id });
+    }
+
+    fn next_geometry_id(&mut self) -> u32 {
+        next_id(&mut self.next_geometry_id)
+    }
+
+    fn create_geometry(
+        &mut self,
+        geometry_id: u32,
+        vertex_buffer: VertexBuffer,
+        index_buffer: IndexBuffer,
+    ) {
+        self.record(GpuTraceEvent::CreateGeometry {
+            geometry_id,
+            vertex_buffer,
+            index_buffer,
+        });
+    }
+
+    fn update_geometry(
+        &mut self,
+        geometry_id: u32,
+        vertex_buffer: VertexBuffer,
+        index_buffer: IndexBuffer,
+    ) {
+        self.record(GpuTraceEvent::UpdateGeometry {
+            geometry_id,
+            vertex_buffer,
+            index_buffer,
+        });
+    }
+
+    fn destroy_geometry(&mut self, geometry_id: u32) {
+        self.record(GpuTraceEvent::DestroyGeometry { geometry_id });
+    }
+
+    fn update_command_list(&mut self, commands: Vec<GpuCommand>) {
+        self.record(GpuTraceEvent::UpdateCommandList { commands });
+    }
+}
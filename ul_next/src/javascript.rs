@@ -27,8 +27,9 @@ mod string;
 mod typed_array;
 mod value;
 
-pub use context::JSContext;
-pub use object::{JSObject, JSPropertyAttributes, JSPropertyNameArray};
+pub use class::{JSClass, JSClassDelegate};
+pub use context::{JSContext, JSContextGroup};
+pub use object::{JSDeferredPromise, JSObject, JSPropertyAttributes, JSPropertyNameArray};
 pub use string::JSString;
 pub use typed_array::{JSTypedArray, JSTypedArrayType};
-pub use value::{AsJSValue, JSType, JSValue};
+pub use value::{AsJSValue, JSProtectedValue, JSType, JSValue};
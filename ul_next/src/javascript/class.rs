@@ -1,3 +1,9 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use crate::Library;
+
+use super::{object::LIBRARY, JSContext, JSObject, JSString, JSValue};
+
 pub(crate) const EMPTY_CLASS_DEF: ul_sys::JSClassDefinition = ul_sys::JSClassDefinition {
     version: 0,
     attributes: 0,
@@ -17,3 +23,494 @@ pub(crate) const EMPTY_CLASS_DEF: ul_sys::JSClassDefinition = ul_sys::JSClassDef
     hasInstance: None,
     convertToType: None,
 };
+
+/// Hooks implementing the behavior of objects created from a [`JSClass`].
+///
+/// Each object owns its own instance of the implementing type, which is
+/// dropped when the object is garbage collected.
+///
+/// Every hook has a default implementation that leaves the request to the
+/// default object behavior (own properties, then the prototype chain).
+///
+/// Implementors must be [`Send`], as the garbage collector may drop them
+/// from another thread, see [`JSClassDelegate::finalize`].
+pub trait JSClassDelegate: Send + 'static {
+    /// Returns `true` if the object has the property `name`.
+    ///
+    /// Used by the `in` operator, the default implementation calls
+    /// [`JSClassDelegate::get_property`].
+    fn has_property(&self, ctx: &JSContext, object: &JSObject, name: &str) -> bool {
+        matches!(self.get_property(ctx, object, name), Ok(Some(_)))
+    }
+
+    /// Gets the value of the property `name`.
+    ///
+    /// Returns [`None`] to forward the request to the default object behavior,
+    /// or [`Err`] to throw an exception.
+    fn get_property<'c>(
+        &self,
+        ctx: &'c JSContext,
+        object: &JSObject<'c>,
+        name: &str,
+    ) -> Result<Option<JSValue<'c>>, JSValue<'c>> {
+        let _ = (ctx, object, name);
+        Ok(None)
+    }
+
+    /// Sets the value of the property `name`.
+    ///
+    /// Returns `false` to forward the request to the default object behavior,
+    /// or [`Err`] to throw an exception.
+    fn set_property<'c>(
+        &self,
+        ctx: &'c JSContext,
+        object: &JSObject<'c>,
+        name: &str,
+        value: &JSValue<'c>,
+    ) -> Result<bool, JSValue<'c>> {
+        let _ = (ctx, object, name, value);
+        Ok(false)
+    }
+
+    /// Deletes the property `name`.
+    ///
+    /// Returns `false` to forward the request to the default object behavior,
+    /// or [`Err`] to throw an exception.
+    fn delete_property<'c>(
+        &self,
+        ctx: &'c JSContext,
+        object: &JSObject<'c>,
+        name: &str,
+    ) -> Result<bool, JSValue<'c>> {
+        let _ = (ctx, object, name);
+        Ok(false)
+    }
+
+    /// Returns the names of the properties vended by [`JSClassDelegate::get_property`].
+    ///
+    /// Used by `Object.keys` and `for...in` loops.
+    fn property_names(&self, ctx: &JSContext, object: &JSObject) -> Vec<String> {
+        let _ = (ctx, object);
+        Vec::new()
+    }
+
+    /// Called when the object is called as a function.
+    ///
+    /// Only used by classes created with [`JSClass::new_callable`].
+    fn call_as_function<'c>(
+        &self,
+        ctx: &'c JSContext,
+        function: &JSObject<'c>,
+        this: &JSObject<'c>,
+        args: &[JSValue<'c>],
+    ) -> Result<JSValue<'c>, JSValue<'c>> {
+        let _ = (function, this, args);
+        Err(JSValue::new_string(ctx, "Object is not a function"))
+    }
+
+    /// Called when the class constructor is called with `new`,
+    /// see [`JSClass::make_constructor`].
+    ///
+    /// Usually returns an object created with [`JSClass::make_object`].
+    fn construct<'c>(
+        ctx: &'c JSContext,
+        constructor: &JSObject<'c>,
+        args: &[JSValue<'c>],
+    ) -> Result<JSObject<'c>, JSValue<'c>>
+    where
+        Self: Sized,
+    {
+        let _ = (constructor, args);
+        Err(JSValue::new_string(ctx, "Object is not a constructor"))
+    }
+
+    /// Called when the object is garbage collected, just before it is dropped.
+    ///
+    /// This can be called from any thread, and must not call into JavaScript.
+    /// State that must be released on the JavaScript thread should be handed
+    /// back to it instead, for example through a queue.
+    fn finalize(&mut self) {}
+}
+
+/// A JavaScript class, used to create objects whose behavior is implemented in Rust.
+///
+/// # Example
+/// ```rust,no_run
+/// # use ul_next::javascript::*;
+/// # let context: JSContext = unsafe {std::mem::zeroed()};
+/// # let lib: std::sync::Arc<ul_next::Library> = unsafe {std::mem::zeroed()};
+/// struct Counter(std::cell::Cell<f64>);
+///
+/// impl JSClassDelegate for Counter {
+///     fn get_property<'c>(
+///         &self,
+///         ctx: &'c JSContext,
+///         _object: &JSObject<'c>,
+///         name: &str,
+///     ) -> Result<Option<JSValue<'c>>, JSValue<'c>> {
+///         if name != "next" {
+///             return Ok(None);
+///         }
+///
+///         self.0.set(self.0.get() + 1.0);
+///         Ok(Some(JSValue::new_number(ctx, self.0.get())))
+///     }
+/// }
+///
+/// let class = JSClass::new(lib, "Counter");
+/// let counter = class.make_object(&context, Counter(std::cell::Cell::new(0.0)));
+/// context
+///     .global_object()
+///     .set_property("counter", &counter, JSPropertyAttributes::default());
+/// ```
+pub struct JSClass<T: JSClassDelegate> {
+    internal: ul_sys::JSClassRef,
+    lib: Arc<Library>,
+    phantom: PhantomData<fn(T)>,
+}
+
+impl<T: JSClassDelegate> JSClass<T> {
+    /// Creates a new class, objects of this class are not callable.
+    pub fn new(lib: Arc<Library>, name: &str) -> Self {
+        Self::create(lib, name, false)
+    }
+
+    /// Creates a new class, objects of this class can be called as functions
+    /// using [`JSClassDelegate::call_as_function`].
+    pub fn new_callable(lib: Arc<Library>, name: &str) -> Self {
+        Self::create(lib, name, true)
+    }
+
+    fn create(lib: Arc<Library>, name: &str, callable: bool) -> Self {
+        LIBRARY.get_or_init(|| lib.clone());
+
+        let name = std::ffi::CString::new(name).unwrap();
+
+        let class_def = ul_sys::JSClassDefinition {
+            className: name.as_ptr(),
+            finalize: Some(finalize::<T>),
+            hasProperty: Some(has_property::<T>),
+            getProperty: Some(get_property::<T>),
+            setProperty: Some(set_property::<T>),
+            deleteProperty: Some(delete_property::<T>),
+            getPropertyNames: Some(get_property_names::<T>),
+            callAsFunction: if callable {
+                Some(call_as_function::<T>)
+            } else {
+                None
+            },
+            ..EMPTY_CLASS_DEF
+        };
+
+        let internal = unsafe { lib.ultralight().JSClassCreate(&class_def) };
+
+        Self {
+            internal,
+            lib,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Creates an object of this class, owning `data`.
+    pub fn make_object<'a>(&self, ctx: &'a JSContext, data: T) -> JSObject<'a> {
+        let data: *mut T = Box::into_raw(Box::new(data));
+
+        let obj = unsafe {
+            ctx.lib
+                .ultralight()
+                .JSObjectMake(ctx.internal, self.internal, data as _)
+        };
+
+        JSObject::copy_from_raw(ctx, obj)
+    }
+
+    /// Creates a constructor for this class, calling [`JSClassDelegate::construct`].
+    ///
+    /// The constructor's `prototype` property is set up so that `instanceof`
+    /// expressions work with objects of this class.
+    pub fn make_constructor<'a>(&self, ctx: &'a JSContext) -> JSObject<'a> {
+        let obj = unsafe {
+            ctx.lib.ultralight().JSObjectMakeConstructor(
+                ctx.internal,
+                self.internal,
+                Some(call_as_constructor::<T>),
+            )
+        };
+
+        JSObject::copy_from_raw(ctx, obj)
+    }
+
+    /// Returns the data of `object` if it was created from this class.
+    pub fn data<'o>(&self, object: &'o JSObject) -> Option<&'o T> {
+        let is_of_class = unsafe {
+            self.lib.ultralight().JSValueIsObjectOfClass(
+                object.value.ctx.internal,
+                object.value.internal,
+                self.internal,
+            )
+        };
+
+        if !is_of_class {
+            return None;
+        }
+
+        unsafe {
+            let data = self
+                .lib
+                .ultralight()
+                .JSObjectGetPrivate(object.value.internal as _) as *const T;
+            data.as_ref()
+        }
+    }
+}
+
+impl<T: JSClassDelegate> Clone for JSClass<T> {
+    fn clone(&self) -> Self {
+        let internal = unsafe { self.lib.ultralight().JSClassRetain(self.internal) };
+
+        Self {
+            internal,
+            lib: self.lib.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: JSClassDelegate> Drop for JSClass<T> {
+    fn drop(&mut self) {
+        unsafe {
+            self.lib.ultralight().JSClassRelease(self.internal);
+        }
+    }
+}
+
+unsafe fn delegate<'d, T: JSClassDelegate>(
+    lib: &Library,
+    object: ul_sys::JSObjectRef,
+) -> Option<&'d T> {
+    (lib.ultralight().JSObjectGetPrivate(object) as *const T).as_ref()
+}
+
+unsafe fn property_name(lib: &Arc<Library>, name: ul_sys::JSStringRef) -> String {
+    String::from(&JSString::copy_from_raw(lib.clone(), name))
+}
+
+unsafe fn set_exception(exception: *mut ul_sys::JSValueRef, value: JSValue) {
+    if !exception.is_null() {
+        *exception = value.into_raw();
+    }
+}
+
+unsafe extern "C" fn finalize<T: JSClassDelegate>(object: ul_sys::JSObjectRef) {
+    let _guard = scopeguard::guard_on_unwind((), |()| {
+        ::std::process::abort();
+    });
+
+    let lib = ffi_unwrap!(LIBRARY.get(), "library undefined ptr",);
+
+    let data = lib.ultralight().JSObjectGetPrivate(object) as *mut T;
+    if !data.is_null() {
+        let mut data = Box::from_raw(data);
+        data.finalize();
+    }
+}
+
+unsafe extern "C" fn has_property<T: JSClassDelegate>(
+    ctx: ul_sys::JSContextRef,
+    object: ul_sys::JSObjectRef,
+    name: ul_sys::JSStringRef,
+) -> bool {
+    let _guard = scopeguard::guard_on_unwind((), |()| {
+        ::std::process::abort();
+    });
+
+    let lib = ffi_unwrap!(LIBRARY.get(), "library undefined ptr",);
+    let data = ffi_unwrap!(delegate::<T>(lib, object), "null ptr",);
+
+    let ctx = JSContext::copy_from_raw(lib.clone(), ctx);
+    let object = JSObject::copy_from_raw(&ctx, object);
+    let name = property_name(lib, name);
+
+    data.has_property(&ctx, &object, &name)
+}
+
+unsafe extern "C" fn get_property<T: JSClassDelegate>(
+    ctx: ul_sys::JSContextRef,
+    object: ul_sys::JSObjectRef,
+    name: ul_sys::JSStringRef,
+    exception: *mut ul_sys::JSValueRef,
+) -> ul_sys::JSValueRef {
+    let _guard = scopeguard::guard_on_unwind((), |()| {
+        ::std::process::abort();
+    });
+
+    let lib = ffi_unwrap!(LIBRARY.get(), "library undefined ptr",);
+    let data = ffi_unwrap!(delegate::<T>(lib, object), "null ptr",);
+
+    let ctx = JSContext::copy_from_raw(lib.clone(), ctx);
+    let object = JSObject::copy_from_raw(&ctx, object);
+    let name = property_name(lib, name);
+
+    let ret = data.get_property(&ctx, &object, &name);
+    match ret {
+        Ok(Some(value)) => value.into_raw(),
+        Ok(None) => std::ptr::null(),
+        Err(value) => {
+            set_exception(exception, value);
+            std::ptr::null()
+        }
+    }
+}
+
+unsafe extern "C" fn set_property<T: JSClassDelegate>(
+    ctx: ul_sys::JSContextRef,
+    object: ul_sys::JSObjectRef,
+    name: ul_sys::JSStringRef,
+    value: ul_sys::JSValueRef,
+    exception: *mut ul_sys::JSValueRef,
+) -> bool {
+    let _guard = scopeguard::guard_on_unwind((), |()| {
+        ::std::process::abort();
+    });
+
+    let lib = ffi_unwrap!(LIBRARY.get(), "library undefined ptr",);
+    let data = ffi_unwrap!(delegate::<T>(lib, object), "null ptr",);
+
+    let ctx = JSContext::copy_from_raw(lib.clone(), ctx);
+    let object = JSObject::copy_from_raw(&ctx, object);
+    let name = property_name(lib, name);
+    let value = JSValue::copy_from_raw(&ctx, value);
+
+    let ret = data.set_property(&ctx, &object, &name, &value);
+    match ret {
+        Ok(handled) => handled,
+        Err(value) => {
+            set_exception(exception, value);
+            false
+        }
+    }
+}
+
+unsafe extern "C" fn delete_property<T: JSClassDelegate>(
+    ctx: ul_sys::JSContextRef,
+    object: ul_sys::JSObjectRef,
+    name: ul_sys::JSStringRef,
+    exception: *mut ul_sys::JSValueRef,
+) -> bool {
+    let _guard = scopeguard::guard_on_unwind((), |()| {
+        ::std::process::abort();
+    });
+
+    let lib = ffi_unwrap!(LIBRARY.get(), "library undefined ptr",);
+    let data = ffi_unwrap!(delegate::<T>(lib, object), "null ptr",);
+
+    let ctx = JSContext::copy_from_raw(lib.clone(), ctx);
+    let object = JSObject::copy_from_raw(&ctx, object);
+    let name = property_name(lib, name);
+
+    let ret = data.delete_property(&ctx, &object, &name);
+    match ret {
+        Ok(handled) => handled,
+        Err(value) => {
+            set_exception(exception, value);
+            false
+        }
+    }
+}
+
+unsafe extern "C" fn get_property_names<T: JSClassDelegate>(
+    ctx: ul_sys::JSContextRef,
+    object: ul_sys::JSObjectRef,
+    accumulator: ul_sys::JSPropertyNameAccumulatorRef,
+) {
+    let _guard = scopeguard::guard_on_unwind((), |()| {
+        ::std::process::abort();
+    });
+
+    let lib = ffi_unwrap!(LIBRARY.get(), "library undefined ptr",);
+    let data = ffi_unwrap!(delegate::<T>(lib, object), "null ptr",);
+
+    let ctx = JSContext::copy_from_raw(lib.clone(), ctx);
+    let object = JSObject::copy_from_raw(&ctx, object);
+
+    for name in data.property_names(&ctx, &object) {
+        let name = JSString::new(lib.clone(), &name);
+        lib.ultralight()
+            .JSPropertyNameAccumulatorAddName(accumulator, name.internal);
+    }
+}
+
+unsafe extern "C" fn call_as_function<T: JSClassDelegate>(
+    ctx: ul_sys::JSContextRef,
+    function: ul_sys::JSObjectRef,
+    this_object: ul_sys::JSObjectRef,
+    argument_count: usize,
+    arguments: *const ul_sys::JSValueRef,
+    exception: *mut ul_sys::JSValueRef,
+) -> ul_sys::JSValueRef {
+    let _guard = scopeguard::guard_on_unwind((), |()| {
+        ::std::process::abort();
+    });
+
+    let lib = ffi_unwrap!(LIBRARY.get(), "library undefined ptr",);
+    let data = ffi_unwrap!(delegate::<T>(lib, function), "null ptr",);
+
+    let ctx = JSContext::copy_from_raw(lib.clone(), ctx);
+    let function = JSObject::copy_from_raw(&ctx, function);
+    let this = if this_object.is_null() {
+        ctx.global_object()
+    } else {
+        JSObject::copy_from_raw(&ctx, this_object)
+    };
+    let args = if argument_count == 0 {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(arguments, argument_count)
+            .iter()
+            .map(|v| JSValue::copy_from_raw(&ctx, *v))
+            .collect::<Vec<_>>()
+    };
+
+    let ret = data.call_as_function(&ctx, &function, &this, &args);
+    match ret {
+        Ok(value) => value.into_raw(),
+        Err(value) => {
+            set_exception(exception, value);
+            std::ptr::null()
+        }
+    }
+}
+
+unsafe extern "C" fn call_as_constructor<T: JSClassDelegate>(
+    ctx: ul_sys::JSContextRef,
+    constructor: ul_sys::JSObjectRef,
+    argument_count: usize,
+    arguments: *const ul_sys::JSValueRef,
+    exception: *mut ul_sys::JSValueRef,
+) -> ul_sys::JSObjectRef {
+    let _guard = scopeguard::guard_on_unwind((), |()| {
+        ::std::process::abort();
+    });
+
+    let lib = ffi_unwrap!(LIBRARY.get(), "library undefined ptr",);
+
+    let ctx = JSContext::copy_from_raw(lib.clone(), ctx);
+    let constructor = JSObject::copy_from_raw(&ctx, constructor);
+    let args = if argument_count == 0 {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(arguments, argument_count)
+            .iter()
+            .map(|v| JSValue::copy_from_raw(&ctx, *v))
+            .collect::<Vec<_>>()
+    };
+
+    let ret = T::construct(&ctx, &constructor, &args);
+    match ret {
+        Ok(object) => object.value.into_raw() as _,
+        Err(value) => {
+            set_exception(exception, value);
+            std::ptr::null_mut()
+        }
+    }
+}
@@ -32,6 +32,30 @@ impl JSContext {
         Self { internal: ctx, lib }
     }
 
+    /// Create a new JavaScript execution context in a context group.
+    ///
+    /// Contexts in the same group may share and exchange JavaScript objects.
+    pub fn new_in_group(group: &JSContextGroup) -> Self {
+        let ctx = unsafe {
+            group
+                .lib
+                .ultralight()
+                .JSGlobalContextCreateInGroup(group.internal, std::ptr::null_mut())
+        };
+
+        Self {
+            internal: ctx,
+            lib: group.lib.clone(),
+        }
+    }
+
+    /// Get the context group this context belongs to.
+    pub fn group(&self) -> JSContextGroup {
+        let group = unsafe { self.lib.ultralight().JSContextGetGroup(self.internal) };
+
+        JSContextGroup::copy_from_raw(self.lib.clone(), group)
+    }
+
     /// Get the global object for this context.
     pub fn global_object(&self) -> JSObject {
         JSObject::copy_from_raw(self, unsafe {
@@ -68,6 +92,17 @@ impl JSContext {
         Some(JSString::from_raw(self.lib.clone(), name))
     }
 
+    /// Set the name of this context, shown when inspecting the context.
+    pub fn set_name(&self, name: &str) {
+        let name = JSString::new(self.lib.clone(), name);
+
+        unsafe {
+            self.lib
+                .ultralight()
+                .JSGlobalContextSetName(self.internal as _, name.internal)
+        }
+    }
+
     /// Gets whether the context is inspectable in Web Inspector.
     pub fn is_inspectable(&self) -> bool {
         unsafe {
@@ -77,21 +112,50 @@ impl JSContext {
         }
     }
 
+    /// Sets whether the context is inspectable in Web Inspector, `false` by default.
+    pub fn set_inspectable(&self, inspectable: bool) {
+        unsafe {
+            self.lib
+                .ultralight()
+                .JSGlobalContextSetInspectable(self.internal as _, inspectable)
+        }
+    }
+
     /// Evaluate a JavaScript script in this context.
     ///
     /// If an exception is thrown during evaluation, it will be returned as an
     /// [`Err`] value, otherwise the result of the script evaluation will be
     /// returned as an [`Ok`] value.
     pub fn evaluate_script(&self, script: &str) -> Result<JSValue, JSValue> {
+        self.evaluate_script_with_source(script, None, 0)
+    }
+
+    /// Evaluate a JavaScript script in this context, as if it was loaded from `source_url`.
+    ///
+    /// `source_url` and `starting_line_number` (1-based) are used when reporting
+    /// exceptions and console messages.
+    ///
+    /// If an exception is thrown during evaluation, it will be returned as an
+    /// [`Err`] value, otherwise the result of the script evaluation will be
+    /// returned as an [`Ok`] value.
+    pub fn evaluate_script_with_source(
+        &self,
+        script: &str,
+        source_url: Option<&str>,
+        starting_line_number: i32,
+    ) -> Result<JSValue<'_>, JSValue<'_>> {
         let script = JSString::new(self.lib.clone(), script);
+        let source_url = source_url.map(|s| JSString::new(self.lib.clone(), s));
         let mut exception = std::ptr::null();
         let ret = unsafe {
             self.lib.ultralight().JSEvaluateScript(
                 self.internal,
                 script.internal,
                 std::ptr::null_mut(),
-                std::ptr::null_mut(),
-                0,
+                source_url
+                    .as_ref()
+                    .map_or(std::ptr::null_mut(), |s| s.internal),
+                starting_line_number,
                 &mut exception,
             )
         };
@@ -109,14 +173,30 @@ impl JSContext {
     ///
     /// `true` if the script is syntactically correct, otherwise `false`.
     pub fn check_script_syntax(&self, script: &str) -> Result<bool, JSValue> {
+        self.check_script_syntax_with_source(script, None, 0)
+    }
+
+    /// Checks for syntax errors in a string of JavaScript, as if it was loaded from `source_url`.
+    ///
+    /// Returns [`Err`] with the `SyntaxError` if the script is not syntactically correct.
+    /// The error's `line` property is relative to `starting_line_number` (1-based).
+    pub fn check_script_syntax_with_source(
+        &self,
+        script: &str,
+        source_url: Option<&str>,
+        starting_line_number: i32,
+    ) -> Result<bool, JSValue<'_>> {
         let script = JSString::new(self.lib.clone(), script);
+        let source_url = source_url.map(|s| JSString::new(self.lib.clone(), s));
         let mut exception = std::ptr::null();
         let ret = unsafe {
             self.lib.ultralight().JSCheckScriptSyntax(
                 self.internal,
                 script.internal,
-                std::ptr::null_mut(),
-                0,
+                source_url
+                    .as_ref()
+                    .map_or(std::ptr::null_mut(), |s| s.internal),
+                starting_line_number,
                 &mut exception,
             )
         };
@@ -168,3 +248,56 @@ impl Drop for JSContext {
         }
     }
 }
+
+/// A group of JavaScript execution contexts.
+///
+/// Contexts in the same group may share and exchange JavaScript objects,
+/// see [`JSContext::new_in_group`]. Exchanging objects between contexts in
+/// different groups is undefined behavior.
+pub struct JSContextGroup {
+    internal: ul_sys::JSContextGroupRef,
+    lib: Arc<Library>,
+}
+
+impl JSContextGroup {
+    pub(crate) fn copy_from_raw(lib: Arc<Library>, group: ul_sys::JSContextGroupRef) -> Self {
+        assert!(!group.is_null());
+
+        let group = unsafe { lib.ultralight().JSContextGroupRetain(group) };
+
+        Self {
+            internal: group,
+            lib,
+        }
+    }
+
+    /// Create a new context group.
+    pub fn new(lib: Arc<Library>) -> Self {
+        let group = unsafe { lib.ultralight().JSContextGroupCreate() };
+
+        Self {
+            internal: group,
+            lib,
+        }
+    }
+}
+
+impl fmt::Debug for JSContextGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JSContextGroup").finish()
+    }
+}
+
+impl Clone for JSContextGroup {
+    fn clone(&self) -> Self {
+        Self::copy_from_raw(self.lib.clone(), self.internal)
+    }
+}
+
+impl Drop for JSContextGroup {
+    fn drop(&mut self) {
+        unsafe {
+            self.lib.ultralight().JSContextGroupRelease(self.internal);
+        }
+    }
+}
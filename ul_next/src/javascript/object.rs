@@ -10,7 +10,7 @@ use super::{AsJSValue, JSContext, JSString, JSValue};
 
 // TODO: major hack, not sure how to get access to the Library
 //       from inside the trampoline
-static LIBRARY: OnceLock<Arc<Library>> = OnceLock::new();
+pub(super) static LIBRARY: OnceLock<Arc<Library>> = OnceLock::new();
 
 /// Attributes for JavaScript properties.
 ///
@@ -76,6 +76,17 @@ pub struct JSObject<'a> {
     pub(crate) value: JSValue<'a>,
 }
 
+/// A promise created by [`JSObject::new_deferred_promise`].
+#[derive(Clone, Debug)]
+pub struct JSDeferredPromise<'a> {
+    /// The promise object, to be returned to JavaScript.
+    pub promise: JSObject<'a>,
+    /// Function resolving the promise with its first argument.
+    pub resolve: JSObject<'a>,
+    /// Function rejecting the promise with its first argument.
+    pub reject: JSObject<'a>,
+}
+
 impl<'a> JSObject<'a> {
     pub(crate) fn copy_from_raw(ctx: &'a JSContext, obj: ul_sys::JSObjectRef) -> Self {
         assert!(!obj.is_null());
@@ -108,10 +119,14 @@ impl<'a> JSObject<'a> {
     /// Results in a JSObject that is a function. The object's prototype will be the default function prototype.
     ///
     /// This can be used to execute Rust code from JavaScript.
+    ///
+    /// The callback must be [`Send`], as the garbage collector may drop it
+    /// from another thread when the function is finalized.
     pub fn new_function_with_callback<F>(ctx: &'a JSContext, callback: F) -> Self
     where
-        for<'c> F:
-            FnMut(&'c JSContext, &JSObject<'c>, &[JSValue<'c>]) -> Result<JSValue<'c>, JSValue<'c>>,
+        for<'c> F: FnMut(&'c JSContext, &JSObject<'c>, &[JSValue<'c>]) -> Result<JSValue<'c>, JSValue<'c>>
+            + Send
+            + 'static,
     {
         LIBRARY.get_or_init(|| ctx.lib.clone());
 
@@ -285,6 +300,66 @@ impl<'a> JSObject<'a> {
         }
     }
 
+    /// Creates a JavaScript Date object, as if by invoking the built-in `Date` constructor.
+    ///
+    /// `args` are the arguments passed to the constructor, e.g. the number of
+    /// milliseconds since the Unix epoch. Pass no arguments to create a Date with the current time.
+    pub fn new_date(ctx: &'a JSContext, args: &[JSValue]) -> Result<Self, JSValue<'a>> {
+        let args_ptrs: Vec<_> = args.iter().map(|v| v.internal).collect();
+
+        let mut exception = std::ptr::null();
+
+        let result = unsafe {
+            ctx.lib.ultralight().JSObjectMakeDate(
+                ctx.internal,
+                args_ptrs.len() as _,
+                args_ptrs.as_ptr(),
+                &mut exception,
+            )
+        };
+
+        if !exception.is_null() {
+            Err(JSValue::from_raw(ctx, exception))
+        } else if result.is_null() {
+            Err(JSValue::new_string(ctx, "Failed to create date"))
+        } else {
+            Ok(Self {
+                value: JSValue::from_raw(ctx, result),
+            })
+        }
+    }
+
+    /// Creates a JavaScript RegExp object, as if by invoking the built-in `RegExp` constructor.
+    ///
+    /// `args` are the arguments passed to the constructor: the pattern and optionally the flags.
+    pub fn new_regexp(ctx: &'a JSContext, args: &[JSValue]) -> Result<Self, JSValue<'a>> {
+        let args_ptrs: Vec<_> = args.iter().map(|v| v.internal).collect();
+
+        let mut exception = std::ptr::null();
+
+        let result = unsafe {
+            ctx.lib.ultralight().JSObjectMakeRegExp(
+                ctx.internal,
+                args_ptrs.len() as _,
+                args_ptrs.as_ptr(),
+                &mut exception,
+            )
+        };
+
+        if !exception.is_null() {
+            Err(JSValue::from_raw(ctx, exception))
+        } else if result.is_null() {
+            Err(JSValue::new_string(
+                ctx,
+                "Failed to create regular expression",
+            ))
+        } else {
+            Ok(Self {
+                value: JSValue::from_raw(ctx, result),
+            })
+        }
+    }
+
     pub fn new_error(ctx: &'a JSContext, value: JSValue) -> Result<Self, JSValue<'a>> {
         let items = vec![value];
         let items_ptrs: Vec<_> = items.iter().map(|v| v.internal).collect();
@@ -311,6 +386,63 @@ impl<'a> JSObject<'a> {
         }
     }
 
+    /// Creates a JavaScript promise object that is settled from native code.
+    ///
+    /// Returns the promise together with its `resolve` and `reject` functions,
+    /// which can be called later with [`JSObject::call_as_function`].
+    pub fn new_deferred_promise(ctx: &'a JSContext) -> Result<JSDeferredPromise<'a>, JSValue<'a>> {
+        let mut resolve = std::ptr::null_mut();
+        let mut reject = std::ptr::null_mut();
+        let mut exception = std::ptr::null();
+
+        let result = unsafe {
+            ctx.lib.ultralight().JSObjectMakeDeferredPromise(
+                ctx.internal,
+                &mut resolve,
+                &mut reject,
+                &mut exception,
+            )
+        };
+
+        if !exception.is_null() {
+            Err(JSValue::from_raw(ctx, exception))
+        } else if result.is_null() || resolve.is_null() || reject.is_null() {
+            Err(JSValue::new_string(ctx, "Failed to create promise"))
+        } else {
+            Ok(JSDeferredPromise {
+                promise: JSObject::copy_from_raw(ctx, result),
+                resolve: JSObject::copy_from_raw(ctx, resolve),
+                reject: JSObject::copy_from_raw(ctx, reject),
+            })
+        }
+    }
+
+    /// Gets the object's prototype.
+    pub fn prototype(&self) -> JSValue<'a> {
+        let result = unsafe {
+            self.value
+                .ctx
+                .lib
+                .ultralight()
+                .JSObjectGetPrototype(self.value.ctx.internal, self.value.internal as _)
+        };
+
+        JSValue::copy_from_raw(self.value.ctx, result)
+    }
+
+    /// Sets the object's prototype.
+    ///
+    /// `prototype` should be an object or `null`.
+    pub fn set_prototype(&self, prototype: &JSValue) {
+        unsafe {
+            self.value.ctx.lib.ultralight().JSObjectSetPrototype(
+                self.value.ctx.internal,
+                self.value.internal as _,
+                prototype.internal,
+            )
+        }
+    }
+
     /// Tests whether an object can be called as a function.
     pub fn is_function(&self) -> bool {
         unsafe {
@@ -341,7 +473,7 @@ impl<'a> JSObject<'a> {
         &self,
         this: Option<&JSObject>,
         args: &[JSValue],
-    ) -> Result<JSValue, JSValue> {
+    ) -> Result<JSValue<'a>, JSValue<'a>> {
         let mut exception = std::ptr::null();
 
         let args: Vec<_> = args.iter().map(|v| v.internal).collect();
@@ -401,12 +533,12 @@ impl<'a> JSObject<'a> {
     }
 }
 
-impl JSObject<'_> {
+impl<'a> JSObject<'a> {
     /// Gets a property from an object by name.
     ///
     /// Returns the property's value if object has the property, otherwise the undefined value,
     /// or [`Err`] if an exception is thrown.
-    pub fn get_property(&self, name: &str) -> Result<JSValue, JSValue> {
+    pub fn get_property(&self, name: &str) -> Result<JSValue<'a>, JSValue<'a>> {
         let name = JSString::new(self.ctx.lib.clone(), name);
         let mut exception = std::ptr::null();
 
@@ -436,7 +568,7 @@ impl JSObject<'_> {
     /// Calling [`JSObject::get_property_at_index`] is equivalent to calling [`JSObject::get_property`]
     /// with a string containing `index`, but [`JSObject::get_property_at_index`] provides optimized
     /// access to numeric properties.
-    pub fn get_property_at_index(&self, index: u32) -> Result<JSValue, JSValue> {
+    pub fn get_property_at_index(&self, index: u32) -> Result<JSValue<'a>, JSValue<'a>> {
         let mut exception = std::ptr::null();
 
         let result_raw = unsafe {
@@ -569,7 +701,7 @@ impl JSObject<'_> {
     /// or [`Err`] if an exception is thrown.
     ///
     /// This function is the same as performing `object[propertyKey](propertyKey)` from JavaScript.
-    pub fn get_property_for_key(&self, key: &JSValue) -> Result<JSValue, JSValue> {
+    pub fn get_property_for_key(&self, key: &JSValue) -> Result<JSValue<'a>, JSValue<'a>> {
         let mut exception = std::ptr::null();
 
         let result_raw = unsafe {
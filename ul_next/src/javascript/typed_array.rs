@@ -186,6 +186,85 @@ impl<'a> JSTypedArray<'a> {
         }
     }
 
+    /// Returns the bytes of a JavaScript Typed Array or `ArrayBuffer` object.
+    ///
+    /// For Typed Arrays, only the bytes visible through the array (starting at
+    /// [`byte_offset`](Self::byte_offset), [`byte_length`](Self::byte_length) long)
+    /// are returned.
+    ///
+    /// The slice points into the JavaScript buffer directly, it should not be kept
+    /// across calls that may run JavaScript code.
+    ///
+    /// Returns [`Err`] if an exception occurred while accessing the buffer.
+    pub fn bytes(&self) -> Result<&[u8], JSValue<'a>> {
+        let mut exception = std::ptr::null();
+
+        let (ptr, offset, len) = if self.ty()? == JSTypedArrayType::ArrayBuffer {
+            let ptr = unsafe {
+                self.value
+                    .ctx
+                    .lib
+                    .ultralight()
+                    .JSObjectGetArrayBufferBytesPtr(
+                        self.value.ctx.internal,
+                        self.value.internal as _,
+                        &mut exception,
+                    )
+            };
+
+            if !exception.is_null() {
+                return Err(JSValue::from_raw(self.value.ctx, exception));
+            }
+
+            let len = unsafe {
+                self.value
+                    .ctx
+                    .lib
+                    .ultralight()
+                    .JSObjectGetArrayBufferByteLength(
+                        self.value.ctx.internal,
+                        self.value.internal as _,
+                        &mut exception,
+                    )
+            };
+
+            if !exception.is_null() {
+                return Err(JSValue::from_raw(self.value.ctx, exception));
+            }
+
+            (ptr, 0, len)
+        } else {
+            let ptr = unsafe {
+                self.value
+                    .ctx
+                    .lib
+                    .ultralight()
+                    .JSObjectGetTypedArrayBytesPtr(
+                        self.value.ctx.internal,
+                        self.value.internal as _,
+                        &mut exception,
+                    )
+            };
+
+            if !exception.is_null() {
+                return Err(JSValue::from_raw(self.value.ctx, exception));
+            }
+
+            (ptr, self.byte_offset()?, self.byte_length()?)
+        };
+
+        if len == 0 {
+            Ok(&[])
+        } else if ptr.is_null() {
+            Err(JSValue::new_string(
+                self.value.ctx,
+                "Failed to get typed array bytes",
+            ))
+        } else {
+            Ok(unsafe { std::slice::from_raw_parts((ptr as *const u8).add(offset), len) })
+        }
+    }
+
     /// Returns the type of a JavaScript Typed Array object.
     ///
     /// Returns [`Err`] if an exception occurred while getting the type,
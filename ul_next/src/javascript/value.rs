@@ -310,6 +310,17 @@ impl JSValue<'_> {
             Some(_) => true,
         }
     }
+
+    /// Tests whether two JavaScript values are strict equal, as compared by the JS `===` operator.
+    pub fn is_strict_equal(&self, other: &JSValue) -> bool {
+        unsafe {
+            self.ctx.lib.ultralight().JSValueIsStrictEqual(
+                self.ctx.internal,
+                self.internal,
+                other.internal,
+            )
+        }
+    }
 }
 
 impl<'a> JSValue<'a> {
@@ -341,6 +352,51 @@ impl<'a> JSValue<'a> {
         }
     }
 
+    /// Tests whether two JavaScript values are equal, as compared by the JS `==` operator.
+    ///
+    /// Returns an [`Err`] if an exception is thrown.
+    pub fn is_equal(&self, other: &JSValue) -> Result<bool, JSValue<'a>> {
+        let mut exception = std::ptr::null();
+
+        let result = unsafe {
+            self.ctx.lib.ultralight().JSValueIsEqual(
+                self.ctx.internal,
+                self.internal,
+                other.internal,
+                &mut exception,
+            )
+        };
+
+        if !exception.is_null() {
+            Err(JSValue::from_raw(self.ctx, exception))
+        } else {
+            Ok(result)
+        }
+    }
+
+    /// Tests whether a JavaScript value is an object constructed by a given constructor,
+    /// as compared by the JS `instanceof` operator.
+    ///
+    /// Returns an [`Err`] if an exception is thrown.
+    pub fn is_instance_of_constructor(&self, constructor: &JSObject) -> Result<bool, JSValue<'a>> {
+        let mut exception = std::ptr::null();
+
+        let result = unsafe {
+            self.ctx.lib.ultralight().JSValueIsInstanceOfConstructor(
+                self.ctx.internal,
+                self.internal,
+                constructor.internal as _,
+                &mut exception,
+            )
+        };
+
+        if !exception.is_null() {
+            Err(JSValue::from_raw(self.ctx, exception))
+        } else {
+            Ok(result)
+        }
+    }
+
     /// Converts a JavaScript value to string.
     ///
     /// Returns an [`Err`] if an exception is thrown.
@@ -441,6 +497,72 @@ impl<'a> JSValue<'a> {
     }
 }
 
+/// A JavaScript value that can outlive the callback it was received in.
+///
+/// The value is protected from garbage collection and keeps its own reference
+/// to the [`JSContext`], so it can be stored (e.g. a JavaScript callback passed to
+/// Rust) and used later. The protection is released when this is dropped.
+///
+/// For the context of a [`View`](crate::View), use and drop it while holding
+/// [`View::lock_js_context`](crate::View::lock_js_context).
+pub struct JSProtectedValue {
+    internal: ul_sys::JSValueRef,
+    ctx: JSContext,
+}
+
+impl JSProtectedValue {
+    /// Protects `value` and takes a reference to its context.
+    pub fn new(value: &JSValue) -> Self {
+        unsafe {
+            value
+                .ctx
+                .lib
+                .ultralight()
+                .JSValueProtect(value.ctx.internal, value.internal);
+        }
+
+        Self {
+            internal: value.internal,
+            ctx: value.ctx.clone(),
+        }
+    }
+
+    /// Returns the context this value belongs to.
+    pub fn context(&self) -> &JSContext {
+        &self.ctx
+    }
+
+    /// Returns a [`JSValue`] handle to the protected value.
+    pub fn value(&self) -> JSValue<'_> {
+        JSValue::copy_from_raw(&self.ctx, self.internal)
+    }
+
+    /// Returns a [`JSValue`] handle to the protected value, bound to `ctx`.
+    ///
+    /// `ctx` must be in the same context group as the value, like the context
+    /// given to a callback of the page the value was received from.
+    pub fn value_in<'c>(&self, ctx: &'c JSContext) -> JSValue<'c> {
+        JSValue::copy_from_raw(ctx, self.internal)
+    }
+}
+
+impl fmt::Debug for JSProtectedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value().fmt(f)
+    }
+}
+
+impl Drop for JSProtectedValue {
+    fn drop(&mut self) {
+        unsafe {
+            self.ctx
+                .lib
+                .ultralight()
+                .JSValueUnprotect(self.ctx.internal, self.internal);
+        }
+    }
+}
+
 impl Clone for JSValue<'_> {
     fn clone(&self) -> Self {
         unsafe {
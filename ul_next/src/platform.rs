@@ -14,6 +14,7 @@ use crate::error::CreationError;
 use crate::{
     gpu_driver::{self, GpuDriver},
     string::UlString,
+    surface::{self, SurfaceDefinition},
     Library,
 };
 
@@ -25,6 +26,7 @@ lazy_static::lazy_static! {
     static ref FILESYSTEM: InternalPlatform<Box<dyn FileSystem + Send>> = InternalPlatform::new();
     static ref FONTLOADER: InternalPlatform<Box<dyn FontLoader + Send>> = InternalPlatform::new();
     pub(crate) static ref GPUDRIVER: InternalPlatform<Box<dyn GpuDriver + Send>> = InternalPlatform::new();
+    pub(crate) static ref SURFACE_DEFINITION: InternalPlatform<Box<dyn SurfaceDefinition + Send>> = InternalPlatform::new();
 }
 
 pub(crate) struct InternalPlatform<T> {
@@ -418,6 +420,21 @@ pub fn set_gpu_driver<G: GpuDriver + Send + 'static>(lib: Arc<Library>, driver:
     gpu_driver::set_gpu_driver(lib, driver)
 }
 
+/// Set a custom Surface implementation.
+///
+/// This can be used to cache pixel data or make the renderer paint directly to a block of memory
+/// controlled by you, see [`SurfaceDefinition`].
+///
+/// Note: only used when the CPU renderer is used, accelerated views use the GpuDriver instead.
+///
+/// You should call this before [`Renderer::create`](crate::renderer::Renderer::create).
+pub fn set_surface_definition<S: SurfaceDefinition + Send + 'static>(
+    lib: Arc<Library>,
+    definition: S,
+) {
+    surface::set_surface_definition(lib, definition)
+}
+
 /// Initializes the default logger (writes the log to a file).
 ///
 /// This is only needed if you are not calling [`App::new`](crate::app::App::new)
@@ -1,5 +1,6 @@
 //! A container for Rectangle structure.
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 /// Rectangle structure
 pub struct Rect<T> {
     pub left: T,
@@ -3,11 +3,12 @@
 //! `Surface`s are used only when the [`View`](crate::view::View) is not accelerated.
 
 use std::{
+    ffi::c_void,
     ops::{Deref, DerefMut},
     sync::Arc,
 };
 
-use crate::{rect::Rect, Library};
+use crate::{platform::SURFACE_DEFINITION, rect::Rect, Library};
 
 /// An RAII implementation of a “scoped lock” of a pixel buffer for [`Surface`].
 /// When this structure is dropped (falls out of scope), the lock will be unlocked.
@@ -50,21 +51,19 @@ impl Drop for PixelsGuard<'_> {
 ///
 /// When using the CPU renderer, each View is painted to its own Surface.
 ///
-// TODO: add support and remove comments below (note //)
-/// **NOTE: Custom Surface is currently not support in this Rust wrapper**
-//
-// You can provide your own Surface implementation to make the renderer paint directly to a block
-// of memory controlled by you (this is useful for lower-latency uploads to GPU memory or other
-// platform-specific bitmaps).
-//
-// A default Surface implementation, `BitmapSurface`, is automatically provided by the library when
-// you call [`Renderer::create`](crate::renderer::Renderer::create)
-// without defining a custom `SurfaceFactory`.
-//
-// To provide your own custom Surface implementation, you should implement the
-// `SurfaceFactory` trait, and pass the struct to `platform::set_surface_definition`
-// before calling [`Renderer::create`](crate::renderer::Renderer::create) or
-// [`App::new`](crate::app::App::new).
+/// You can provide your own Surface implementation to make the renderer paint directly to a block
+/// of memory controlled by you (this is useful for lower-latency uploads to GPU memory or other
+/// platform-specific bitmaps).
+///
+/// A default Surface implementation, `BitmapSurface`, is automatically provided by the library when
+/// you call [`Renderer::create`](crate::renderer::Renderer::create)
+/// without defining a custom [`SurfaceDefinition`].
+///
+/// To provide your own custom Surface implementation, you should implement the
+/// [`SurfaceDefinition`] trait, and pass the struct to
+/// [`platform::set_surface_definition`](crate::platform::set_surface_definition)
+/// before calling [`Renderer::create`](crate::renderer::Renderer::create) or
+/// [`App::new`](crate::app::App::new).
 pub struct Surface {
     lib: Arc<Library>,
     internal: ul_sys::ULSurface,
@@ -184,7 +183,101 @@ impl Surface {
         }
     }
 
-    //pub fn user_data(&self) -> *mut std::ffi::c_void {
-    //    unsafe { ul_sys::ulSurfaceGetUserData(self.internal) }
-    //}
+    /// Get the user data of the surface.
+    ///
+    /// When a custom [`SurfaceDefinition`] is set, this is the id returned by
+    /// [`SurfaceDefinition::create`], see [`Surface::id`].
+    pub fn user_data(&self) -> *mut c_void {
+        unsafe { self.lib.ultralight().ulSurfaceGetUserData(self.internal) }
+    }
+
+    /// Get the id of the surface given by [`SurfaceDefinition::create`].
+    ///
+    /// This is only meaningful when a custom [`SurfaceDefinition`] is set.
+    pub fn id(&self) -> u32 {
+        self.user_data() as usize as u32
+    }
+}
+
+/// User-defined Surface implementation.
+///
+/// Each surface is identified by an id returned from [`create`](SurfaceDefinition::create),
+/// which is passed to all the other methods, and can later be retrieved from a
+/// [`Surface`] with [`Surface::id`].
+///
+/// The pixel buffer is expected to be in premultiplied BGRA 32-bit format,
+/// the renderer always paints in this format.
+///
+/// To use it, pass an instance of your struct to
+/// [`platform::set_surface_definition`](crate::platform::set_surface_definition)
+/// before calling [`Renderer::create`](crate::renderer::Renderer::create).
+///
+/// # Safety
+///
+/// The pointer returned by [`lock_pixels`](SurfaceDefinition::lock_pixels) must point to
+/// at least [`get_size`](SurfaceDefinition::get_size) writable bytes, and stay valid until
+/// [`unlock_pixels`](SurfaceDefinition::unlock_pixels) is called for the same surface.
+pub unsafe trait SurfaceDefinition {
+    /// Create a new surface of `width` x `height` pixels, and return its id.
+    fn create(&mut self, width: u32, height: u32) -> u32;
+
+    /// Destroy the surface with the given id.
+    fn destroy(&mut self, id: u32);
+
+    /// Get the width (in pixels) of the surface.
+    fn get_width(&mut self, id: u32) -> u32;
+
+    /// Get the height (in pixels) of the surface.
+    fn get_height(&mut self, id: u32) -> u32;
+
+    /// Get the number of bytes between each row of pixels, usually `width * 4`.
+    fn get_row_bytes(&mut self, id: u32) -> u32;
+
+    /// Get the size in bytes of the pixel buffer, `row_bytes * height`.
+    fn get_size(&mut self, id: u32) -> usize;
+
+    /// Lock the pixel buffer for reading/writing and return a pointer to it.
+    fn lock_pixels(&mut self, id: u32) -> *mut u8;
+
+    /// Unlock the pixel buffer after a previous call to
+    /// [`lock_pixels`](SurfaceDefinition::lock_pixels).
+    fn unlock_pixels(&mut self, id: u32);
+
+    /// Resize the surface to `width` x `height` pixels.
+    fn resize(&mut self, id: u32, width: u32, height: u32);
+}
+
+platform_set_interface_macro! {
+    #[inline]
+    pub(crate) set_surface_definition<SurfaceDefinition>(lib, surface_definition -> SURFACE_DEFINITION) -> ulPlatformSetSurfaceDefinition(ULSurfaceDefinition) {
+        create((width: u32, height: u32) -> *mut c_void) -> ((width: u32, height: u32) -> id: u32) {} {
+            id as usize as *mut c_void
+        }
+        destroy((user_data: *mut c_void)) -> ((id: u32)) {
+            let id = user_data as usize as u32;
+        }
+        get_width((user_data: *mut c_void) -> u32) -> ((id: u32)) {
+            let id = user_data as usize as u32;
+        }
+        get_height((user_data: *mut c_void) -> u32) -> ((id: u32)) {
+            let id = user_data as usize as u32;
+        }
+        get_row_bytes((user_data: *mut c_void) -> u32) -> ((id: u32)) {
+            let id = user_data as usize as u32;
+        }
+        get_size((user_data: *mut c_void) -> usize) -> ((id: u32)) {
+            let id = user_data as usize as u32;
+        }
+        lock_pixels((user_data: *mut c_void) -> *mut c_void) -> ((id: u32) -> pixels: *mut u8) {
+            let id = user_data as usize as u32;
+        } {
+            pixels as *mut c_void
+        }
+        unlock_pixels((user_data: *mut c_void)) -> ((id: u32)) {
+            let id = user_data as usize as u32;
+        }
+        resize((user_data: *mut c_void, width: u32, height: u32)) -> ((id: u32, width: u32, height: u32)) {
+            let id = user_data as usize as u32;
+        }
+    }
 }
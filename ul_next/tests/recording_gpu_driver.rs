@@ -0,0 +1,335 @@
+use ul_next::{
+    gpu_driver::{
+        rasterizer::CpuRasterizer,
+        recording::{GpuTrace, GpuTraceEvent, RecordingGpuDriver},
+        GpuCommand, GpuDriver, GpuState, IndexBuffer, RenderBuffer, ShaderType, VertexBuffer,
+        VertexBufferFormat,
+    },
+    rect::Rect,
+};
+
+const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0, //
+    0.0, 1.0, 0.0, 0.0, //
+    0.0, 0.0, 1.0, 0.0, //
+    0.0, 0.0, 0.0, 1.0,
+];
+
+fn path_vertices(points: &[(f32, f32)], color: [u8; 4]) -> VertexBuffer {
+    let mut buffer = Vec::new();
+    for &(x, y) in points {
+        buffer.extend_from_slice(&x.to_ne_bytes());
+        buffer.extend_from_slice(&y.to_ne_bytes());
+        buffer.extend_from_slice(&color);
+        buffer.extend_from_slice(&[0; 8]);
+    }
+    VertexBuffer {
+        format: VertexBufferFormat::Format_2f_4ub_2f,
+        buffer,
+    }
+}
+
+fn quad_vertices(points: &[(f32, f32)], color: [u8; 4], fill_type: f32) -> VertexBuffer {
+    let mut buffer = Vec::new();
+    for &(x, y) in points {
+        buffer.extend_from_slice(&x.to_ne_bytes());
+        buffer.extend_from_slice(&y.to_ne_bytes());
+        buffer.extend_from_slice(&color);
+        buffer.extend_from_slice(&[0; 16]);
+        buffer.extend_from_slice(&fill_type.to_ne_bytes());
+        buffer.extend_from_slice(&[0; 108]);
+    }
+    VertexBuffer {
+        format: VertexBufferFormat::Format_2f_4ub_2f_2f_28f,
+        buffer,
+    }
+}
+
+// two triangles covering `(left, top)` to `(right, bottom)`
+fn rect_points(left: f32, top: f32, right: f32, bottom: f32) -> [(f32, f32); 4] {
+    [(left, top), (right, top), (right, bottom), (left, bottom)]
+}
+
+fn rect_indices() -> IndexBuffer {
+    IndexBuffer {
+        buffer: vec![0, 1, 2, 0, 2, 3],
+    }
+}
+
+fn state(render_buffer_id: u32, shader_type: ShaderType) -> GpuState {
+    GpuState {
+        viewport_width: 8,
+        viewport_height: 8,
+        transform: IDENTITY,
+        enable_texturing: false,
+        enable_blend: true,
+        shader_type,
+        render_buffer_id,
+        texture_1_id: None,
+        texture_2_id: None,
+        texture_3_id: None,
+        uniform_scalar: [0.0; 8],
+        uniform_vector: [[0.0; 4]; 8],
+        clip_size: 0,
+        clip: [[[0.0; 4]; 4]; 8],
+        enable_scissor: false,
+        scissor_rect: Rect {
+            left: 0,
+            top: 0,
+            right: 0,
+            bottom: 0,
+        },
+    }
+}
+
+fn draw(gpu_state: GpuState, geometry_id: u32) -> GpuCommand {
+    GpuCommand::DrawGeometry {
+        gpu_state: Box::new(gpu_state),
+        geometry_id,
+        indices_offset: 0,
+        indices_count: 6,
+    }
+}
+
+/// Creates an 8x8 render buffer, returns its id.
+fn create_target(driver: &mut dyn GpuDriver) -> u32 {
+    let texture_id = driver.next_texture_id();
+    let render_buffer_id = driver.next_render_buffer_id();
+    driver.create_render_buffer(
+        render_buffer_id,
+        RenderBuffer {
+            texture_id,
+            width: 8,
+            height: 8,
+            has_stencil_buffer: false,
+            has_depth_buffer: false,
+        },
+    );
+    render_buffer_id
+}
+
+#[test]
+fn rasterizer_fills_solid_rect() {
+    let mut rasterizer = CpuRasterizer::new();
+    let target = create_target(&mut rasterizer);
+    let geometry = rasterizer.next_geometry_id();
+    rasterizer.create_geometry(
+        geometry,
+        path_vertices(&rect_points(2.0, 2.0, 6.0, 5.0), [255, 0, 0, 255]),
+        rect_indices(),
+    );
+    rasterizer.update_command_list(vec![
+        GpuCommand::ClearRenderBuffer {
+            render_buffer_id: target,
+        },
+        draw(state(target, ShaderType::FillPath), geometry),
+    ]);
+
+    let buffer = rasterizer.texture(1).unwrap();
+    let filled = (0..8)
+        .flat_map(|y| (0..8).map(move |x| (x, y)))
+        .filter(|&(x, y)| buffer.pixel(x, y) == Some([255, 0, 0, 255]))
+        .count();
+    // every pixel is drawn once, shared edges included
+    assert_eq!(filled, 4 * 3);
+    assert_eq!(buffer.pixel(2, 2), Some([255, 0, 0, 255]));
+    assert_eq!(buffer.pixel(5, 4), Some([255, 0, 0, 255]));
+    assert_eq!(buffer.pixel(6, 4), Some([0, 0, 0, 0]));
+    assert_eq!(buffer.pixel(1, 2), Some([0, 0, 0, 0]));
+    assert_eq!(rasterizer.skipped_draws(), 0);
+}
+
+#[test]
+fn rasterizer_blends_and_scissors() {
+    let mut rasterizer = CpuRasterizer::new();
+    let target = create_target(&mut rasterizer);
+    let background = rasterizer.next_geometry_id();
+    rasterizer.create_geometry(
+        background,
+        quad_vertices(&rect_points(0.0, 0.0, 8.0, 8.0), [0, 0, 255, 255], 0.0),
+        rect_indices(),
+    );
+    // half transparent white, premultiplied
+    let overlay = rasterizer.next_geometry_id();
+    rasterizer.create_geometry(
+        overlay,
+        quad_vertices(&rect_points(0.0, 0.0, 8.0, 8.0), [128, 128, 128, 128], 0.0),
+        rect_indices(),
+    );
+
+    let mut scissored = state(target, ShaderType::Fill);
+    scissored.enable_scissor = true;
+    scissored.scissor_rect = Rect {
+        left: 0,
+        top: 0,
+        right: 4,
+        bottom: 8,
+    };
+    rasterizer.update_command_list(vec![
+        draw(state(target, ShaderType::Fill), background),
+        draw(scissored, overlay),
+    ]);
+
+    let buffer = rasterizer.render_buffer(target).unwrap();
+    assert_eq!(buffer.pixel(0, 0), Some([128, 128, 255, 255]));
+    assert_eq!(buffer.pixel(3, 7), Some([128, 128, 255, 255]));
+    assert_eq!(buffer.pixel(4, 0), Some([0, 0, 255, 255]));
+}
+
+#[test]
+fn rasterizer_skips_unsupported_fills() {
+    let mut rasterizer = CpuRasterizer::new();
+    let target = create_target(&mut rasterizer);
+    let image = rasterizer.next_geometry_id();
+    rasterizer.create_geometry(
+        image,
+        quad_vertices(&rect_points(0.0, 0.0, 8.0, 8.0), [255, 255, 255, 255], 1.0),
+        rect_indices(),
+    );
+    let solid = rasterizer.next_geometry_id();
+    rasterizer.create_geometry(
+        solid,
+        path_vertices(&rect_points(0.0, 0.0, 8.0, 8.0), [255, 255, 255, 255]),
+        rect_indices(),
+    );
+
+    let mut clipped = state(target, ShaderType::FillPath);
+    clipped.clip_size = 1;
+    rasterizer.update_command_list(vec![
+        draw(state(target, ShaderType::Fill), image),
+        draw(clipped, solid),
+        // missing geometry
+        draw(state(target, ShaderType::FillPath), 42),
+    ]);
+
+    assert_eq!(rasterizer.skipped_draws(), 3);
+    let buffer = rasterizer.render_buffer(target).unwrap();
+    assert!(buffer.pixels().iter().all(|&byte| byte == 0));
+}
+
+#[test]
+fn recorded_trace_replays_into_rasterizer() {
+    let mut recorder = RecordingGpuDriver::new();
+    let trace = recorder.trace_handle();
+
+    let mut direct = CpuRasterizer::new();
+    // the same calls, made on both drivers
+    let drivers: [&mut dyn GpuDriver; 2] = [&mut recorder, &mut direct];
+    for driver in drivers {
+        driver.begin_synchronize();
+        let target = create_target(driver);
+        let geometry = driver.next_geometry_id();
+        driver.create_geometry(
+            geometry,
+            path_vertices(&rect_points(1.0, 1.0, 7.0, 3.0), [0, 255, 0, 255]),
+            rect_indices(),
+        );
+        driver.end_synchronize();
+        driver.update_command_list(vec![
+            GpuCommand::ClearRenderBuffer {
+                render_buffer_id: target,
+            },
+            draw(state(target, ShaderType::FillPath), geometry),
+        ]);
+    }
+
+    let recorded = trace.snapshot();
+    assert!(matches!(
+        recorded.events[..],
+        [
+            GpuTraceEvent::BeginSynchronize,
+            GpuTraceEvent::CreateRenderBuffer {
+                render_buffer_id: 1,
+                ..
+            },
+            GpuTraceEvent::CreateGeometry { geometry_id: 1, .. },
+            GpuTraceEvent::EndSynchronize,
+            GpuTraceEvent::UpdateCommandList { .. },
+        ]
+    ));
+    assert_eq!(recorded.commands().count(), 2);
+
+    let mut replayed = CpuRasterizer::new();
+    trace.take().replay(&mut replayed);
+    assert!(trace.snapshot().events.is_empty());
+
+    let expected = direct.render_buffer(1).unwrap();
+    assert_eq!(replayed.render_buffer(1), Some(expected));
+    assert_eq!(expected.pixel(4, 2), Some([0, 255, 0, 255]));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn trace_survives_serialization() {
+    let mut recorder = RecordingGpuDriver::new();
+    let trace = recorder.trace_handle();
+
+    recorder.begin_synchronize();
+    let target = create_target(&mut recorder);
+    let geometry = recorder.next_geometry_id();
+    recorder.create_geometry(
+        geometry,
+        path_vertices(&rect_points(2.0, 2.0, 6.0, 6.0), [255, 0, 0, 255]),
+        rect_indices(),
+    );
+    recorder.end_synchronize();
+    recorder.update_command_list(vec![
+        GpuCommand::ClearRenderBuffer {
+            render_buffer_id: target,
+        },
+        draw(state(target, ShaderType::FillPath), geometry),
+    ]);
+
+    let recorded = trace.snapshot();
+    let json = serde_json::to_string(&recorded).unwrap();
+    let restored: GpuTrace = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+
+    let mut expected = CpuRasterizer::new();
+    recorded.replay(&mut expected);
+    let mut replayed = CpuRasterizer::new();
+    restored.replay(&mut replayed);
+    assert_eq!(
+        replayed.render_buffer(target),
+        expected.render_buffer(target)
+    );
+    assert_eq!(
+        replayed.render_buffer(target).unwrap().pixel(4, 4),
+        Some([255, 0, 0, 255])
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn deserialized_bitmaps_are_checked() {
+    fn texture(bitmap: serde_json::Value) -> Result<GpuTrace, serde_json::Error> {
+        serde_json::from_value(serde_json::json!({
+            "events": [{ "CreateTexture": { "texture_id": 1, "bitmap": bitmap } }]
+        }))
+    }
+
+    let bitmap = serde_json::json!({
+        "width": 2,
+        "height": 2,
+        "format": "Bgra8UnormSrgb",
+        "bpp": 4,
+        "row_bytes": 8,
+        "bytes_size": 16,
+        "pixels": vec![0u8; 16],
+        "is_empty": false,
+    });
+    assert!(texture(bitmap.clone()).is_ok());
+
+    let broken = [
+        ("pixels", serde_json::json!(vec![0u8; 12])),
+        ("bpp", serde_json::json!(1)),
+        ("row_bytes", serde_json::json!(4)),
+        ("bytes_size", serde_json::json!(32)),
+        ("is_empty", serde_json::json!(true)),
+    ];
+    for (field, value) in broken {
+        let mut bitmap = bitmap.clone();
+        bitmap[field] = value;
+        assert!(texture(bitmap).is_err(), "accepted a bad `{}`", field);
+    }
+}